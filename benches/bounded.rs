@@ -1,10 +1,11 @@
 use std::fmt::Debug;
-use std::sync::{Arc, Barrier};
+use std::sync::Arc;
 use std::thread;
 use std::time::Instant;
 
 use bus;
 use fremkit::bounded::Log;
+use fremkit::StartGate;
 
 use criterion::measurement::WallTime;
 use criterion::{
@@ -228,14 +229,14 @@ fn multi_thread_concurrent_write<T: Item, C: Chan<T>>(
             let c = C::new(iters as usize * n_threads);
 
             let mut threads = Vec::with_capacity(n_threads);
-            let barrier = Arc::new(Barrier::new(n_threads + 1));
+            let gate = Arc::new(StartGate::new(n_threads));
 
             for _ in 0..n_threads {
-                let b = barrier.clone();
+                let g = gate.clone();
                 let mut tx = c.clone();
 
                 let thread = thread::spawn(move || {
-                    b.wait();
+                    g.wait();
 
                     for _ in 0..iters {
                         tx.write(T::default());
@@ -245,8 +246,7 @@ fn multi_thread_concurrent_write<T: Item, C: Chan<T>>(
                 threads.push(thread);
             }
 
-            let start = Instant::now();
-            barrier.wait();
+            let start = gate.open();
 
             for thread in threads {
                 thread.join().unwrap();
@@ -267,7 +267,7 @@ fn multi_thread_concurrent_mixio<T: Item, C: Chan<T>>(
             let c = C::new(iters as usize * n_threads * 2);
 
             let mut threads = Vec::with_capacity(n_threads * 2);
-            let barrier = Arc::new(Barrier::new(n_threads * 2 + 1));
+            let gate = Arc::new(StartGate::new(n_threads * 2));
 
             for _ in 0..n_threads {
                 let mut tx = c.clone();
@@ -275,9 +275,9 @@ fn multi_thread_concurrent_mixio<T: Item, C: Chan<T>>(
 
                 // Writer Thread
 
-                let b = barrier.clone();
+                let g = gate.clone();
                 let thread = thread::spawn(move || {
-                    b.wait();
+                    g.wait();
 
                     for _ in 0..iters {
                         tx.write(T::default());
@@ -288,9 +288,9 @@ fn multi_thread_concurrent_mixio<T: Item, C: Chan<T>>(
 
                 // Reader Thread
 
-                let b = barrier.clone();
+                let g = gate.clone();
                 let thread = thread::spawn(move || {
-                    b.wait();
+                    g.wait();
 
                     for i in 0..(iters as usize) {
                         rx.read(i);
@@ -300,8 +300,7 @@ fn multi_thread_concurrent_mixio<T: Item, C: Chan<T>>(
                 threads.push(thread);
             }
 
-            let start = Instant::now();
-            barrier.wait();
+            let start = gate.open();
 
             for thread in threads {
                 thread.join().unwrap();
@@ -1,12 +1,11 @@
 use std::collections::HashMap;
 use std::io::stdin;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Barrier};
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
 use fremkit::bounded::Log;
-use fremkit::LogError;
+use fremkit::{LogError, StartGate};
 
 const THREADS: usize = 8;
 
@@ -17,23 +16,21 @@ pub fn main() {
     // Setting up threads
     let mut threads: Vec<thread::JoinHandle<Result<(), LogError<u64>>>> =
         Vec::with_capacity(THREADS);
-    let barrier = Arc::new(Barrier::new(THREADS + 1));
-    let alarm = Arc::new(AtomicBool::new(false));
+    let gate = Arc::new(StartGate::new(THREADS));
 
     for id in 0..THREADS {
-        let b = barrier.clone();
+        let g = gate.clone();
         let lg = log.clone();
-        let alr = alarm.clone();
 
         // Each thread will try to push as many items as possible
         // into the channel before the timer stops.
         let thread = thread::spawn(move || {
-            b.wait();
+            g.wait();
 
             loop {
                 lg.push(id as u64)?;
 
-                if alr.load(Ordering::Relaxed) {
+                if g.is_stopped() {
                     break;
                 }
             }
@@ -51,13 +48,12 @@ pub fn main() {
 
     println!("> GO!");
 
-    let start = std::time::Instant::now();
-    barrier.wait();
+    let start = gate.open();
 
     thread::sleep(Duration::from_secs(1));
 
     // Ring the bell!
-    alarm.store(true, Ordering::Relaxed);
+    gate.stop();
 
     let elapsed = start.elapsed();
     println!("> Elapsed: {:?}s", elapsed.as_secs_f32());
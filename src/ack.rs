@@ -0,0 +1,210 @@
+//! This module contains the implementation of the `AckQueue` type, an at-least-once work queue.
+
+use crate::bounded::Log;
+use crate::replay::Append;
+use crate::unbounded::Channel;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Claims made on an AckQueue.
+#[derive(Debug)]
+struct Claims {
+    /// Next index which has never been claimed.
+    next: usize,
+    /// Claimed indices which have not been acknowledged, and when they become visible again.
+    in_flight: BTreeMap<usize, Instant>,
+}
+
+/// Work queue sharing the items of a Log or Channel between workers, with at-least-once delivery.
+///
+/// Unlike a `WorkQueueReceiver`, claiming an item is not final: a worker must `ack` the item once
+/// it has been processed. An item which has not been acknowledged within the visibility timeout,
+/// because its worker crashed or gave up with `nack`, is delivered again to the next worker.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use fremkit::unbounded::Channel;
+///
+/// let channel: Channel<u64> = Channel::new();
+/// channel.push(1).unwrap();
+/// channel.push(2).unwrap();
+///
+/// let queue = channel.ack_queue(Duration::from_secs(30));
+///
+/// let (first, _) = queue.try_recv().unwrap();
+/// let (second, _) = queue.try_recv().unwrap();
+///
+/// queue.ack(first);
+/// queue.nack(second);
+///
+/// assert_eq!(queue.try_recv(), Some((second, &2)));
+/// ```
+pub struct AckQueue<'a, T, A: ?Sized> {
+    target: &'a A,
+    visibility: Duration,
+    claims: Mutex<Claims>,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<'a, T, A: Append<T> + ?Sized> AckQueue<'a, T, A> {
+    /// Create a new AckQueue over a Log or Channel.
+    ///
+    /// # Arguments
+    /// * `target` - The Log or Channel to read from.
+    /// * `visibility` - How long a claimed item stays hidden from other workers.
+    pub fn new(target: &'a A, visibility: Duration) -> Self {
+        Self {
+            target,
+            visibility,
+            claims: Mutex::new(Claims {
+                next: 0,
+                in_flight: BTreeMap::new(),
+            }),
+            _item: PhantomData,
+        }
+    }
+
+    /// Claim an item, if one is available.
+    ///
+    /// Items whose visibility timeout expired are delivered again first, in index order.
+    ///
+    /// # Returns
+    /// The index and the item, or `None` if every available item is claimed.
+    pub fn try_recv(&self) -> Option<(usize, &'a T)> {
+        let mut claims = self.claims.lock();
+        let now = Instant::now();
+
+        let expired = claims
+            .in_flight
+            .iter()
+            .find(|(_, &visible_at)| visible_at <= now)
+            .map(|(&index, _)| index);
+
+        let index = match expired {
+            Some(index) => index,
+            None => {
+                let index = claims.next;
+                self.target.lookup(index)?;
+
+                claims.next += 1;
+                index
+            }
+        };
+
+        claims.in_flight.insert(index, now + self.visibility);
+
+        self.target.lookup(index).map(|item| (index, item))
+    }
+
+    /// Acknowledge a claimed item, so it is never delivered again.
+    ///
+    /// # Returns
+    /// `false` if the item was not in flight.
+    pub fn ack(&self, index: usize) -> bool {
+        self.claims.lock().in_flight.remove(&index).is_some()
+    }
+
+    /// Give up on a claimed item, so it is delivered again right away.
+    ///
+    /// # Returns
+    /// `false` if the item was not in flight.
+    pub fn nack(&self, index: usize) -> bool {
+        match self.claims.lock().in_flight.get_mut(&index) {
+            Some(visible_at) => {
+                *visible_at = Instant::now();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the number of items claimed and not acknowledged yet.
+    pub fn in_flight(&self) -> usize {
+        self.claims.lock().in_flight.len()
+    }
+}
+
+impl<'a, T, A: ?Sized> fmt::Debug for AckQueue<'a, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AckQueue")
+            .field("visibility", &self.visibility)
+            .field("claims", &*self.claims.lock())
+            .finish()
+    }
+}
+
+impl<T> Log<T> {
+    /// Create a work queue with at-least-once delivery over the log.
+    ///
+    /// # Arguments
+    /// * `visibility` - How long a claimed item stays hidden from other workers.
+    pub fn ack_queue(&self, visibility: Duration) -> AckQueue<'_, T, Self> {
+        AckQueue::new(self, visibility)
+    }
+}
+
+impl<T> Channel<T> {
+    /// Create a work queue with at-least-once delivery over the channel.
+    ///
+    /// # Arguments
+    /// * `visibility` - How long a claimed item stays hidden from other workers.
+    pub fn ack_queue(&self, visibility: Duration) -> AckQueue<'_, T, Self> {
+        AckQueue::new(self, visibility)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_ack_queue_redelivery() {
+        init();
+
+        let log = Log::new(10);
+        for i in 0..10 {
+            log.push(i).unwrap();
+        }
+
+        let queue = log.ack_queue(Duration::from_millis(20));
+
+        // A crashed worker claims items and never acknowledges them.
+        let (lost, _) = queue.try_recv().unwrap();
+        assert_eq!(queue.in_flight(), 1);
+
+        thread::sleep(Duration::from_millis(30));
+
+        let delivered = Mutex::new(HashSet::new());
+        thread::scope(|s| {
+            for _ in 0..3 {
+                let (queue, delivered) = (&queue, &delivered);
+                s.spawn(move || {
+                    while let Some((index, &item)) = queue.try_recv() {
+                        assert_eq!(index, item);
+                        assert!(queue.ack(index));
+                        delivered.lock().insert(index);
+                    }
+                });
+            }
+        });
+
+        assert!(delivered.lock().contains(&lost));
+        assert_eq!(delivered.lock().len(), 10);
+        assert_eq!(queue.in_flight(), 0);
+        assert!(!queue.ack(lost));
+    }
+}
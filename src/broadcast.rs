@@ -0,0 +1,456 @@
+//! This module contains the implementation of the `Broadcast` type, and its slow-consumer policies.
+
+use crate::sync::{AtomicBool, AtomicUsize, Ordering};
+use crate::unbounded::Channel;
+use crate::Notifier;
+use crate::{LogError, RecvError};
+
+use std::fmt;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+/// What a Broadcast does when a subscriber falls too far behind the producers.
+pub enum LagPolicy {
+    /// Call the function with the id and the lag of the subscriber, and keep going.
+    Warn(Box<dyn Fn(usize, usize) + Send + Sync>),
+    /// Unregister the subscriber. Its next read returns a `Lagged` error.
+    Drop,
+    /// Block producers until the subscriber catches up.
+    Block,
+}
+
+impl fmt::Debug for LagPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LagPolicy::Warn(_) => f.write_str("Warn"),
+            LagPolicy::Drop => f.write_str("Drop"),
+            LagPolicy::Block => f.write_str("Block"),
+        }
+    }
+}
+
+/// Position of a registered subscriber.
+#[derive(Debug)]
+struct Subscription {
+    id: usize,
+    position: AtomicUsize,
+    dropped: AtomicBool,
+    /// Flow control window of the subscriber, if it has one.
+    window: Option<usize>,
+    /// Index up to which the subscriber replenished its credit.
+    processed: AtomicUsize,
+}
+
+impl Subscription {
+    /// Get the number of items which can still be pushed before the window of the subscriber is
+    /// exhausted, if it has one.
+    fn credit(&self, len: usize) -> Option<usize> {
+        let window = self.window?;
+
+        Some((self.processed.load(Ordering::Acquire) + window).saturating_sub(len))
+    }
+}
+
+/// A Channel which keeps track of its subscribers, and applies a policy to the slow ones.
+///
+/// Every push checks the lag of each subscriber: the number of items pushed which it has not read
+/// yet. Once a lag reaches `max_lag`, the `LagPolicy` is applied. Checking the subscribers takes a
+/// lock, so a Broadcast trades some push throughput for bounded consumer lag.
+///
+/// # Examples
+/// ```
+/// use fremkit::{Broadcast, LagPolicy, RecvError};
+///
+/// let broadcast = Broadcast::new(2, LagPolicy::Drop);
+/// let mut slow = broadcast.subscribe();
+///
+/// broadcast.push(1).unwrap();
+/// broadcast.push(2).unwrap();
+/// assert_eq!(broadcast.lags(), vec![(0, 2)]);
+///
+/// broadcast.push(3).unwrap();
+/// assert_eq!(slow.try_recv(), Err(RecvError::Lagged(0)));
+/// ```
+pub struct Broadcast<T> {
+    channel: Channel<T>,
+    max_lag: usize,
+    policy: LagPolicy,
+    subscriptions: Mutex<Vec<Arc<Subscription>>>,
+    next_id: AtomicUsize,
+    notifier: Notifier,
+}
+
+impl<T> Broadcast<T> {
+    /// Create a new Broadcast.
+    ///
+    /// # Arguments
+    /// * `max_lag` - The lag at which the policy is applied to a subscriber, at least 1.
+    /// * `policy` - What to do with subscribers reaching `max_lag`.
+    pub fn new(max_lag: usize, policy: LagPolicy) -> Self {
+        Self {
+            channel: Channel::new(),
+            max_lag: max_lag.max(1),
+            policy,
+            subscriptions: Mutex::new(Vec::new()),
+            next_id: AtomicUsize::new(0),
+            notifier: Notifier::new(),
+        }
+    }
+
+    /// Get the Channel backing the broadcast.
+    #[inline]
+    pub fn channel(&self) -> &Channel<T> {
+        &self.channel
+    }
+
+    /// Register a new subscriber.
+    ///
+    /// The subscriber starts at the current end of the channel: it only receives items pushed after
+    /// it subscribed.
+    pub fn subscribe(&self) -> BroadcastReceiver<'_, T> {
+        self.register(None)
+    }
+
+    /// Register a new subscriber with a flow control window.
+    ///
+    /// The subscriber grants `window` credits to the producers. Every item pushed uses one credit,
+    /// and the subscriber gives them back with `BroadcastReceiver::replenish` once it has processed
+    /// the items. Producers are not blocked when credits run out: they check `credit`, or wait
+    /// with `wait_for_credit`, to slow down. Lag policies still apply to the subscriber.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::{Broadcast, LagPolicy};
+    ///
+    /// let broadcast = Broadcast::new(usize::MAX, LagPolicy::Drop);
+    /// let mut receiver = broadcast.subscribe_with_window(2);
+    ///
+    /// broadcast.push(1).unwrap();
+    /// broadcast.push(2).unwrap();
+    /// assert_eq!(broadcast.credit(), Some(0));
+    ///
+    /// // Reading an item does not give its credit back, processing it does.
+    /// receiver.recv().unwrap();
+    /// assert_eq!(broadcast.credit(), Some(0));
+    ///
+    /// receiver.replenish(1);
+    /// assert_eq!(broadcast.credit(), Some(1));
+    /// ```
+    pub fn subscribe_with_window(&self, window: usize) -> BroadcastReceiver<'_, T> {
+        self.register(Some(window))
+    }
+
+    fn register(&self, window: Option<usize>) -> BroadcastReceiver<'_, T> {
+        let position = self.channel.len();
+        let subscription = Arc::new(Subscription {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            position: AtomicUsize::new(position),
+            dropped: AtomicBool::new(false),
+            window,
+            processed: AtomicUsize::new(position),
+        });
+
+        self.subscriptions.lock().push(subscription.clone());
+
+        BroadcastReceiver {
+            subscription,
+            broadcast: self,
+        }
+    }
+
+    /// Get the lag of every registered subscriber, as `(id, lag)` pairs.
+    pub fn lags(&self) -> Vec<(usize, usize)> {
+        let len = self.channel.len();
+
+        self.subscriptions
+            .lock()
+            .iter()
+            .map(|s| (s.id, len.saturating_sub(s.position.load(Ordering::Acquire))))
+            .collect()
+    }
+
+    /// Get the number of items which can be pushed before a subscriber runs out of credit.
+    ///
+    /// # Returns
+    /// The lowest credit of the subscribers with a flow control window, or `None` if there is none.
+    pub fn credit(&self) -> Option<usize> {
+        let len = self.channel.len();
+
+        self.subscriptions
+            .lock()
+            .iter()
+            .filter_map(|s| s.credit(len))
+            .min()
+    }
+
+    /// Block until every subscriber with a flow control window has some credit left.
+    pub fn wait_for_credit(&self) {
+        self.notifier.wait_while(|| self.credit() == Some(0));
+    }
+
+    /// Append an item to the channel, after applying the policy to the subscribers lagging behind.
+    ///
+    /// # Returns
+    /// The index of the item in the channel, or an error containing the item if it was rejected or
+    /// the channel is closed.
+    pub fn push(&self, value: T) -> Result<usize, LogError<T>> {
+        match &self.policy {
+            LagPolicy::Warn(warn) => {
+                for (id, lag) in self.lags() {
+                    if lag >= self.max_lag {
+                        warn(id, lag);
+                    }
+                }
+            }
+            LagPolicy::Drop => {
+                let len = self.channel.len();
+
+                self.subscriptions.lock().retain(|s| {
+                    let lagging =
+                        len.saturating_sub(s.position.load(Ordering::Acquire)) >= self.max_lag;
+
+                    if lagging {
+                        s.dropped.store(true, Ordering::Release);
+                    }
+
+                    !lagging
+                });
+            }
+            LagPolicy::Block => {
+                self.notifier
+                    .wait_while(|| self.lags().iter().any(|&(_, lag)| lag >= self.max_lag));
+            }
+        }
+
+        self.channel.push(value)
+    }
+
+    /// Unregister a subscriber, and wake up producers which might be waiting for it.
+    fn unsubscribe(&self, subscription: &Arc<Subscription>) {
+        self.subscriptions
+            .lock()
+            .retain(|s| !Arc::ptr_eq(s, subscription));
+
+        self.notifier.notify();
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Broadcast<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Broadcast")
+            .field("channel", &self.channel)
+            .field("max_lag", &self.max_lag)
+            .field("policy", &self.policy)
+            .field("subscribers", &self.subscriptions.lock().len())
+            .finish()
+    }
+}
+
+/// A registered subscriber of a Broadcast.
+///
+/// Dropping the receiver unregisters it.
+#[derive(Debug)]
+pub struct BroadcastReceiver<'a, T> {
+    subscription: Arc<Subscription>,
+    broadcast: &'a Broadcast<T>,
+}
+
+impl<'a, T> BroadcastReceiver<'a, T> {
+    /// Get the id of the subscriber.
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.subscription.id
+    }
+
+    /// Get the index of the next item to be read.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.subscription.position.load(Ordering::Relaxed)
+    }
+
+    /// Read the next item, if it is available.
+    ///
+    /// # Returns
+    /// The next item, an `Empty` error if it is not available yet, or a `Lagged` error if the
+    /// subscriber has been dropped for lagging behind.
+    pub fn try_recv(&mut self) -> Result<&'a T, RecvError> {
+        self.check()?;
+
+        let item = self
+            .broadcast
+            .channel
+            .get(self.position())
+            .ok_or(RecvError::Empty)?;
+        self.advance();
+
+        Ok(item)
+    }
+
+    /// Read the next item, blocking until it becomes available.
+    ///
+    /// # Returns
+    /// The next item, a `Lagged` error if the subscriber has been dropped for lagging behind, or a
+    /// `Closed` error once the channel is closed and every item has been read.
+    pub fn recv(&mut self) -> Result<&'a T, RecvError> {
+        self.check()?;
+
+        let position = self.position();
+        let item = self
+            .broadcast
+            .channel
+            .wait_for(position)
+            .ok_or(RecvError::Closed(position))?;
+        self.advance();
+
+        Ok(item)
+    }
+
+    /// Give back the credits of processed items to the producers.
+    ///
+    /// Credits can only be given back for items which have been read: `n` is capped accordingly.
+    /// This does nothing if the subscriber has no flow control window.
+    ///
+    /// # Arguments
+    /// * `n` - The number of items processed since the last call.
+    pub fn replenish(&self, n: usize) {
+        if self.subscription.window.is_none() {
+            return;
+        }
+
+        let position = self.position();
+        let _ = self.subscription.processed.fetch_update(
+            Ordering::AcqRel,
+            Ordering::Acquire,
+            |processed| Some(processed.saturating_add(n).min(position)),
+        );
+
+        self.broadcast.notifier.notify();
+    }
+
+    fn check(&self) -> Result<(), RecvError> {
+        if self.subscription.dropped.load(Ordering::Acquire) {
+            return Err(RecvError::Lagged(self.subscription.id));
+        }
+
+        Ok(())
+    }
+
+    fn advance(&self) {
+        self.subscription.position.fetch_add(1, Ordering::Release);
+        self.broadcast.notifier.notify();
+    }
+}
+
+impl<'a, T> Drop for BroadcastReceiver<'a, T> {
+    fn drop(&mut self) {
+        self.broadcast.unsubscribe(&self.subscription);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::AtomicUsize as StdAtomicUsize;
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_broadcast_warn() {
+        init();
+
+        let warnings = Arc::new(StdAtomicUsize::new(0));
+        let w = warnings.clone();
+
+        let broadcast = Broadcast::new(
+            2,
+            LagPolicy::Warn(Box::new(move |_, _| {
+                w.fetch_add(1, Ordering::Relaxed);
+            })),
+        );
+        let mut receiver = broadcast.subscribe();
+
+        for i in 0..4 {
+            broadcast.push(i).unwrap();
+        }
+
+        // The third and fourth pushes found the subscriber lagging by 2 and 3 items.
+        assert_eq!(warnings.load(Ordering::Relaxed), 2);
+        assert_eq!(receiver.recv().unwrap(), &0);
+        assert_eq!(broadcast.lags(), vec![(0, 3)]);
+    }
+
+    #[test]
+    fn test_broadcast_block() {
+        init();
+
+        let broadcast = Broadcast::new(1, LagPolicy::Block);
+        let mut receiver = broadcast.subscribe();
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 0..10 {
+                    broadcast.push(i).unwrap();
+                }
+            });
+
+            for i in 0..10 {
+                assert_eq!(receiver.recv().unwrap(), &i);
+            }
+        });
+
+        assert_eq!(receiver.try_recv(), Err(RecvError::Empty));
+
+        drop(receiver);
+
+        assert!(broadcast.lags().is_empty());
+    }
+
+    #[test]
+    fn test_broadcast_window() {
+        init();
+
+        let broadcast = Broadcast::new(usize::MAX, LagPolicy::Drop);
+        let mut receiver = broadcast.subscribe_with_window(4);
+        let _unbounded = broadcast.subscribe();
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 0..100 {
+                    broadcast.wait_for_credit();
+
+                    // The producer never gets further ahead than the window.
+                    let lag = broadcast.channel().len() - receiver_processed(&broadcast);
+                    assert!(lag < 4);
+
+                    broadcast.push(i).unwrap();
+                }
+            });
+
+            for i in 0..100 {
+                assert_eq!(receiver.recv().unwrap(), &i);
+                receiver.replenish(1);
+            }
+        });
+
+        // Credits of unread items cannot be given back.
+        receiver.replenish(10);
+        assert_eq!(broadcast.credit(), Some(4));
+    }
+
+    /// Get the lowest index processed by a subscriber with a window.
+    fn receiver_processed<T>(broadcast: &Broadcast<T>) -> usize {
+        broadcast
+            .subscriptions
+            .lock()
+            .iter()
+            .filter(|s| s.window.is_some())
+            .map(|s| s.processed.load(Ordering::Acquire))
+            .min()
+            .unwrap()
+    }
+}
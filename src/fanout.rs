@@ -0,0 +1,373 @@
+//! This module contains the implementation of the `Fanout` type, a set of Channels indexed by key.
+
+use crate::sync::{AtomicUsize, Ordering};
+use crate::unbounded::{Channel, Cursor};
+use crate::LogError;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+/// A wildcard subscription: its key predicate, and the `(topic, index)` of the items matching it.
+struct Wildcard<K> {
+    predicate: Box<dyn Fn(&K) -> bool + Send + Sync>,
+    items: Channel<(usize, usize)>,
+}
+
+/// A set of Channels, the topics, each identified by a key.
+///
+/// A topic is created by the first publish or subscription on its key, and is never removed: the
+/// topics live in a Channel themselves, so references to a topic and to its items stay valid as long
+/// as the Fanout.
+///
+/// Besides following a single topic, a wildcard subscription follows every topic whose key matches a
+/// predicate, including the topics created after it.
+///
+/// # Examples
+/// ```
+/// use fremkit::Fanout;
+///
+/// let fanout: Fanout<&str, u64> = Fanout::new();
+/// let mut orders = fanout.subscribe("orders").unwrap();
+/// let mut all = fanout.subscribe_all();
+///
+/// fanout.publish("orders", 1).unwrap();
+/// fanout.publish("audit", 2).unwrap();
+///
+/// assert_eq!(orders.next(), Some(&1));
+/// assert_eq!(orders.next(), None);
+///
+/// assert_eq!(all.by_ref().collect::<Vec<_>>(), vec![(&"orders", &1), (&"audit", &2)]);
+/// assert_eq!(fanout.len(), 2);
+/// ```
+pub struct Fanout<K, T> {
+    topics: Channel<(K, Channel<T>)>,
+    /// Index of each topic in `topics`. Topics are only appended with the write lock held.
+    index: RwLock<HashMap<K, usize>>,
+    wildcards: RwLock<Vec<Arc<Wildcard<K>>>>,
+    /// Number of wildcard subscriptions, to skip routing when there is none.
+    wildcard_count: AtomicUsize,
+}
+
+impl<K, T> Fanout<K, T>
+where
+    K: Hash + Eq + Clone,
+{
+    /// Create a new Fanout, without any topic.
+    pub fn new() -> Self {
+        Self {
+            topics: Channel::new(),
+            index: RwLock::new(HashMap::new()),
+            wildcards: RwLock::new(Vec::new()),
+            wildcard_count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the number of topics.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.topics.len()
+    }
+
+    /// Is there no topic yet ?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.topics.is_empty()
+    }
+
+    /// Get the Channel of a topic, if it exists.
+    pub fn topic(&self, key: &K) -> Option<&Channel<T>> {
+        let id = *self.index.read().get(key)?;
+
+        self.topics.get(id).map(|(_, channel)| channel)
+    }
+
+    /// Iterate over the topics, in the order they were created.
+    pub fn topics(&self) -> impl Iterator<Item = (&K, &Channel<T>)> {
+        self.topics.iter().map(|(key, channel)| (key, channel))
+    }
+
+    /// Append an item to a topic, creating the topic if needed.
+    ///
+    /// # Returns
+    /// The index of the item in its topic, or an error containing the item if it was rejected or
+    /// the Fanout is closed.
+    pub fn publish(&self, key: K, value: T) -> Result<usize, LogError<T>> {
+        let Some((id, (key, channel))) = self.entry(key) else {
+            return Err(LogError::LogClosed(value));
+        };
+        let index = channel.push(value)?;
+
+        if self.wildcard_count.load(Ordering::Acquire) > 0 {
+            for wildcard in self.wildcards.read().iter() {
+                if (wildcard.predicate)(key) {
+                    // The subscription is only closed along with the Fanout.
+                    let _ = wildcard.items.push((id, index));
+                }
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Read a topic from its beginning, creating the topic if needed.
+    ///
+    /// # Returns
+    /// A cursor on the topic, or `None` if the topic needs to be created and the Fanout is closed.
+    pub fn subscribe(&self, key: K) -> Option<Cursor<'_, T>> {
+        let (_, (_, channel)) = self.entry(key)?;
+
+        Some(channel.cursor())
+    }
+
+    /// Subscribe to the items published on every topic whose key matches a predicate.
+    ///
+    /// The predicate is run on the key of every item published after the subscription is created,
+    /// by the producer publishing it. Items which were published before are not part of the
+    /// subscription.
+    pub fn subscribe_matching<F>(&self, predicate: F) -> WildcardSubscription<'_, K, T>
+    where
+        F: Fn(&K) -> bool + Send + Sync + 'static,
+    {
+        let wildcard = Arc::new(Wildcard {
+            predicate: Box::new(predicate),
+            items: Channel::new(),
+        });
+
+        {
+            let mut wildcards = self.wildcards.write();
+            wildcards.push(wildcard.clone());
+            self.wildcard_count
+                .store(wildcards.len(), Ordering::Release);
+        }
+
+        // The Fanout may have been closed before the subscription was registered.
+        if self.topics.is_closed() {
+            wildcard.items.close();
+        }
+
+        WildcardSubscription {
+            idx: 0,
+            wildcard,
+            fanout: self,
+        }
+    }
+
+    /// Subscribe to the items published on every topic, see `subscribe_matching`.
+    pub fn subscribe_all(&self) -> WildcardSubscription<'_, K, T> {
+        self.subscribe_matching(|_| true)
+    }
+
+    /// Close every topic and every wildcard subscription.
+    ///
+    /// Publishing fails from now on, and readers stop waiting once they have read every item.
+    pub fn close(&self) {
+        // Holding the lock keeps a topic from being created while the others are closed.
+        let _index = self.index.write();
+
+        self.topics.close();
+        for (_, channel) in self.topics.iter() {
+            channel.close();
+        }
+
+        for wildcard in self.wildcards.read().iter() {
+            wildcard.items.close();
+        }
+    }
+
+    /// Get the id and the entry of a topic, creating the topic if needed.
+    ///
+    /// # Returns
+    /// The id of the topic with its key and Channel, or `None` if it does not exist and the Fanout
+    /// is closed.
+    fn entry(&self, key: K) -> Option<(usize, &(K, Channel<T>))> {
+        let id = self.topic_id(key)?;
+
+        Some((id, self.topics.get(id)?))
+    }
+
+    /// Get the id of a topic, creating the topic if needed.
+    fn topic_id(&self, key: K) -> Option<usize> {
+        if let Some(&id) = self.index.read().get(&key) {
+            return Some(id);
+        }
+
+        let mut index = self.index.write();
+
+        // The topic may have been created while the lock was released.
+        if let Some(&id) = index.get(&key) {
+            return Some(id);
+        }
+
+        let id = self.topics.push((key.clone(), Channel::new())).ok()?;
+        index.insert(key, id);
+
+        Some(id)
+    }
+}
+
+impl<K, T> Fanout<K, T> {
+    fn remove_wildcard(&self, wildcard: &Arc<Wildcard<K>>) {
+        let mut wildcards = self.wildcards.write();
+
+        wildcards.retain(|w| !Arc::ptr_eq(w, wildcard));
+        self.wildcard_count
+            .store(wildcards.len(), Ordering::Release);
+    }
+}
+
+impl<K, T> Default for Fanout<K, T>
+where
+    K: Hash + Eq + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, T> fmt::Debug for Fanout<K, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.topics
+                    .iter()
+                    .map(|(key, channel)| (key, channel.len())),
+            )
+            .finish()
+    }
+}
+
+/// Reader of the items of every topic of a Fanout whose key matches a predicate, returned by
+/// `Fanout::subscribe_matching`.
+///
+/// Items are received in the order they were published, across topics. Reaching the end of the
+/// subscription is not final: `next` returns `None` until another matching item is published.
+/// Dropping the subscription stops the routing.
+pub struct WildcardSubscription<'a, K, T> {
+    idx: usize,
+    wildcard: Arc<Wildcard<K>>,
+    fanout: &'a Fanout<K, T>,
+}
+
+impl<'a, K, T> WildcardSubscription<'a, K, T> {
+    /// Get the number of matching items read so far.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.idx
+    }
+
+    /// Read the next matching item, blocking until it is published.
+    ///
+    /// # Returns
+    /// The key of its topic and the item, or `None` once the Fanout is closed and every matching
+    /// item has been read.
+    pub fn recv(&mut self) -> Option<(&'a K, &'a T)> {
+        let &(id, index) = self.wildcard.items.wait_for(self.idx)?;
+        self.idx += 1;
+
+        self.item(id, index)
+    }
+
+    /// Get a matching item. Topics are never removed, and items are only routed once they have been
+    /// written, so it is always found.
+    fn item(&self, id: usize, index: usize) -> Option<(&'a K, &'a T)> {
+        let (key, channel) = self.fanout.topics.get(id)?;
+
+        Some((key, channel.get(index)?))
+    }
+}
+
+impl<'a, K, T> Iterator for WildcardSubscription<'a, K, T> {
+    type Item = (&'a K, &'a T);
+
+    /// Read the next matching item, if it has been published.
+    fn next(&mut self) -> Option<Self::Item> {
+        let &(id, index) = self.wildcard.items.get(self.idx)?;
+        self.idx += 1;
+
+        self.item(id, index)
+    }
+}
+
+impl<'a, K, T> Drop for WildcardSubscription<'a, K, T> {
+    fn drop(&mut self) {
+        self.fanout.remove_wildcard(&self.wildcard);
+    }
+}
+
+impl<'a, K, T> fmt::Debug for WildcardSubscription<'a, K, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WildcardSubscription")
+            .field("position", &self.idx)
+            .field("matched", &self.wildcard.items.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_fanout_topics() {
+        init();
+
+        let fanout: Fanout<usize, usize> = Fanout::new();
+        let mut odd = fanout.subscribe_matching(|key| key % 2 == 1);
+
+        thread::scope(|s| {
+            for t in 0..4 {
+                let fanout = &fanout;
+                s.spawn(move || {
+                    for i in 0..100 {
+                        fanout.publish(i % 5, t * 100 + i).unwrap();
+                    }
+                });
+            }
+
+            let mut received = 0;
+            while received < 160 {
+                let (&key, &item) = odd.recv().unwrap();
+                assert_eq!(key, item % 5);
+                received += 1;
+            }
+        });
+
+        // Every topic was created once, whichever producer created it.
+        assert_eq!(fanout.len(), 5);
+        for key in 0..5 {
+            let mut cursor = fanout.subscribe(key).unwrap();
+            let mut last = [None; 4];
+
+            for &item in cursor.by_ref() {
+                assert_eq!(item % 5, key);
+                assert!(last[item / 100] < Some(item));
+                last[item / 100] = Some(item);
+            }
+            assert_eq!(cursor.position(), 80);
+        }
+
+        assert_eq!(odd.next(), None);
+        fanout.close();
+
+        assert_eq!(odd.recv(), None);
+        assert!(fanout.topic(&0).unwrap().is_closed());
+        assert!(matches!(fanout.publish(7, 0), Err(LogError::LogClosed(0))));
+
+        // Existing topics can still be read, new ones cannot be created.
+        assert!(fanout.subscribe(0).is_some());
+        assert!(fanout.subscribe(7).is_none());
+
+        drop(odd);
+        assert_eq!(fanout.wildcard_count.load(Ordering::Relaxed), 0);
+    }
+}
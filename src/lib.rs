@@ -4,9 +4,45 @@
 //!
 //! A Log's primary use case is to store an immutable sequence of messages, events, or other data, and to allow
 //! multiple readers to access the data concurrently.
+//!
+//! When the number of items is not known upfront, `unbounded::Channel` chains bounded Logs into an
+//! unbounded sequence.
+//!
+//! With the `safe-impl` feature, the crate swaps its storage for a slower implementation without any
+//! unsafe code, for audit policies forbidding unsafe in dependencies. `Log::claim` and `MmapLog` are
+//! unavailable with it, as they hand out uninitialized memory.
+
+#![cfg_attr(feature = "safe-impl", forbid(unsafe_code))]
 
+mod ack;
+mod broadcast;
+mod fanout;
 mod log;
+#[cfg(feature = "net")]
+pub mod net;
+mod notifier;
+mod pool;
+mod rendezvous;
+mod replay;
+mod router;
+mod start_gate;
 mod sync;
+mod transaction;
+mod wait_set;
 
+pub use crate::ack::AckQueue;
+pub use crate::broadcast::{Broadcast, BroadcastReceiver, LagPolicy};
+pub use crate::fanout::{Fanout, WildcardSubscription};
 pub use crate::log::bounded;
-pub use crate::log::error::LogError;
+pub use crate::log::capacity;
+pub use crate::log::error::{ConfigError, GapError, LogError, RecvError};
+pub use crate::log::projection::Projection;
+pub use crate::log::unbounded;
+pub use crate::notifier::{CancelToken, Notifier};
+pub use crate::pool::Pool;
+pub use crate::rendezvous::{Pending, Rendezvous};
+pub use crate::replay::{Append, Trace};
+pub use crate::router::{Router, Subscriber};
+pub use crate::start_gate::StartGate;
+pub use crate::transaction::{Coordinator, Staged, Transaction};
+pub use crate::wait_set::{WaitSet, Watch};
@@ -1,12 +1,47 @@
 //! This module contains the implementation of the bounded `Log` type.
 
-use crate::sync::{AtomicUsize, Ordering};
-use crate::LogError;
-
-use std::cell::UnsafeCell;
+use crate::capacity::Capacity;
+#[cfg(feature = "metrics")]
+use crate::log::instrument;
+#[cfg(feature = "stats")]
+use crate::log::stats::StatsCounters;
+use crate::notifier::{Listeners, ShardedNotifier};
+use crate::sync::{fence, AtomicBool, AtomicUsize, Ordering};
+use crate::{ConfigError, GapError, LogError, RecvError};
+
+use std::fmt;
+use std::marker::PhantomData;
+#[cfg(not(feature = "safe-impl"))]
+use std::mem::{ManuallyDrop, MaybeUninit};
+#[cfg(not(feature = "safe-impl"))]
+use std::ops::DerefMut;
+use std::ops::{Bound, Range, RangeBounds};
 use std::sync::Arc;
+#[cfg(not(feature = "safe-impl"))]
+use std::sync::PoisonError;
+use std::time::{Duration, Instant};
 
 use cache_padded::CachePadded;
+use parking_lot::Mutex;
+
+pub use crate::log::fair::FairLog;
+#[cfg(all(feature = "mmap", not(feature = "safe-impl")))]
+pub use crate::log::mmap::MmapLog;
+pub use crate::log::pages::Pages;
+pub use crate::log::ring::RingLog;
+pub use crate::log::sealed::SealedLog;
+pub use crate::log::seq::SeqLog;
+pub use crate::log::sharded::ShardedLog;
+#[cfg(feature = "stats")]
+pub use crate::log::stats::{Histogram, LogStats, PushProfiler};
+pub use crate::log::storage::Storage;
+#[cfg(feature = "async")]
+pub use crate::log::stream::{LogStream, WaitFor, STREAM_BUDGET};
+pub use crate::log::timestamped::TimestampedLog;
+
+/// Number of notifier shards per Log. Waiters for an index are only woken up by pushes to an index
+/// in the same shard.
+const NOTIFIER_SHARDS: usize = 32;
 
 /// This Log stores an immutable, append-only, bounded, concurrent sequence of items.
 ///
@@ -21,7 +56,8 @@ use cache_padded::CachePadded;
 /// For multi-threaded get operations, the Log will be faster than a `Vec` wrapped in a `RwLock`.
 /// Additional performance analysis are available in the benchmarks.
 ///
-/// Operations on Log are lock-free, and will never block.
+/// Operations on Log are lock-free, and will never block. The only exception is `wait_for`, which parks the
+/// calling thread until an item is available at a given index.
 /// The Log also supports concurrent push get operations.
 /// The Log will never be resized, and will always have the same capacity.
 ///
@@ -45,17 +81,37 @@ use cache_padded::CachePadded;
 /// assert_eq!(log.len(), 2);
 /// assert_eq!(log.capacity(), 100);
 /// ```
-#[derive(Debug)]
-pub struct Log<T> {
+///
+/// Items are held by a `Storage`, `Pages` by default. `Log::with_storage` builds a Log over another
+/// backing store, with the same push and get logic.
+pub struct Log<T, S = Pages<T>> {
     len: CachePadded<AtomicUsize>,
+    committed: CachePadded<AtomicUsize>,
     capacity: usize,
-    data: Vec<UnsafeCell<Option<T>>>,
+    epoch: usize,
+    data: S,
+    _item: PhantomData<T>,
+    notifier: ShardedNotifier,
+    /// Live Senders and Receivers, for the mpsc-like API.
+    peers: Peers,
+    /// Wait sets watching the log.
+    listeners: Listeners,
+    /// Slots of claims dropped without committing.
+    skipped: Skipped,
+    /// Claims being published.
+    #[cfg(not(feature = "safe-impl"))]
+    batches: Batches,
+    #[cfg(feature = "stats")]
+    counters: StatsCounters,
 }
 
 impl<T> Log<T> {
     /// Create a new empty Log. It will be able to hold at least `capacity` items.
     /// If `capacity` is 0, the Log will be created with a capacity of 1.
     ///
+    /// Slots are allocated page by page, when the first item of a page is pushed: creating a Log
+    /// with a large capacity is cheap until it fills up.
+    ///
     /// # Examples
     /// ```
     /// use fremkit::bounded::Log;
@@ -63,27 +119,51 @@ impl<T> Log<T> {
     /// let log: Log<u64> = Log::new(100);
     /// ```
     pub fn new(capacity: usize) -> Self {
-        let capacity = capacity.max(1);
+        Self::with_storage(Pages::new(capacity.max(1)))
+    }
 
-        // Specifying capacity here, means we are able to hold at least
-        // this many items without reallocating.
-        let mut data = Vec::with_capacity(capacity);
+    /// Create a new empty Log, with the capacity of a preset checked at compile time.
+    ///
+    /// See the `capacity` module.
+    pub fn with_preset<C: Capacity>() -> Self {
+        Self::new(C::CAPACITY)
+    }
 
-        // Initialize the data.
-        for _ in 0..capacity {
-            data.push(UnsafeCell::new(None));
-        }
+    /// Take the values out of the slots of the log, in index order.
+    pub(crate) fn into_values(self) -> impl Iterator<Item = Option<T>> {
+        self.data.into_values()
+    }
+}
+
+impl<T, S: Storage<T>> Log<T, S> {
+    /// Create a new empty Log backed by a storage, with the capacity of the storage.
+    ///
+    /// The storage must be empty: its slots are written as items are pushed on the log.
+    pub fn with_storage(storage: S) -> Self {
+        let capacity = storage.capacity();
 
         Self {
             capacity,
             len: CachePadded::new(AtomicUsize::new(0)),
-            data,
+            committed: CachePadded::new(AtomicUsize::new(0)),
+            epoch: 0,
+            notifier: ShardedNotifier::new(capacity.min(NOTIFIER_SHARDS)),
+            peers: Peers::new(),
+            listeners: Listeners::new(),
+            skipped: Skipped::new(),
+            #[cfg(not(feature = "safe-impl"))]
+            batches: Batches::new(),
+            data: storage,
+            _item: PhantomData,
+            #[cfg(feature = "stats")]
+            counters: StatsCounters::default(),
         }
     }
 
     /// Get the current length of the log.
     ///
-    /// This is the number of items that have been pushed on the log.
+    /// This is the number of items that have been pushed on the log and are fully written: every
+    /// index below `len()` is available for get. It is the same as `committed_len`.
     /// It will never be greater than the capacity of the log.
     ///
     /// # Examples
@@ -98,9 +178,49 @@ impl<T> Log<T> {
     /// ```
     #[inline]
     pub fn len(&self) -> usize {
+        self.committed_len()
+    }
+
+    /// Get the number of slots reserved by producers.
+    ///
+    /// A slot is reserved by `push` before the item is written to it, so this can be ahead of
+    /// `committed_len` while producers are in the middle of a write.
+    /// It will never be greater than the capacity of the log.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    ///
+    /// assert_eq!(log.reserved_len(), 1);
+    /// ```
+    #[inline]
+    pub fn reserved_len(&self) -> usize {
         self.len.load(Ordering::Relaxed).min(self.capacity())
     }
 
+    /// Get the length of the fully written prefix of the log.
+    ///
+    /// Every slot below the committed length has been written, and its item is visible to all
+    /// readers. It will never be greater than `reserved_len`.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    ///
+    /// assert_eq!(log.committed_len(), 1);
+    /// assert_eq!(log.get(0), Some(&1));
+    /// ```
+    #[inline]
+    pub fn committed_len(&self) -> usize {
+        self.committed.load(Ordering::Acquire)
+    }
+
     /// Get the capacity of the log.
     ///
     /// This is the maximum number of items that can be pushed on the log.
@@ -139,7 +259,8 @@ impl<T> Log<T> {
     /// * `index` - The index of the item to get.
     ///
     /// # Returns
-    /// A reference to the item at the given index, or `None` if the index is out of bounds.
+    /// A reference to the item at the given index, or `None` if the index is out of bounds or the
+    /// item is not written yet.
     ///
     /// # Examples
     /// ```
@@ -155,18 +276,101 @@ impl<T> Log<T> {
     /// assert_eq!(log.get(123), None);
     /// ```
     pub fn get(&self, index: usize) -> Option<&T> {
-        if index >= self.len() {
+        // The reserved length is loaded relaxed, and may lag behind the committed length.
+        let committed = self.committed.load(Ordering::Acquire);
+
+        if index >= committed && index >= self.reserved_len() {
             return None;
         }
 
-        // SAFETY: We know that the index is in bounds, and that the cell is initialized.
+        // The slot contract holds: we know that the index is in bounds, and that the cell is reserved.
         // We also know that the cell will not be modified while we are holding a reference to it.
         // This is because the cell is never modified. The only way to modify the cell is to push an item,
         // and this will only happen if the cell is empty.
         // We also know that the cell will not be dropped while we are holding a reference to it.
-        let cell = &self.data[index];
+        let value = self.data.read(index)?;
+
+        // The items of a claim are only visible once the committed length moves over all of them.
+        #[cfg(not(feature = "safe-impl"))]
+        if index >= committed && self.batches.contains(index) {
+            return None;
+        }
+
+        Some(value)
+    }
+
+    /// Get an item from the log, telling apart an item which is not available yet from an index
+    /// which will never hold one.
+    ///
+    /// # Returns
+    /// A reference to the item at the given index, an `Empty` error if it is not written yet, or a
+    /// `Closed` error if the index is beyond the capacity of the log or its slot was skipped.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    /// use fremkit::RecvError;
+    ///
+    /// let log: Log<u64> = Log::new(2);
+    /// log.push(1).unwrap();
+    ///
+    /// assert_eq!(log.try_get(0), Ok(&1));
+    /// assert_eq!(log.try_get(1), Err(RecvError::Empty));
+    /// assert_eq!(log.try_get(2), Err(RecvError::Closed(2)));
+    /// ```
+    pub fn try_get(&self, index: usize) -> Result<&T, RecvError> {
+        if index >= self.capacity() || self.is_skipped(index) {
+            return Err(RecvError::Closed(index));
+        }
+
+        self.get(index).ok_or(RecvError::Empty)
+    }
+
+    /// Get a range of items from the log.
+    ///
+    /// The range is clamped to the committed length of the log once, when the iterator is created.
+    /// Every slot in it has been written or skipped, and reading the items does not go through an
+    /// atomic load of the length for each of them. Skipped slots are stepped over.
+    ///
+    /// # Arguments
+    /// * `range` - The range of indices to read.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    /// log.push(2).unwrap();
+    /// log.push(3).unwrap();
+    ///
+    /// assert_eq!(log.get_range(1..).collect::<Vec<_>>(), vec![&2, &3]);
+    /// assert_eq!(log.get_range(..2).collect::<Vec<_>>(), vec![&1, &2]);
+    /// assert_eq!(log.get_range(2..10).collect::<Vec<_>>(), vec![&3]);
+    /// ```
+    pub fn get_range<R: RangeBounds<usize>>(&self, range: R) -> LogRangeIterator<'_, T, S> {
+        let len = self.len();
+
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => len,
+        }
+        .min(len);
+
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        }
+        .min(end);
 
-        unsafe { (*cell.get()).as_ref() }
+        LogRangeIterator {
+            idx: start,
+            end,
+            remaining: end - start - self.skipped.count(start..end),
+            log: self,
+        }
     }
 
     /// Append an item to the log.
@@ -193,6 +397,92 @@ impl<T> Log<T> {
     /// assert_eq!(log.get(1), Some(&2));
     /// ```
     pub fn push(&self, value: T) -> Result<usize, LogError<T>> {
+        let result = self
+            .push_unobserved(value)
+            .map_err(LogError::LogCapacityExceeded);
+
+        #[cfg(feature = "metrics")]
+        if let Err(LogError::LogCapacityExceeded(_)) = result {
+            instrument::log_full();
+        }
+
+        result
+    }
+
+    /// Append the item held by an `Option` to the log, leaving it in place if the log is full.
+    ///
+    /// Unlike `push`, a rejected item is not moved back to the caller through the error: it is not
+    /// moved at all. This saves copying large items around when pushes fail often, and the caller
+    /// can retry with the same `Option`, on this log or another one.
+    ///
+    /// # Returns
+    /// The index of the item in the log, in which case `value` is now `None`, or an error if the log
+    /// is full, in which case `value` is untouched. A `LogRejected` error if `value` is `None`.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<[u8; 8192]> = Log::new(1);
+    /// let mut item = Some([1; 8192]);
+    ///
+    /// assert_eq!(log.push_from(&mut item).unwrap(), 0);
+    /// assert!(item.is_none());
+    ///
+    /// let mut item = Some([2; 8192]);
+    /// assert!(log.push_from(&mut item).is_err());
+    /// assert!(item.is_some());
+    /// ```
+    pub fn push_from(&self, value: &mut Option<T>) -> Result<usize, LogError<()>> {
+        if value.is_none() {
+            return Err(LogError::LogRejected(()));
+        }
+
+        loop {
+            let Some(token) = self.reserve_slot() else {
+                #[cfg(feature = "metrics")]
+                instrument::log_full();
+
+                return Err(LogError::LogCapacityExceeded(()));
+            };
+
+            // The item is checked above, and put back when its slot is skipped.
+            let Some(item) = value.take() else {
+                self.skip(token..token + 1);
+                return Err(LogError::LogRejected(()));
+            };
+
+            match self.publish_slot(token, item) {
+                Ok(()) => return Ok(token),
+                Err(item) => *value = Some(item),
+            }
+        }
+    }
+
+    /// Append an item to the log, without reporting a full log to the metrics.
+    ///
+    /// A Channel pushes on its segments with this: a full segment is expected, and is not an error.
+    pub(crate) fn push_unobserved(&self, value: T) -> Result<usize, T> {
+        let mut value = value;
+
+        loop {
+            let Some(token) = self.reserve_slot() else {
+                return Err(value);
+            };
+
+            match self.publish_slot(token, value) {
+                Ok(()) => return Ok(token),
+                Err(item) => value = item,
+            }
+        }
+    }
+
+    /// Reserve the next slot of the log.
+    ///
+    /// # Returns
+    /// The index of the slot, or `None` if the log is full.
+    #[inline]
+    fn reserve_slot(&self) -> Option<usize> {
         // Get the next token.
         // This is the index the item will be written to.
         // INVARIANT: The token will always be in the range [0, capacity).
@@ -201,157 +491,1678 @@ impl<T> Log<T> {
         let token = self.len.fetch_add(1, Ordering::Relaxed);
 
         if token >= self.capacity() {
-            return Err(LogError::LogCapacityExceeded(value));
-        }
+            #[cfg(feature = "stats")]
+            self.counters.failed_push();
 
-        // Get the cell to write to.
-        // SAFETY: The token is always in the range [0, capacity).
-        let cell = &self.data[token];
+            return None;
+        }
 
-        // SAFETY: Cells can only be written to once, and we are the only writer.
-        // SAFETY: It is safe to write to the cell, as it cannot be read from until we first write to it.
-        let slot = unsafe { &mut *cell.get() };
-        *slot = Some(value);
+        #[cfg(feature = "stats")]
+        self.counters
+            .pending(token + 1 - self.committed.load(Ordering::Relaxed).min(token + 1));
 
-        Ok(token)
+        Some(token)
     }
-}
 
-unsafe impl<T: Sync + Send> Send for Log<T> {}
-unsafe impl<T: Sync + Send> Sync for Log<T> {}
+    /// Write an item to a slot reserved with `reserve_slot`, and wake up its readers.
+    ///
+    /// # Returns
+    /// An error containing the item if the storage refused the write. The slot is skipped, and the
+    /// item can be pushed again.
+    #[inline]
+    fn publish_slot(&self, token: usize, value: T) -> Result<(), T> {
+        // Get the slot to write to.
+        // INVARIANT: The token is always in the range [0, capacity).
+        // The slot contract holds: slots can only be written to once, and we are the only writer.
+        // It cannot be read from until we first write to it.
+        if let Err(value) = self.data.write(token, value) {
+            self.skip(token..token + 1);
+            return Err(value);
+        }
 
-//
-// Public API similar to std::sync::mpsc::channel simplified consumption.
-// Please note that the API does not make complete sense for a bounded log.
-//
+        self.commit();
 
-impl<T> Log<T> {
-    /// Convert the Log into a Sender.
-    pub fn into_sender(self: Arc<Self>) -> Sender<T> {
-        Sender { log: self }
+        #[cfg(feature = "stats")]
+        if self.notifier.has_waiters(token) {
+            self.counters.contended_notify();
+        }
+
+        self.notifier.notify(token);
+        self.listeners.notify();
+
+        Ok(())
     }
 
-    /// Convert the Log into a Receiver.
-    ///
-    /// Please note that 'Receiver' is not a good name for the reading end of a Log,
-    /// but it is used for consistency with the std::sync::mpsc::channel API.
-    pub fn into_receiver(self: Arc<Self>) -> Receiver<T> {
-        Receiver { log: self }
+    /// Skip reserved slots which will never be written, waking up their readers.
+    fn skip(&self, range: Range<usize>) {
+        // Like a write: either a committing producer sees the skipped slots, or we see its slot.
+        self.skipped.add(range.clone());
+        fence(Ordering::SeqCst);
+        self.commit();
+
+        // Readers waiting for the skipped slots stop waiting.
+        for index in range {
+            self.notifier.notify(index);
+        }
+        self.listeners.notify();
     }
 
-    /// Create an iterator over the log.
+    /// Get an item from the log, blocking until it becomes available.
     ///
-    /// The iterator will start at the beginning of the channel.
-    /// When reaching the end of the channel, the iterator will stop.
+    /// The calling thread is parked until an item has been written at the given index.
+    /// Note that if no producer ever pushes up to this index, this will block forever.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the item to wait for.
+    ///
+    /// # Returns
+    /// A reference to the item at the given index, or `None` if the index is beyond the capacity of the log
+    /// or its slot was skipped by a claim dropped without committing.
     ///
     /// # Examples
     /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
     /// use fremkit::bounded::Log;
     ///
-    /// let log: Log<u64> = Log::new(100);
-    /// log.push(1).unwrap();
-    /// log.push(2).unwrap();
+    /// let log: Arc<Log<u64>> = Arc::new(Log::new(100));
+    /// let producer = log.clone();
     ///
-    /// for item in log.iter() {
-    ///    println!("{}", item);
-    /// }
+    /// thread::spawn(move || producer.push(1).unwrap());
+    ///
+    /// assert_eq!(log.wait_for(0), Some(&1));
+    /// assert_eq!(log.wait_for(100), None);
     /// ```
-    pub fn iter(&self) -> LogReaderIterator<T> {
-        LogReaderIterator { idx: 0, log: self }
-    }
-}
+    pub fn wait_for(&self, index: usize) -> Option<&T> {
+        if index >= self.capacity() {
+            return None;
+        }
 
-/// Open a new log with a given capacity.
-///
-/// The capacity is the maximum number of items that can be stored in the log.
-///
-/// # Arguments
-/// * `capacity` - The maximum number of items that can be stored in the log.
-///
-/// # Returns
-/// A Sender and a Receiver.
-pub fn open<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
-    let log = Arc::new(Log::new(capacity));
+        #[cfg(feature = "tracing")]
+        let _span = self
+            .get(index)
+            .is_none()
+            .then(|| tracing::debug_span!("wait_for", index).entered());
 
-    (Sender { log: log.clone() }, Receiver { log })
-}
+        self.notifier.wait_while(index, || {
+            self.get(index).is_none() && !self.is_skipped(index)
+        });
 
-/// Sender half of a Log.
-///
-/// The Sender can be cloned, and the clones will all refer to the same Log.
-/// Note, this struct is provided for compatibilities with the std::sync::mpsc::channel API.
-#[derive(Debug, Clone)]
-pub struct Sender<T> {
-    log: Arc<Log<T>>,
-}
+        self.get(index)
+    }
 
-impl<T> Sender<T> {
-    /// Send an item to the Log.
-    ///
-    /// # Arguments
-    /// * `value` - The item to send.
+    /// Wait for an item in bounds of the log, until `stop` returns true or the deadline is reached.
     ///
-    /// # Returns
-    /// The index of the item in the log, or an error containing the item if the log is full.
-    pub fn send(&self, value: T) -> Result<usize, LogError<T>> {
-        self.log.push(value)
+    /// A Channel waits on its segments with this, to stop waiting once it is closed. `stop` is
+    /// checked every time waiters are notified: wake them up with `notify_all` once it changes.
+    pub(crate) fn wait_until<F: Fn() -> bool>(
+        &self,
+        index: usize,
+        deadline: Option<Instant>,
+        stop: F,
+    ) -> Option<&T> {
+        let pending = || self.get(index).is_none() && !stop();
+
+        match deadline {
+            Some(deadline) => {
+                self.notifier.wait_while_timeout(
+                    index,
+                    pending,
+                    deadline.saturating_duration_since(Instant::now()),
+                );
+            }
+            None => self.notifier.wait_while(index, pending),
+        }
+
+        self.get(index)
     }
 
-    /// Convert the sender into its inner Log.
-    pub fn into_inner(self) -> Arc<Log<T>> {
-        self.log
+    /// Wake up every thread and task waiting on the log.
+    pub(crate) fn notify_all(&self) {
+        self.notifier.notify_all();
     }
-}
 
-/// Reader half of a Log.
-///
-/// The Reader can be cloned, and the clones will all refer to the same Log.
-/// Note, this struct is provided for compatibilities with the std::sync::mpsc::channel API.
-#[derive(Debug, Clone)]
-pub struct Receiver<T> {
-    log: Arc<Log<T>>,
-}
+    /// Get the wait sets watching the log.
+    #[inline]
+    pub(crate) fn listeners(&self) -> &Listeners {
+        &self.listeners
+    }
 
-impl<T> Receiver<T> {
-    /// Read an item from the Log at a given index.
-    ///
-    /// # Arguments
-    /// * `index` - The index of the item to read, or receive.
+    /// Get an item from the log, blocking until it becomes available or the deadline is reached.
     ///
     /// # Returns
-    /// The item at the given index, or None if the index is out of bounds.
-    pub fn recv(&self, index: usize) -> Option<&T> {
-        self.log.get(index)
+    /// A reference to the item at the given index, a `Timeout` error if it was not written before
+    /// the deadline, or a `Closed` error if the index is beyond the capacity of the log or its slot
+    /// was skipped.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::{Duration, Instant};
+    ///
+    /// use fremkit::bounded::Log;
+    /// use fremkit::RecvError;
+    ///
+    /// let log: Log<u64> = Log::new(2);
+    /// log.push(1).unwrap();
+    ///
+    /// let deadline = Instant::now() + Duration::from_millis(10);
+    ///
+    /// assert_eq!(log.wait_for_deadline(0, deadline), Ok(&1));
+    /// assert_eq!(log.wait_for_deadline(1, deadline), Err(RecvError::Timeout));
+    /// assert_eq!(log.wait_for_deadline(2, deadline), Err(RecvError::Closed(2)));
+    /// ```
+    pub fn wait_for_deadline(&self, index: usize, deadline: Instant) -> Result<&T, RecvError> {
+        if index >= self.capacity() {
+            return Err(RecvError::Closed(index));
+        }
+
+        self.notifier.wait_while_timeout(
+            index,
+            || self.get(index).is_none() && !self.is_skipped(index),
+            deadline.saturating_duration_since(Instant::now()),
+        );
+
+        match self.get(index) {
+            Some(item) => Ok(item),
+            None if self.is_skipped(index) => Err(RecvError::Closed(index)),
+            None => Err(RecvError::Timeout),
+        }
     }
 
-    /// Convert the Reader into its inner Log.
-    pub fn into_inner(self) -> Arc<Log<T>> {
-        self.log
+    /// Get an item from the log, blocking until it becomes available or the timeout elapses.
+    ///
+    /// See `wait_for_deadline`.
+    pub fn wait_for_timeout(&self, index: usize, timeout: Duration) -> Result<&T, RecvError> {
+        self.wait_for_deadline(index, Instant::now() + timeout)
     }
-}
 
-/// Iterator over the items in a Log.
-pub struct LogReaderIterator<'a, T> {
-    idx: usize,
-    log: &'a Log<T>,
-}
+    /// Advance the committed length over every slot written since the last commit.
+    ///
+    /// A producer only moves the committed length past its own slot once all the slots before it
+    /// have been written. If a previous slot is still being written, its producer will move it
+    /// further when it commits.
+    fn commit(&self) {
+        // Pairs with the fence of the other committing producers: either they see our slot as
+        // written, or we see theirs, so the committed length cannot get stuck behind a written slot.
+        fence(Ordering::SeqCst);
+
+        let mut committed = self.committed.load(Ordering::Acquire);
+
+        while committed < self.capacity() {
+            let Some(next) = self.settled_end(committed) else {
+                break;
+            };
+
+            match self.committed.compare_exchange_weak(
+                committed,
+                next,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => committed = next,
+                Err(current) => {
+                    #[cfg(feature = "stats")]
+                    self.counters.commit_retry();
+
+                    committed = current
+                }
+            }
+        }
 
-impl<'a, T> Iterator for LogReaderIterator<'a, T> {
-    type Item = &'a T;
+        #[cfg(not(feature = "safe-impl"))]
+        self.batches.remove_below(committed);
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let idx = self.idx;
-        self.idx += 1;
+    /// Get how far the committed length can move from an index, if the slot at this index will never
+    /// change again: past the slot if it has been written or skipped, past the whole claim it starts
+    /// if it has been published by one.
+    #[inline]
+    fn settled_end(&self, index: usize) -> Option<usize> {
+        if self.data.is_written(index) {
+            // A claim is registered before its slots are marked as written, from the last to the
+            // first: once its first slot is seen as written, the claim and all its slots are seen.
+            #[cfg(not(feature = "safe-impl"))]
+            if let Some(end) = self.batches.end(index) {
+                return Some(end);
+            }
 
-        self.log.get(idx)
+            return Some(index + 1);
+        }
+
+        self.skipped.contains(index).then_some(index + 1)
     }
-}
 
-#[cfg(test)]
-mod test {
-    use std::sync::Arc;
+    /// Was the slot at an index skipped by a claim dropped without committing ?
+    ///
+    /// The committed length only moves over written and skipped slots, so below it, a slot which is
+    /// not written is skipped.
+    #[inline]
+    fn is_skipped(&self, index: usize) -> bool {
+        index < self.committed_len() && !self.data.is_written(index)
+    }
 
-    use log::debug;
+    /// Get the contention counters of the log.
+    #[cfg(feature = "stats")]
+    #[inline]
+    pub(crate) fn counters(&self) -> &StatsCounters {
+        &self.counters
+    }
+
+    /// Get the notifier woken up on every push.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub(crate) fn notifier(&self) -> &ShardedNotifier {
+        &self.notifier
+    }
+
+    /// Find the first unwritten slot below the reserved length of the log.
+    ///
+    /// A slot is reserved by `push` before the item is written to it. A slot below `reserved_len()`
+    /// can therefore still be empty while its producer is in the middle of a write, or if it died
+    /// before completing it.
+    ///
+    /// # Returns
+    /// The index of the first unwritten slot, or `None` if the committed prefix is hole-free.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    /// log.push(2).unwrap();
+    ///
+    /// assert_eq!(log.first_gap(), None);
+    /// ```
+    pub fn first_gap(&self) -> Option<usize> {
+        (self.committed_len()..self.reserved_len()).find(|&index| self.get(index).is_none())
+    }
+
+    /// Verify that every slot below the reserved length of the log has been written.
+    ///
+    /// # Returns
+    /// A `GapError` holding the index of the first unwritten slot, if any.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    ///
+    /// assert!(log.verify().is_ok());
+    /// ```
+    pub fn verify(&self) -> Result<(), GapError> {
+        match self.first_gap() {
+            Some(index) => Err(GapError(index)),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the epoch of the log.
+    ///
+    /// The epoch starts at 0 and is incremented every time the log is reset. Indices returned by `push`
+    /// are only meaningful within the epoch they were returned in: after a reset, they point to the
+    /// items of the new epoch. Use `push_tagged` and `get_tagged` to keep stale indices from reading
+    /// them.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let mut log: Log<u64> = Log::new(100);
+    /// assert_eq!(log.epoch(), 0);
+    ///
+    /// log.reset();
+    /// assert_eq!(log.epoch(), 1);
+    /// ```
+    #[inline]
+    pub fn epoch(&self) -> usize {
+        self.epoch
+    }
+
+    /// Drop every item of the log and start a new epoch.
+    ///
+    /// The allocation is kept, so the log can be reused across processing rounds without allocating
+    /// its slots again. This requires exclusive access: no reader can hold a reference into the log.
+    ///
+    /// Indices returned by `push` stay plain positions, which read the items pushed at the same
+    /// positions in the new epoch. Indices returned by `push_tagged` carry their epoch, and read
+    /// nothing once it is over.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let mut log: Log<u64> = Log::new(1);
+    /// log.push(1).unwrap();
+    /// assert!(log.push(2).is_err());
+    ///
+    /// log.reset();
+    ///
+    /// assert!(log.is_empty());
+    /// assert_eq!(log.push(3).unwrap(), 0);
+    /// assert_eq!(log.get(0), Some(&3));
+    /// ```
+    pub fn reset(&mut self) {
+        self.data.clear();
+        self.skipped.clear();
+        #[cfg(not(feature = "safe-impl"))]
+        self.batches.clear();
+
+        self.len.store(0, Ordering::Relaxed);
+        self.committed.store(0, Ordering::Relaxed);
+        self.epoch += 1;
+    }
+
+    /// Append an item to the log, like `push`, and tag its index with the current epoch.
+    ///
+    /// # Returns
+    /// The tagged index of the item in the log, or an error containing the item if the log is full.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let mut log: Log<u64> = Log::new(100);
+    /// let index = log.push_tagged(1).unwrap();
+    ///
+    /// assert_eq!(index.index(), 0);
+    /// assert_eq!(log.get_tagged(index), Some(&1));
+    ///
+    /// log.reset();
+    /// log.push(2).unwrap();
+    ///
+    /// // The index is stale: it does not read the items of the new epoch.
+    /// assert_eq!(log.get_tagged(index), None);
+    /// ```
+    pub fn push_tagged(&self, value: T) -> Result<EpochIndex, LogError<T>> {
+        let index = self.push(value)?;
+
+        Ok(EpochIndex {
+            epoch: self.epoch,
+            index,
+        })
+    }
+
+    /// Get an item from the log, if its index was returned in the current epoch.
+    ///
+    /// # Returns
+    /// A reference to the item at the given index, or `None` if it is not available, or the log has
+    /// been reset since the index was returned.
+    #[inline]
+    pub fn get_tagged(&self, index: EpochIndex) -> Option<&T> {
+        if index.epoch != self.epoch {
+            return None;
+        }
+
+        self.get(index.index)
+    }
+}
+
+#[cfg(not(feature = "safe-impl"))]
+impl<T> Log<T> {
+    /// Reserve `n` contiguous slots, to be written in place and published together.
+    ///
+    /// The items are written through the returned guard, straight into the slots of the log, then
+    /// published with `ClaimGuard::commit`. Publication is atomic: readers see none of the items until
+    /// the committed length moves over all of them at once.
+    ///
+    /// The slots of a claim are held by a single page: if the current page has fewer than `n` slots
+    /// left, they are skipped and the claim starts on the next one.
+    ///
+    /// Dropping the guard without committing gives the slots back if no other slot was reserved
+    /// since. Otherwise the slots are skipped: they are never written, the committed length moves
+    /// past them, and readers step over them.
+    ///
+    /// Not available with the `safe-impl` feature, as the guard hands out uninitialized slots.
+    ///
+    /// # Returns
+    /// The guard, or `None` if `n` is 0, larger than a page, or more than the slots left.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(4);
+    /// log.push(0).unwrap();
+    ///
+    /// let mut claim = log.claim(2).unwrap();
+    /// assert_eq!(claim.start(), 1);
+    ///
+    /// for (i, slot) in claim.slots().iter_mut().enumerate() {
+    ///     slot.write(i as u64 + 1);
+    /// }
+    ///
+    /// // SAFETY: Every slot has been written above.
+    /// unsafe { claim.commit() };
+    ///
+    /// assert_eq!(log.iter().collect::<Vec<_>>(), vec![&0, &1, &2]);
+    /// assert!(log.claim(2).is_none());
+    /// ```
+    pub fn claim(&self, n: usize) -> Option<ClaimGuard<'_, T>> {
+        let capacity = self.capacity();
+        let page_size = self.data.page_size();
+
+        // Where a claim reserved at `len` starts: on the next page if the current one is too short.
+        let place = |len: usize| {
+            let room = page_size - len % page_size;
+            if n > room {
+                len + room
+            } else {
+                len
+            }
+        };
+
+        // Unlike `push`, the reservation must not overshoot the capacity: a partial batch of slots
+        // would never be written, and would hold back the committed length forever.
+        let reserved = self
+            .len
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |len| {
+                if n == 0 || n > page_size {
+                    return None;
+                }
+
+                place(len).checked_add(n).filter(|&end| end <= capacity)
+            });
+
+        #[cfg(feature = "stats")]
+        match reserved {
+            Ok(len) => {
+                let end = place(len) + n;
+                self.counters
+                    .pending(end - self.committed.load(Ordering::Relaxed).min(end))
+            }
+            Err(_) => self.counters.failed_push(),
+        }
+
+        let len = reserved.ok()?;
+        let start = place(len);
+
+        if start > len {
+            self.skip(len..start);
+        }
+
+        // SAFETY: The slots have just been reserved, so no producer writes to them, and they are
+        // only read once published. The values are only used through the guard, which publishes or
+        // releases them when consumed.
+        let Some(values) = (unsafe { self.data.claim(start..start + n) }) else {
+            self.skip(start..start + n);
+            return None;
+        };
+
+        Some(ClaimGuard {
+            log: self,
+            start,
+            values,
+        })
+    }
+}
+
+/// An index of a Log, tagged with the epoch it was returned in.
+///
+/// Returned by `Log::push_tagged`. Once the log is reset, `Log::get_tagged` no longer reads anything
+/// through it, where a plain index would read the item pushed at the same position since.
+///
+/// `Log::push` keeps returning a plain `usize`: Channel segments, Senders and the other wrappers of the
+/// crate compute positions from it, and a Log which is never reset has no use for the epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EpochIndex {
+    epoch: usize,
+    index: usize,
+}
+
+impl EpochIndex {
+    /// Get the epoch the index was returned in.
+    #[inline]
+    pub fn epoch(&self) -> usize {
+        self.epoch
+    }
+
+    /// Get the index, without its epoch.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+/// Number of entries shown at each end of the log by the `Debug` implementation.
+const DEBUG_ENTRIES: usize = 4;
+
+/// Only the first and last few entries are shown, as a Log can hold millions of slots.
+/// Reserved slots which have not been written yet are shown as `_`.
+impl<T: fmt::Debug, S: Storage<T>> fmt::Debug for Log<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Log")
+            .field("capacity", &self.capacity())
+            .field("len", &self.len())
+            .field("entries", &DebugEntries(self))
+            .finish()
+    }
+}
+
+struct DebugEntries<'a, T, S>(&'a Log<T, S>);
+
+impl<'a, T: fmt::Debug, S: Storage<T>> fmt::Debug for DebugEntries<'a, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let log = self.0;
+
+        debug_entries(f, log.reserved_len(), |index| log.get(index))
+    }
+}
+
+/// Format the first and last `DEBUG_ENTRIES` entries below `len` as a list, with `_` for the
+/// entries which are not written yet.
+pub(crate) fn debug_entries<'a, T: fmt::Debug + 'a>(
+    f: &mut fmt::Formatter<'_>,
+    len: usize,
+    get: impl Fn(usize) -> Option<&'a T>,
+) -> fmt::Result {
+    let (head, tail) = if len <= DEBUG_ENTRIES * 2 {
+        (0..len, len..len)
+    } else {
+        (0..DEBUG_ENTRIES, len - DEBUG_ENTRIES..len)
+    };
+
+    let mut list = f.debug_list();
+
+    for index in head {
+        debug_entry(&mut list, get(index));
+    }
+
+    if !tail.is_empty() {
+        list.entry(&format_args!(".."));
+    }
+
+    for index in tail {
+        debug_entry(&mut list, get(index));
+    }
+
+    list.finish()
+}
+
+fn debug_entry<T: fmt::Debug>(list: &mut fmt::DebugList<'_, '_>, item: Option<&T>) {
+    match item {
+        Some(item) => list.entry(item),
+        None => list.entry(&format_args!("_")),
+    };
+}
+
+//
+// Single-threaded API, for the "fill on one thread, then share" pattern.
+//
+
+/// A single-threaded Log.
+///
+/// It shares the storage of `Log`, but uses a plain integer for its length, so pushes do not pay for
+/// atomic operations. A LocalLog cannot be shared between threads; convert it into a `Log` once it has
+/// been filled.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::{LocalLog, Log};
+///
+/// let mut local: LocalLog<u64> = LocalLog::new(100);
+/// local.push(1).unwrap();
+/// local.push(2).unwrap();
+///
+/// let log: Log<u64> = local.into();
+///
+/// assert_eq!(log.get(1), Some(&2));
+/// assert_eq!(log.len(), 2);
+/// ```
+pub struct LocalLog<T> {
+    len: usize,
+    epoch: usize,
+    data: Pages<T>,
+    skipped: Skipped,
+}
+
+impl<T> LocalLog<T> {
+    /// Create a new empty LocalLog. It will be able to hold at least `capacity` items.
+    /// If `capacity` is 0, the LocalLog will be created with a capacity of 1.
+    pub fn new(capacity: usize) -> Self {
+        Log::new(capacity).into()
+    }
+
+    /// Get the current length of the log.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Get the capacity of the log.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
+    /// Is the log empty ?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get an item from the log.
+    ///
+    /// # Returns
+    /// A reference to the item at the given index, or `None` if the index is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+
+        // A LocalLog is not Sync, and push requires a mutable reference.
+        // No write can happen while this reference is alive.
+        self.data.read(index)
+    }
+
+    /// Append an item to the log.
+    ///
+    /// # Returns
+    /// The index of the item in the log, or an error containing the item if the log is full.
+    pub fn push(&mut self, value: T) -> Result<usize, LogError<T>> {
+        let token = self.len;
+
+        if token >= self.capacity() {
+            return Err(LogError::LogCapacityExceeded(value));
+        }
+
+        // We hold the only reference to the log, and the slot has never been written to.
+        if let Err(value) = Storage::write(&self.data, token, value) {
+            return Err(LogError::LogCapacityExceeded(value));
+        }
+        self.len += 1;
+
+        Ok(token)
+    }
+}
+
+/// Only the committed prefix of the log is kept. Slots written after a gap were never committed:
+/// their items are dropped, so the LocalLog can push over them.
+impl<T> From<Log<T>> for LocalLog<T> {
+    fn from(log: Log<T>) -> Self {
+        let len = log.committed_len();
+        let mut data = log.data;
+        data.clear_from(len);
+
+        // Slots skipped after the gap are writable again, and must not be stepped over.
+        let mut skipped = log.skipped;
+        skipped.truncate(len);
+
+        Self {
+            len,
+            epoch: log.epoch,
+            data,
+            skipped,
+        }
+    }
+}
+
+impl<T> From<LocalLog<T>> for Log<T> {
+    fn from(local: LocalLog<T>) -> Self {
+        Self {
+            len: CachePadded::new(AtomicUsize::new(local.len)),
+            committed: CachePadded::new(AtomicUsize::new(local.len)),
+            capacity: local.data.capacity(),
+            epoch: local.epoch,
+            notifier: ShardedNotifier::new(local.data.capacity().min(NOTIFIER_SHARDS)),
+            peers: Peers::new(),
+            listeners: Listeners::new(),
+            skipped: local.skipped,
+            #[cfg(not(feature = "safe-impl"))]
+            batches: Batches::new(),
+            data: local.data,
+            _item: PhantomData,
+            #[cfg(feature = "stats")]
+            counters: StatsCounters::default(),
+        }
+    }
+}
+
+//
+// Builder API, for callers who want configuration errors to be reported instead of silently fixed.
+//
+
+/// Builder for a Log.
+///
+/// Unlike `Log::new`, which silently raises a capacity of 0 to 1, the builder reports invalid
+/// configurations as errors.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::LogBuilder;
+///
+/// let log = LogBuilder::new().capacity(100).build::<u64>().unwrap();
+/// assert_eq!(log.capacity(), 100);
+///
+/// assert!(LogBuilder::new().build::<u64>().is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LogBuilder {
+    capacity: usize,
+}
+
+impl LogBuilder {
+    /// Create a new builder. A capacity must be set before building the Log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the capacity of the Log.
+    ///
+    /// # Arguments
+    /// * `capacity` - The maximum number of items that can be stored in the log. Must be greater than 0.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Build the Log.
+    ///
+    /// # Returns
+    /// A new empty Log, or an error if the configuration is invalid.
+    pub fn build<T>(self) -> Result<Log<T>, ConfigError> {
+        if self.capacity == 0 {
+            return Err(ConfigError::InvalidCapacity(self.capacity));
+        }
+
+        Ok(Log::new(self.capacity))
+    }
+}
+
+//
+// Public API similar to std::sync::mpsc::channel simplified consumption.
+// Please note that the API does not make complete sense for a bounded log.
+//
+
+impl<T> Log<T> {
+    /// Convert the Log into a Sender.
+    pub fn into_sender(self: Arc<Self>) -> Sender<T> {
+        Sender::new(self)
+    }
+
+    /// Convert the Log into a Receiver.
+    ///
+    /// Please note that 'Receiver' is not a good name for the reading end of a Log,
+    /// but it is used for consistency with the std::sync::mpsc::channel API.
+    pub fn into_receiver(self: Arc<Self>) -> Receiver<T> {
+        Receiver::new(self)
+    }
+
+    /// Create an iterator over the log.
+    ///
+    /// The iterator will start at the beginning of the channel.
+    /// When reaching the end of the channel, the iterator will stop.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    /// log.push(2).unwrap();
+    ///
+    /// for item in log.iter() {
+    ///    println!("{}", item);
+    /// }
+    /// ```
+    pub fn iter(&self) -> LogReaderIterator<'_, T> {
+        LogReaderIterator {
+            idx: 0,
+            end: usize::MAX,
+            log: self,
+        }
+    }
+
+    /// Create an iterator over a snapshot of the log.
+    ///
+    /// The length of the log is pinned when the iterator is created: items appended afterwards are
+    /// never yielded, even if they become available while iterating. This gives an internally
+    /// consistent view of the log while producers keep appending.
+    /// Like `iter`, the iterator stops early at a slot which has been reserved but not written yet.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    ///
+    /// let mut iter = log.iter_snapshot();
+    /// log.push(2).unwrap();
+    ///
+    /// assert_eq!(iter.next(), Some(&1));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    pub fn iter_snapshot(&self) -> LogReaderIterator<'_, T> {
+        LogReaderIterator {
+            idx: 0,
+            end: self.len(),
+            log: self,
+        }
+    }
+
+    /// Create an iterator over the committed items of the log, starting at an index.
+    ///
+    /// This is `get_range(start..)`: the iterator covers the items committed when it was created,
+    /// knows its exact length, and can be walked from both ends.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    /// log.push(2).unwrap();
+    /// log.push(3).unwrap();
+    ///
+    /// let iter = log.iter_from(1);
+    /// assert_eq!(iter.len(), 2);
+    /// assert_eq!(iter.rev().collect::<Vec<_>>(), vec![&3, &2]);
+    /// ```
+    pub fn iter_from(&self, start: usize) -> LogRangeIterator<'_, T> {
+        self.get_range(start..)
+    }
+
+    /// Create a cursor following the log from its beginning.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// let mut cursor = log.cursor();
+    ///
+    /// log.push(1).unwrap();
+    /// log.push(2).unwrap();
+    ///
+    /// assert_eq!(cursor.remaining(), 2);
+    /// assert_eq!(cursor.next(), Some(&1));
+    /// assert_eq!(cursor.next_blocking(), Some(&2));
+    /// assert_eq!(cursor.next(), None);
+    ///
+    /// log.push(3).unwrap();
+    ///
+    /// assert_eq!(cursor.next(), Some(&3));
+    /// ```
+    pub fn cursor(&self) -> Cursor<'_, T> {
+        Cursor { idx: 0, log: self }
+    }
+
+    /// Create a receiver sharing the items of the log between workers.
+    ///
+    /// Unlike cursors, which each see every item, workers sharing a `WorkQueueReceiver` claim items
+    /// from a common position: every item is received by exactly one of them.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::thread;
+    ///
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// for i in 0..100 {
+    ///     log.push(i).unwrap();
+    /// }
+    ///
+    /// let queue = log.work_queue();
+    ///
+    /// let sum: u64 = thread::scope(|s| {
+    ///     let workers: Vec<_> = (0..4)
+    ///         .map(|_| s.spawn(|| queue.recv_iter().map(|(_, item)| item).sum::<u64>()))
+    ///         .collect();
+    ///
+    ///     workers.into_iter().map(|w| w.join().unwrap()).sum()
+    /// });
+    ///
+    /// assert_eq!(sum, (0..100).sum());
+    /// ```
+    pub fn work_queue(&self) -> WorkQueueReceiver<'_, T> {
+        WorkQueueReceiver {
+            next: CachePadded::new(AtomicUsize::new(0)),
+            log: self,
+        }
+    }
+
+    /// Create an iterator over the log which skips unwritten slots.
+    ///
+    /// Where `iter` stops at the first slot that has been reserved but not written yet, this iterator
+    /// steps over it and remembers its index, so a slow producer does not stall the reader.
+    /// Skipped slots can be collected later with `LogSkippingIterator::revisit`.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    /// log.push(2).unwrap();
+    ///
+    /// let mut iter = log.iter_skip_gaps();
+    ///
+    /// assert_eq!(iter.by_ref().count(), 2);
+    /// assert!(iter.gaps().is_empty());
+    /// ```
+    pub fn iter_skip_gaps(&self) -> LogSkippingIterator<'_, T> {
+        LogSkippingIterator {
+            idx: 0,
+            gaps: Vec::new(),
+            log: self,
+        }
+    }
+}
+
+/// Open a new log with a given capacity.
+///
+/// The capacity is the maximum number of items that can be stored in the log.
+///
+/// # Arguments
+/// * `capacity` - The maximum number of items that can be stored in the log.
+///
+/// # Returns
+/// A Sender and a Receiver.
+pub fn open<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let log = Arc::new(Log::new(capacity));
+
+    (Sender::new(log.clone()), Receiver::new(log))
+}
+
+/// Number of live peers on one side of a Log.
+///
+/// A side is disconnected once its last peer is dropped. A side which never had a peer is not:
+/// a Log shared through an `Arc` can be read or written to without Senders or Receivers.
+struct PeerCount {
+    live: AtomicUsize,
+    dropped: AtomicBool,
+}
+
+impl PeerCount {
+    fn new() -> Self {
+        Self {
+            live: AtomicUsize::new(0),
+            dropped: AtomicBool::new(false),
+        }
+    }
+
+    fn join(&self) {
+        self.live.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Drop a peer.
+    ///
+    /// # Returns
+    /// `true` if it was the last peer of its side.
+    fn leave(&self) -> bool {
+        let last = self.live.fetch_sub(1, Ordering::AcqRel) == 1;
+
+        if last {
+            self.dropped.store(true, Ordering::Release);
+        }
+
+        last
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.dropped.load(Ordering::Acquire) && self.live.load(Ordering::Acquire) == 0
+    }
+}
+
+/// Live Senders and Receivers of a Log.
+struct Peers {
+    senders: PeerCount,
+    receivers: PeerCount,
+}
+
+impl Peers {
+    fn new() -> Self {
+        Self {
+            senders: PeerCount::new(),
+            receivers: PeerCount::new(),
+        }
+    }
+}
+
+/// Sender half of a Log.
+///
+/// The Sender can be cloned, and the clones will all refer to the same Log. Once every Receiver of
+/// the Log is dropped, sending fails with a `LogDisconnected` error.
+/// Note, this struct is provided for compatibilities with the std::sync::mpsc::channel API.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::open;
+/// use fremkit::LogError;
+///
+/// let (tx, rx) = open(4);
+/// tx.send(1).unwrap();
+///
+/// drop(rx);
+/// assert!(matches!(tx.send(2), Err(LogError::LogDisconnected(2))));
+/// ```
+#[derive(Debug)]
+pub struct Sender<T> {
+    log: Arc<Log<T>>,
+}
+
+impl<T> Sender<T> {
+    fn new(log: Arc<Log<T>>) -> Self {
+        log.peers.senders.join();
+
+        Self { log }
+    }
+
+    /// Send an item to the Log.
+    ///
+    /// # Arguments
+    /// * `value` - The item to send.
+    ///
+    /// # Returns
+    /// The index of the item in the log, or an error containing the item if the log is full or
+    /// every Receiver has been dropped.
+    pub fn send(&self, value: T) -> Result<usize, LogError<T>> {
+        if self.log.peers.receivers.is_disconnected() {
+            return Err(LogError::LogDisconnected(value));
+        }
+
+        self.log.push(value)
+    }
+
+    /// Convert the sender into its inner Log. The sender is dropped.
+    pub fn into_inner(self) -> Arc<Log<T>> {
+        self.log.clone()
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.log.clone())
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // Wake up the readers blocked on an item which will never be sent.
+        if self.log.peers.senders.leave() {
+            self.log.notify_all();
+        }
+    }
+}
+
+/// Reader half of a Log.
+///
+/// The Reader can be cloned, and the clones will all refer to the same Log. Once every Sender of
+/// the Log is dropped, blocking reads stop waiting for items which have not been sent.
+/// Note, this struct is provided for compatibilities with the std::sync::mpsc::channel API.
+#[derive(Debug)]
+pub struct Receiver<T> {
+    log: Arc<Log<T>>,
+}
+
+impl<T> Receiver<T> {
+    fn new(log: Arc<Log<T>>) -> Self {
+        log.peers.receivers.join();
+
+        Self { log }
+    }
+
+    /// Read an item from the Log at a given index.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the item to read, or receive.
+    ///
+    /// # Returns
+    /// The item at the given index, or None if the index is out of bounds.
+    pub fn recv(&self, index: usize) -> Option<&T> {
+        self.log.get(index)
+    }
+
+    /// Read an item from the Log at a given index, blocking until it becomes available.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the item to read, or receive.
+    ///
+    /// # Returns
+    /// The item at the given index, or None if the index is beyond the capacity of the log, or if
+    /// every Sender was dropped before sending it.
+    pub fn recv_blocking(&self, index: usize) -> Option<&T> {
+        if index >= self.log.capacity() {
+            return None;
+        }
+
+        self.log
+            .wait_until(index, None, || self.log.peers.senders.is_disconnected())
+    }
+
+    /// Convert the Reader into its inner Log. The reader is dropped.
+    pub fn into_inner(self) -> Arc<Log<T>> {
+        self.log.clone()
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.log.clone())
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.log.peers.receivers.leave();
+    }
+}
+
+/// Iterator over the items in a Log.
+pub struct LogReaderIterator<'a, T> {
+    idx: usize,
+    end: usize,
+    log: &'a Log<T>,
+}
+
+impl<'a, T> Iterator for LogReaderIterator<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.idx < self.end {
+            let idx = self.idx;
+            self.idx += 1;
+
+            match self.log.get(idx) {
+                Some(item) => return Some(item),
+                None if self.log.is_skipped(idx) => continue,
+                None => return None,
+            }
+        }
+
+        None
+    }
+}
+
+/// Reader following a Log at its own pace.
+///
+/// A cursor remembers the index of the next item to read. Unlike the iterators, reaching the end of
+/// the log is not final: `next` returns `None` until another item is pushed, and then yields it.
+/// Multiple cursors can follow the same log independently.
+#[derive(Debug)]
+pub struct Cursor<'a, T> {
+    idx: usize,
+    log: &'a Log<T>,
+}
+
+impl<'a, T> Cursor<'a, T> {
+    /// Get the index of the next item to be read.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.idx
+    }
+
+    /// Get the number of items available to the cursor without blocking.
+    pub fn remaining(&self) -> usize {
+        self.log.len().saturating_sub(self.idx)
+    }
+
+    /// Read the next item, blocking until it becomes available.
+    ///
+    /// # Returns
+    /// The next item, or `None` if the cursor reached the capacity of the log.
+    pub fn next_blocking(&mut self) -> Option<&'a T> {
+        loop {
+            let item = self.log.wait_for(self.idx);
+
+            if item.is_none() && !self.log.is_skipped(self.idx) {
+                return None;
+            }
+            self.idx += 1;
+
+            if item.is_some() {
+                return item;
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for Cursor<'a, T> {
+    type Item = &'a T;
+
+    /// Read the next item, if it is available. Skipped slots are stepped over.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = self.log.get(self.idx);
+
+            if item.is_none() && !self.log.is_skipped(self.idx) {
+                return None;
+            }
+            self.idx += 1;
+
+            if item.is_some() {
+                return item;
+            }
+        }
+    }
+}
+
+/// Receiver sharing the items of a Log between workers, returned by `Log::work_queue`.
+///
+/// Workers share the receiver by reference, and each call claims the next unclaimed index.
+#[derive(Debug)]
+pub struct WorkQueueReceiver<'a, T> {
+    next: CachePadded<AtomicUsize>,
+    log: &'a Log<T>,
+}
+
+impl<'a, T> WorkQueueReceiver<'a, T> {
+    /// Claim the next item, if it is available.
+    ///
+    /// # Returns
+    /// The index and the item, or `None` if every available item has been claimed.
+    pub fn try_recv(&self) -> Option<(usize, &'a T)> {
+        let mut index = self.next.load(Ordering::Relaxed);
+
+        loop {
+            if index >= self.log.len() {
+                return None;
+            }
+
+            match self.next.compare_exchange_weak(
+                index,
+                index + 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => match self.log.get(index) {
+                    Some(item) => return Some((index, item)),
+                    // The slot was skipped: claim the next one.
+                    None => index += 1,
+                },
+                Err(current) => index = current,
+            }
+        }
+    }
+
+    /// Claim the next index, and block until its item becomes available.
+    ///
+    /// # Returns
+    /// The index and the item, or `None` once every index up to the capacity has been claimed.
+    pub fn recv(&self) -> Option<(usize, &'a T)> {
+        loop {
+            let index = self.next.fetch_add(1, Ordering::Relaxed);
+
+            match self.log.wait_for(index) {
+                Some(item) => return Some((index, item)),
+                None if self.log.is_skipped(index) => continue,
+                None => return None,
+            }
+        }
+    }
+
+    /// Create an iterator claiming the items which are available, until none are left.
+    pub fn recv_iter(&self) -> impl Iterator<Item = (usize, &'a T)> + '_ {
+        std::iter::from_fn(move || self.try_recv())
+    }
+}
+
+/// Iterator over a range of items in a Log, returned by `Log::get_range`.
+pub struct LogRangeIterator<'a, T, S = Pages<T>> {
+    idx: usize,
+    end: usize,
+    /// Number of items left in the range, which does not count skipped slots.
+    remaining: usize,
+    log: &'a Log<T, S>,
+}
+
+impl<'a, T, S: Storage<T>> Iterator for LogRangeIterator<'a, T, S> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The range is below the committed length, so every slot in it has been written or skipped.
+        // Step over any slot which was not written, rather than ending the iteration early.
+        while self.idx < self.end {
+            let idx = self.idx;
+            self.idx += 1;
+
+            if let Some(item) = self.log.data.read(idx) {
+                self.remaining = self.remaining.saturating_sub(1);
+                return Some(item);
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T, S: Storage<T>> DoubleEndedIterator for LogRangeIterator<'a, T, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // See `next`.
+        while self.idx < self.end {
+            self.end -= 1;
+
+            if let Some(item) = self.log.data.read(self.end) {
+                self.remaining = self.remaining.saturating_sub(1);
+                return Some(item);
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a, T, S: Storage<T>> ExactSizeIterator for LogRangeIterator<'a, T, S> {}
+
+/// Ranges of slots which will never be written, because the claim reserving them was dropped
+/// without committing, or the storage refused the write.
+///
+/// The committed length moves over them like over written slots, so a dropped claim does not hold
+/// the log back. They are rare: the ranges are only locked once a slot has been skipped.
+struct Skipped {
+    any: AtomicBool,
+    ranges: Mutex<Vec<Range<usize>>>,
+}
+
+impl Skipped {
+    fn new() -> Self {
+        Self {
+            any: AtomicBool::new(false),
+            ranges: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Skip a range of slots.
+    fn add(&self, range: Range<usize>) {
+        self.ranges.lock().push(range);
+        self.any.store(true, Ordering::SeqCst);
+    }
+
+    /// Is the slot at an index skipped ?
+    #[inline]
+    fn contains(&self, index: usize) -> bool {
+        self.any.load(Ordering::SeqCst)
+            && self
+                .ranges
+                .lock()
+                .iter()
+                .any(|range| range.contains(&index))
+    }
+
+    /// Count the skipped slots in a range of indices.
+    fn count(&self, indices: Range<usize>) -> usize {
+        if !self.any.load(Ordering::SeqCst) {
+            return 0;
+        }
+
+        self.ranges
+            .lock()
+            .iter()
+            .map(|range| {
+                range
+                    .end
+                    .min(indices.end)
+                    .saturating_sub(range.start.max(indices.start))
+            })
+            .sum()
+    }
+
+    /// Forget the skipped slots from an index on, once they have been made writable again.
+    fn truncate(&mut self, len: usize) {
+        let ranges = self.ranges.get_mut();
+
+        ranges.retain_mut(|range| {
+            range.end = range.end.min(len);
+            range.start < range.end
+        });
+        self.any = AtomicBool::new(!ranges.is_empty());
+    }
+
+    fn clear(&mut self) {
+        self.ranges.get_mut().clear();
+        self.any = AtomicBool::new(false);
+    }
+}
+
+/// Ranges of slots written by claims, whose items are only visible once the committed length moves
+/// over all of them.
+///
+/// A claim is registered before its slots are marked as written, so whoever sees one of them written
+/// also sees the claim. They are rare: the ranges are only locked while a claim is being published.
+#[cfg(not(feature = "safe-impl"))]
+struct Batches {
+    /// Number of registered claims. It is only updated once the lock is released, and may be briefly
+    /// ahead of the ranges, but a claim is always counted before its slots are published.
+    count: AtomicUsize,
+    /// Unlike the other locks of the log, this one is modelled by loom: it decides whether a written
+    /// slot is visible.
+    ranges: crate::sync::Mutex<Vec<Range<usize>>>,
+}
+
+#[cfg(not(feature = "safe-impl"))]
+impl Batches {
+    fn new() -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+            ranges: crate::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    #[inline]
+    fn ranges(&self) -> impl DerefMut<Target = Vec<Range<usize>>> + '_ {
+        self.ranges.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Register the slots of a claim.
+    fn add(&self, range: Range<usize>) {
+        self.ranges().push(range);
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Get the end of the claim starting at an index, if there is one.
+    #[inline]
+    fn end(&self, index: usize) -> Option<usize> {
+        if self.count.load(Ordering::SeqCst) == 0 {
+            return None;
+        }
+
+        self.ranges()
+            .iter()
+            .find(|range| range.start == index)
+            .map(|range| range.end)
+    }
+
+    /// Is the slot at an index part of a claim ?
+    #[inline]
+    fn contains(&self, index: usize) -> bool {
+        self.count.load(Ordering::SeqCst) > 0
+            && self.ranges().iter().any(|range| range.contains(&index))
+    }
+
+    /// Forget the claims the committed length has moved over.
+    fn remove_below(&self, committed: usize) {
+        if self.count.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+
+        let removed = {
+            let mut ranges = self.ranges();
+            let len = ranges.len();
+            ranges.retain(|range| range.end > committed);
+            len - ranges.len()
+        };
+
+        self.count.fetch_sub(removed, Ordering::SeqCst);
+    }
+
+    fn clear(&mut self) {
+        self.ranges().clear();
+        self.count.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Contiguous slots reserved in a Log, returned by `Log::claim`.
+///
+/// Items are written in place, in the slots of the log, and become visible together on commit.
+#[cfg(not(feature = "safe-impl"))]
+pub struct ClaimGuard<'a, T> {
+    log: &'a Log<T>,
+    start: usize,
+    values: &'a mut [MaybeUninit<T>],
+}
+
+#[cfg(not(feature = "safe-impl"))]
+impl<'a, T> ClaimGuard<'a, T> {
+    /// Get the index of the first claimed slot.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Get the number of claimed slots.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Are there no claimed slots ?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Get the slots to write, in index order.
+    #[inline]
+    pub fn slots(&mut self) -> &mut [MaybeUninit<T>] {
+        self.values
+    }
+
+    /// Publish the items, making them available for get all at once.
+    ///
+    /// # Returns
+    /// The index of the first item.
+    ///
+    /// # Safety
+    /// Every element of `slots` must have been initialized.
+    pub unsafe fn commit(self) -> usize {
+        // The slots are published below: dropping the guard must not release them.
+        let this = ManuallyDrop::new(self);
+        let (log, start) = (this.log, this.start);
+        let end = start + this.values.len();
+
+        // Registered first: a reader or producer seeing one of the slots written also sees the claim,
+        // and holds off until the committed length moves over all of its slots.
+        log.batches.add(start..end);
+
+        // SAFETY: The caller initialized every value, and the guard handing them out is consumed.
+        unsafe { log.data.publish(start..end) };
+
+        // If the entries before the claim are committed, move over all of its slots at once.
+        // Otherwise, this is left to the producer committing these entries.
+        fence(Ordering::SeqCst);
+        let _ = log
+            .committed
+            .compare_exchange(start, end, Ordering::AcqRel, Ordering::Acquire);
+        log.commit();
+
+        for index in start..end {
+            log.notifier.notify(index);
+        }
+        log.listeners.notify();
+
+        start
+    }
+}
+
+/// Dropping a guard without committing gives its slots back if no other slot was reserved since,
+/// and skips them otherwise. Items already written to its slots are leaked, not dropped.
+#[cfg(not(feature = "safe-impl"))]
+impl<'a, T> Drop for ClaimGuard<'a, T> {
+    fn drop(&mut self) {
+        let (log, start) = (self.log, self.start);
+        let end = start + self.values.len();
+
+        // SAFETY: The guard is dropped, so the values it handed out are not used anymore.
+        unsafe { log.data.release(start..end) };
+
+        if log
+            .len
+            .compare_exchange(end, start, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            return;
+        }
+
+        log.skip(start..end);
+    }
+}
+
+#[cfg(not(feature = "safe-impl"))]
+impl<'a, T> fmt::Debug for ClaimGuard<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClaimGuard")
+            .field("start", &self.start)
+            .field("len", &self.values.len())
+            .finish()
+    }
+}
+
+/// Iterator over the items in a Log, skipping slots which have not been written yet.
+pub struct LogSkippingIterator<'a, T> {
+    idx: usize,
+    gaps: Vec<usize>,
+    log: &'a Log<T>,
+}
+
+impl<'a, T> LogSkippingIterator<'a, T> {
+    /// Indices of the slots skipped so far, in ascending order.
+    pub fn gaps(&self) -> &[usize] {
+        &self.gaps
+    }
+
+    /// Read again the slots skipped so far.
+    ///
+    /// # Returns
+    /// The index and item of every skipped slot which has been written since. Slots which are still
+    /// empty are kept for a later call.
+    pub fn revisit(&mut self) -> Vec<(usize, &'a T)> {
+        let log = self.log;
+        let mut found = Vec::new();
+
+        self.gaps.retain(|&idx| match log.get(idx) {
+            Some(item) => {
+                found.push((idx, item));
+                false
+            }
+            None => true,
+        });
+
+        found
+    }
+}
+
+impl<'a, T> Iterator for LogSkippingIterator<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.idx < self.log.reserved_len() {
+            let idx = self.idx;
+            self.idx += 1;
+
+            match self.log.get(idx) {
+                Some(item) => return Some(item),
+                None => self.gaps.push(idx),
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use log::debug;
 
     use crate::sync::thread;
 
@@ -372,44 +2183,274 @@ mod test {
         loom::model(test_log_iter);
         loom::model(test_send_recv);
         loom::model(test_eventual_consistency);
+        loom::model(test_wait_for);
+        loom::model(test_log_committed_len);
+        #[cfg(not(feature = "safe-impl"))]
+        loom::model(test_log_claim_committed_len);
+        #[cfg(not(feature = "safe-impl"))]
+        loom::model(test_log_claim_visibility);
+    }
+
+    #[test]
+    fn test_log_capacity() {
+        init();
+
+        let log: Log<u32> = Log::new(0);
+
+        assert_eq!(log.capacity(), 1);
+    }
+
+    #[test]
+    fn test_log_capacity_excess() {
+        init();
+
+        let log = Log::new(1);
+
+        log.push(0).unwrap();
+
+        assert!(log.push(1).is_err());
+    }
+
+    #[test]
+    fn test_log_push_from() {
+        init();
+
+        let primary = Log::new(50);
+        let overflow = Log::new(50);
+
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                let (primary, overflow) = (&primary, &overflow);
+                s.spawn(move || {
+                    for i in 0..25 {
+                        // A rejected item stays in the Option, and goes to the overflow log instead.
+                        let mut item = Some(t * 25 + i);
+                        if primary.push_from(&mut item).is_err() {
+                            overflow.push_from(&mut item).unwrap();
+                        }
+                        assert_eq!(item, None);
+                    }
+                });
+            }
+        });
+
+        let mut items: Vec<_> = primary.iter().chain(overflow.iter()).copied().collect();
+        items.sort_unstable();
+
+        assert_eq!(items, (0..100).collect::<Vec<_>>());
+
+        // An empty Option is rejected, without reserving a slot.
+        let log: Log<usize> = Log::new(1);
+
+        assert!(matches!(
+            log.push_from(&mut None),
+            Err(LogError::LogRejected(()))
+        ));
+        assert_eq!(log.reserved_len(), 0);
+    }
+
+    /// Storage refusing to write at an index.
+    struct Refusing(Pages<usize>, usize);
+
+    impl Storage<usize> for Refusing {
+        fn capacity(&self) -> usize {
+            self.0.capacity()
+        }
+
+        fn read(&self, index: usize) -> Option<&usize> {
+            self.0.read(index)
+        }
+
+        fn write(&self, index: usize, value: usize) -> Result<(), usize> {
+            if index == self.1 {
+                return Err(value);
+            }
+
+            Storage::write(&self.0, index, value)
+        }
+
+        fn is_written(&self, index: usize) -> bool {
+            self.0.is_written(index)
+        }
+
+        fn clear(&mut self) {
+            Storage::clear(&mut self.0);
+        }
+    }
+
+    #[test]
+    fn test_log_refused_write() {
+        init();
+
+        let log = Log::with_storage(Refusing(Pages::new(3), 1));
+
+        // The refused slot is skipped, and the item goes to the next one.
+        assert_eq!(log.push(0).unwrap(), 0);
+        assert_eq!(log.push(1).unwrap(), 2);
+        assert!(matches!(log.push(2), Err(LogError::LogCapacityExceeded(2))));
+
+        assert_eq!(log.committed_len(), 3);
+        assert_eq!(log.get_range(..).collect::<Vec<_>>(), vec![&0, &1]);
+        assert_eq!(log.try_get(1), Err(RecvError::Closed(1)));
+    }
+
+    #[test]
+    fn test_log_capacity_excess_len() {
+        init();
+
+        let log = Log::new(1);
+
+        log.push(0).unwrap();
+        log.push(1).unwrap_err();
+        log.push(2).unwrap_err();
+        log.push(3).unwrap_err();
+        log.push(4).unwrap_err();
+
+        assert_eq!(log.len(), 1);
+    }
+
+    #[test]
+    fn test_local_log() {
+        init();
+
+        let mut local = LocalLog::new(3);
+
+        local.push(1).unwrap();
+        local.push(2).unwrap();
+
+        assert_eq!(local.get(1), Some(&2));
+        assert_eq!(local.get(2), None);
+
+        let log: Log<_> = local.into();
+
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.push(3).unwrap(), 2);
+        assert!(log.push(4).is_err());
+
+        let mut local: LocalLog<_> = log.into();
+
+        assert_eq!(local.len(), 3);
+        assert_eq!(local.get(2), Some(&3));
+        assert!(local.push(4).is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "safe-impl"))]
+    fn test_local_log_gap() {
+        init();
+
+        let log = Log::new(8);
+
+        // A claim which is never committed nor dropped leaves a gap before the next push.
+        log.push(1).unwrap();
+        std::mem::forget(log.claim(2).unwrap());
+        log.push(4).unwrap();
+
+        // Only the committed prefix is converted, and the slot written after the gap is freed.
+        let mut local: LocalLog<_> = log.into();
+
+        assert_eq!(local.len(), 1);
+        assert_eq!(local.get(3), None);
+        assert_eq!(local.push(5).unwrap(), 1);
+
+        let log: Log<_> = local.into();
+
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.get_range(..).collect::<Vec<_>>(), vec![&1, &5]);
+        assert_eq!(log.push(6).unwrap(), 2);
+    }
+
+    #[test]
+    #[cfg(not(feature = "safe-impl"))]
+    fn test_local_log_skipped() {
+        init();
+
+        let log = Log::new(8);
+
+        // The dropped claim is skipped after the gap left by the forgotten one.
+        log.push(1).unwrap();
+        std::mem::forget(log.claim(2).unwrap());
+        let claim = log.claim(2).unwrap();
+        log.push(5).unwrap();
+        drop(claim);
+
+        let mut local: LocalLog<_> = log.into();
+
+        // The skipped slots are pushed over like the others.
+        for i in 2..6 {
+            local.push(i).unwrap();
+        }
+
+        let log: Log<_> = local.into();
+        let range = log.get_range(..);
+
+        assert_eq!(log.len(), 5);
+        assert_eq!(range.len(), 5);
+        assert_eq!(range.collect::<Vec<_>>(), vec![&1, &2, &3, &4, &5]);
+        assert_eq!(log.iter().count(), 5);
     }
 
     #[test]
-    fn test_log_capacity() {
+    fn test_log_builder() {
         init();
 
-        let log: Log<u32> = Log::new(0);
+        let log: Log<u32> = LogBuilder::new().capacity(3).build().unwrap();
 
-        assert_eq!(log.capacity(), 1);
+        assert_eq!(log.capacity(), 3);
+
+        let err = LogBuilder::new().capacity(0).build::<u32>().unwrap_err();
+
+        assert!(matches!(err, ConfigError::InvalidCapacity(0)));
     }
 
     #[test]
-    fn test_log_capacity_excess() {
+    fn test_log_reset() {
         init();
 
-        let log = Log::new(1);
+        let mut log = Log::new(2);
 
-        log.push(0).unwrap();
+        log.push(Arc::new(0)).unwrap();
+        log.push(Arc::new(1)).unwrap();
+        log.push(Arc::new(2)).unwrap_err();
 
-        assert!(log.push(1).is_err());
+        let item = log.get(1).cloned().unwrap();
+
+        assert_eq!(Arc::strong_count(&item), 2);
+
+        log.reset();
+
+        assert_eq!(Arc::strong_count(&item), 1);
+        assert_eq!(log.epoch(), 1);
+        assert_eq!(log.len(), 0);
+        assert_eq!(log.get(0), None);
+        assert_eq!(log.push(Arc::new(3)).unwrap(), 0);
+        assert_eq!(log.get(0).map(|x| **x), Some(3));
     }
 
     #[test]
-    fn test_log_capacity_excess_len() {
+    fn test_log_reset_tagged() {
         init();
 
-        let log = Log::new(1);
+        let mut log = Log::new(2);
 
-        log.push(0).unwrap();
-        log.push(1).unwrap_err();
-        log.push(2).unwrap_err();
-        log.push(3).unwrap_err();
-        log.push(4).unwrap_err();
+        let stale = log.push_tagged(1).unwrap();
+        assert_eq!(stale.epoch(), 0);
+        assert_eq!(log.get_tagged(stale), Some(&1));
 
-        assert_eq!(log.len(), 1);
+        log.reset();
+
+        // The new item is pushed at the same index, but only its own tag reads it.
+        let fresh = log.push_tagged(2).unwrap();
+
+        assert_eq!(fresh.index(), stale.index());
+        assert_eq!(fresh.epoch(), 1);
+        assert_eq!(log.get(stale.index()), Some(&2));
+        assert_eq!(log.get_tagged(stale), None);
+        assert_eq!(log.get_tagged(fresh), Some(&2));
     }
 
     #[test]
+    #[allow(clippy::map_clone)]
     fn test_log_immutable_entries() {
         init();
 
@@ -461,6 +2502,450 @@ mod test {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn test_log_first_gap() {
+        init();
+
+        let log = Log::new(4);
+
+        log.push(0).unwrap();
+        log.push(1).unwrap();
+
+        assert_eq!(log.first_gap(), None);
+        assert!(log.verify().is_ok());
+
+        // Simulate a producer which reserved a slot but never wrote to it.
+        log.len.fetch_add(1, Ordering::Relaxed);
+        log.push(3).unwrap();
+
+        assert_eq!(log.first_gap(), Some(2));
+        assert_eq!(log.verify(), Err(GapError(2)));
+    }
+
+    #[test]
+    #[cfg(feature = "paranoid")]
+    #[should_panic(expected = "out-of-order read at index 1")]
+    fn test_log_paranoid_out_of_order() {
+        init();
+
+        let log = Log::new(4);
+
+        log.push(0).unwrap();
+
+        // Simulate a producer writing to the wrong slot.
+        log.len.fetch_add(1, Ordering::Relaxed);
+        let (page, position) = log.data.page(1).unwrap();
+        page.write(position, 2, 2).unwrap();
+
+        log.get(1);
+    }
+
+    #[test]
+    fn test_log_debug() {
+        init();
+
+        let log = Log::new(100);
+
+        assert_eq!(
+            format!("{:?}", log),
+            "Log { capacity: 100, len: 0, entries: [] }"
+        );
+
+        for i in 0..3 {
+            log.push(i).unwrap();
+        }
+
+        assert_eq!(
+            format!("{:?}", log),
+            "Log { capacity: 100, len: 3, entries: [0, 1, 2] }"
+        );
+
+        for i in 3..20 {
+            log.push(i).unwrap();
+        }
+
+        assert_eq!(
+            format!("{:?}", log),
+            "Log { capacity: 100, len: 20, entries: [0, 1, 2, 3, .., 16, 17, 18, 19] }"
+        );
+    }
+
+    #[test]
+    fn test_log_iter_skip_gaps() {
+        init();
+
+        let log = Log::new(4);
+
+        log.push(1).unwrap();
+
+        // Simulate a producer which reserved a slot but did not write to it yet.
+        log.len.fetch_add(1, Ordering::Relaxed);
+        log.push(3).unwrap();
+
+        let mut iter = log.iter_skip_gaps();
+
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.gaps(), &[1]);
+
+        assert!(iter.revisit().is_empty());
+
+        log.data.write(1, 2).unwrap();
+
+        assert_eq!(iter.revisit(), vec![(1, &2)]);
+        assert!(iter.gaps().is_empty());
+    }
+
+    #[test]
+    fn test_log_cursor() {
+        init();
+
+        let log = Log::new(3);
+        let mut fast = log.cursor();
+        let mut slow = log.cursor();
+
+        log.push(1).unwrap();
+        log.push(2).unwrap();
+
+        assert_eq!(fast.by_ref().count(), 2);
+        assert_eq!(fast.remaining(), 0);
+        assert_eq!(slow.remaining(), 2);
+        assert_eq!(slow.next(), Some(&1));
+
+        log.push(3).unwrap();
+
+        assert_eq!(fast.next(), Some(&3));
+        assert_eq!(fast.next_blocking(), None);
+        assert_eq!(slow.position(), 1);
+    }
+
+    #[test]
+    fn test_log_work_queue() {
+        init();
+
+        let log = Log::new(100);
+        for i in 0..100 {
+            log.push(i).unwrap();
+        }
+
+        let queue = log.work_queue();
+        let mut claimed: Vec<usize> = std::thread::scope(|s| {
+            let workers: Vec<_> = (0..4)
+                .map(|_| {
+                    s.spawn(|| {
+                        queue
+                            .recv_iter()
+                            .map(|(index, _)| index)
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .flat_map(|w| w.join().unwrap())
+                .collect()
+        });
+
+        claimed.sort_unstable();
+
+        assert_eq!(claimed, (0..100).collect::<Vec<_>>());
+        assert_eq!(queue.recv(), None);
+    }
+
+    #[test]
+    fn test_log_iter_snapshot() {
+        init();
+
+        let log = Log::new(4);
+
+        log.push(1).unwrap();
+        log.push(2).unwrap();
+
+        let mut iter = log.iter_snapshot();
+
+        assert_eq!(iter.next(), Some(&1));
+
+        log.push(3).unwrap();
+
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(log.iter().count(), 3);
+    }
+
+    #[test]
+    fn test_log_committed_len() {
+        init();
+
+        let log = Arc::new(Log::new(2));
+        let producer = log.clone();
+
+        let h1 = thread::spawn(move || {
+            producer.push(1).unwrap();
+        });
+
+        log.push(2).unwrap();
+
+        // Every index below the committed length is readable.
+        let len = log.len();
+        for index in 0..len {
+            assert!(log.get(index).is_some());
+        }
+
+        h1.join().unwrap();
+
+        assert_eq!(log.reserved_len(), 2);
+        assert_eq!(log.committed_len(), 2);
+    }
+
+    #[test]
+    fn test_log_get_range() {
+        init();
+
+        let log = Log::new(5);
+
+        for i in 0..4 {
+            log.push(i).unwrap();
+        }
+
+        assert_eq!(log.get_range(..).count(), 4);
+        assert_eq!(log.get_range(1..=2).collect::<Vec<_>>(), vec![&1, &2]);
+        assert_eq!(log.get_range(3..100).collect::<Vec<_>>(), vec![&3]);
+        assert_eq!(log.get_range(10..).count(), 0);
+
+        // Reserve a slot without writing to it.
+        log.len.fetch_add(1, Ordering::Relaxed);
+        log.push(5).unwrap_err();
+
+        assert_eq!(log.get_range(2..).collect::<Vec<_>>(), vec![&2, &3]);
+
+        // Iterators over the committed prefix are exact, and walk from both ends.
+        let mut iter = log.iter_from(1);
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next_back(), Some(&2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "safe-impl"))]
+    fn test_log_claim() {
+        init();
+
+        let log = Arc::new(Log::new(1_000));
+
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let log = log.clone();
+                thread::spawn(move || {
+                    while let Some(mut claim) = log.claim(5) {
+                        for (i, slot) in claim.slots().iter_mut().enumerate() {
+                            slot.write((t, i));
+                        }
+
+                        // SAFETY: Every slot has been written above.
+                        unsafe { claim.commit() };
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // Every batch was written to contiguous slots, and the log is full.
+        assert_eq!(log.len(), 1_000);
+        for batch in log.iter().collect::<Vec<_>>().chunks(5) {
+            assert!(batch
+                .iter()
+                .enumerate()
+                .all(|(i, &&(t, j))| t == batch[0].0 && i == j));
+        }
+
+        // A claim never overshoots the capacity.
+        let log: Log<u64> = Log::new(3);
+        log.push(0).unwrap();
+        assert!(log.claim(3).is_none());
+        assert_eq!(log.claim(2).map(|claim| claim.start()), Some(1));
+    }
+
+    #[test]
+    #[cfg(not(feature = "safe-impl"))]
+    fn test_log_claim_committed_len() {
+        init();
+
+        let log = Arc::new(Log::new(3));
+        let producer = log.clone();
+
+        let h1 = thread::spawn(move || {
+            let mut claim = producer.claim(2).unwrap();
+            for (i, slot) in claim.slots().iter_mut().enumerate() {
+                slot.write(i + 1);
+            }
+
+            // SAFETY: Every slot has been written above.
+            unsafe { claim.commit() };
+        });
+
+        log.push(0).unwrap();
+
+        // Whether the batch was committed in one step or by this push, every index below the
+        // committed length is readable.
+        let len = log.len();
+        for index in 0..len {
+            assert!(log.get(index).is_some());
+        }
+
+        h1.join().unwrap();
+
+        assert_eq!(log.committed_len(), 3);
+    }
+
+    #[test]
+    #[cfg(not(feature = "safe-impl"))]
+    fn test_log_claim_atomic() {
+        init();
+
+        let log = Log::new(8);
+
+        let mut first = log.claim(2).unwrap();
+        let mut second = log.claim(2).unwrap();
+        for (i, slot) in second.slots().iter_mut().enumerate() {
+            slot.write(i + 2);
+        }
+
+        // SAFETY: Every slot has been written above.
+        unsafe { second.commit() };
+
+        // The second batch is written, but held back by the first one: none of it is visible.
+        assert_eq!(log.len(), 0);
+        assert_eq!(log.get(2), None);
+        assert_eq!(log.get(3), None);
+
+        for (i, slot) in first.slots().iter_mut().enumerate() {
+            slot.write(i);
+        }
+
+        // SAFETY: Every slot has been written above.
+        unsafe { first.commit() };
+
+        assert_eq!(log.len(), 4);
+        assert_eq!(log.iter().collect::<Vec<_>>(), vec![&0, &1, &2, &3]);
+
+        // A claim is held by a single page: the end of the current one is skipped.
+        let log = Log::new(10_000);
+        let page_size = log.data.page_size();
+
+        let mut claim = log.claim(page_size - 2).unwrap();
+        for (i, slot) in claim.slots().iter_mut().enumerate() {
+            slot.write(i);
+        }
+
+        // SAFETY: Every slot has been written above.
+        unsafe { claim.commit() };
+
+        let claim = log.claim(4).unwrap();
+        assert_eq!(claim.start(), page_size);
+        assert_eq!(
+            log.try_get(page_size - 1),
+            Err(RecvError::Closed(page_size - 1))
+        );
+
+        drop(claim);
+        assert_eq!(log.len(), page_size);
+        assert!(log.claim(page_size + 1).is_none());
+        assert!(log.claim(0).is_none());
+    }
+
+    #[test]
+    #[cfg(not(feature = "safe-impl"))]
+    fn test_log_claim_visibility() {
+        init();
+
+        let log = Arc::new(Log::new(3));
+        let producer = log.clone();
+
+        let h1 = thread::spawn(move || {
+            let mut claim = producer.claim(2).unwrap();
+            for (i, slot) in claim.slots().iter_mut().enumerate() {
+                slot.write(i + 1);
+            }
+
+            // SAFETY: Every slot has been written above.
+            unsafe { claim.commit() };
+        });
+
+        // The batch lands either before or after the push.
+        let start = if log.push(0).unwrap() == 0 { 1 } else { 0 };
+
+        // Once part of the batch is visible, the whole batch and the slots before it are too.
+        if (start..start + 2).any(|index| log.get(index).is_some()) {
+            assert!((0..start + 2).all(|index| log.get(index).is_some()));
+        }
+
+        h1.join().unwrap();
+
+        assert_eq!(log.len(), 3);
+    }
+
+    #[test]
+    #[cfg(not(feature = "safe-impl"))]
+    fn test_log_claim_drop_last() {
+        init();
+
+        let log = Log::new(8);
+
+        // Nothing was reserved after the claim: its slots are given back.
+        log.push(1).unwrap();
+        drop(log.claim(2).unwrap());
+
+        assert_eq!(log.push(4).unwrap(), 1);
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.iter().collect::<Vec<_>>(), vec![&1, &4]);
+    }
+
+    #[test]
+    #[cfg(not(feature = "safe-impl"))]
+    fn test_log_claim_drop_skipped() {
+        init();
+
+        let log = Log::new(8);
+
+        log.push(1).unwrap();
+        let claim = log.claim(2).unwrap();
+        log.push(4).unwrap();
+
+        // The push is held back by the claim until it is dropped, then its slots are skipped.
+        assert_eq!(log.len(), 1);
+        drop(claim);
+        assert_eq!(log.len(), 4);
+
+        assert_eq!(log.get(1), None);
+        assert_eq!(log.try_get(2), Err(RecvError::Closed(2)));
+        assert_eq!(log.wait_for(1), None);
+
+        let range = log.get_range(..);
+        assert_eq!(range.len(), 2);
+        assert_eq!(range.collect::<Vec<_>>(), vec![&1, &4]);
+        assert_eq!(log.get_range(..).rev().collect::<Vec<_>>(), vec![&4, &1]);
+        assert_eq!(log.iter().collect::<Vec<_>>(), vec![&1, &4]);
+        assert_eq!(log.snapshot(), vec![1, 4]);
+
+        let mut cursor = log.cursor();
+        assert_eq!(cursor.next(), Some(&1));
+        assert_eq!(cursor.next(), Some(&4));
+        assert_eq!(cursor.next(), None);
+
+        // Pushes after the skipped slots are committed right away.
+        assert_eq!(log.push(5).unwrap(), 4);
+        assert_eq!(log.len(), 5);
+    }
+
     #[test]
     fn test_send_recv() {
         init();
@@ -482,6 +2967,53 @@ mod test {
     }
 
     #[test]
+    fn test_wait_for() {
+        init();
+
+        let (tx, rx) = open(2);
+
+        let h1 = thread::spawn(move || {
+            tx.send(1).unwrap();
+            tx.send(2).unwrap();
+        });
+
+        assert_eq!(rx.recv_blocking(1), Some(&2));
+        assert_eq!(rx.recv_blocking(0), Some(&1));
+        assert_eq!(rx.recv_blocking(2), None);
+
+        h1.join().unwrap();
+    }
+
+    #[test]
+    fn test_send_recv_disconnected() {
+        init();
+
+        let (tx, rx) = open(4);
+        let tx2 = tx.clone();
+
+        let h1 = thread::spawn(move || {
+            tx.send(1).unwrap();
+        });
+        h1.join().unwrap();
+
+        let rx2 = rx.clone();
+        let h2 = thread::spawn(move || rx2.recv_blocking(1).copied());
+
+        // The reader stops waiting once the last sender is gone.
+        drop(tx2);
+        assert_eq!(h2.join().unwrap(), None);
+        assert_eq!(rx.recv_blocking(0), Some(&1));
+
+        let log = rx.into_inner();
+        let tx = log.clone().into_sender();
+        assert!(matches!(tx.send(2), Err(LogError::LogDisconnected(2))));
+
+        // The log itself can still be written to.
+        assert_eq!(log.push(2).unwrap(), 1);
+    }
+
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_eventual_consistency() {
         init();
 
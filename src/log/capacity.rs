@@ -0,0 +1,52 @@
+//! Capacity presets, checked at compile time.
+//!
+//! A preset is a type carrying a capacity as a constant. Logs and Channels built from a preset get a
+//! capacity which is known to be valid when the crate using them compiles, instead of being clamped
+//! or rejected at runtime.
+//!
+//! # Examples
+//! ```
+//! use fremkit::bounded::{Log, RingLog};
+//! use fremkit::capacity::Pow2;
+//! use fremkit::unbounded::Channel;
+//!
+//! let log: Log<u64> = Log::with_preset::<Pow2<1024>>();
+//! assert_eq!(log.capacity(), 1024);
+//!
+//! let ring: RingLog<u64> = RingLog::with_preset::<Pow2<64>>();
+//! assert_eq!(ring.capacity(), 64);
+//!
+//! let channel: Channel<u64> = Channel::with_segment_preset::<Pow2<256>>();
+//! assert_eq!(channel.segment_capacity(), 256);
+//! ```
+//!
+//! A capacity which is not a power of two fails to compile:
+//! ```compile_fail
+//! use fremkit::bounded::Log;
+//! use fremkit::capacity::Pow2;
+//!
+//! let log: Log<u64> = Log::with_preset::<Pow2<1000>>();
+//! ```
+
+/// A capacity known at compile time.
+pub trait Capacity {
+    /// The capacity. Never 0.
+    const CAPACITY: usize;
+}
+
+/// A power of two capacity.
+///
+/// Indices into a power of two capacity are mapped to slots with a mask instead of a division:
+/// a `RingLog` with such a capacity finds the slot of an index without dividing by its capacity.
+///
+/// Using a `Pow2<N>` where `N` is not a power of two is a compile-time error.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pow2<const N: usize>;
+
+impl<const N: usize> Capacity for Pow2<N> {
+    const CAPACITY: usize = {
+        // Associated constants are evaluated at compile time: this never panics at runtime.
+        assert!(N.is_power_of_two(), "capacity must be a power of two");
+        N
+    };
+}
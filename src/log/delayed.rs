@@ -0,0 +1,239 @@
+//! This module contains the timer wheel holding the delayed items of a `Channel`.
+//!
+//! Delayed items are kept out of the channel until they are due. The wheel does not run on its own
+//! thread: due items are published by the next push on the channel, or by `Channel::publish_due`.
+
+use crate::sync::{AtomicU64, Ordering};
+use crate::unbounded::Channel;
+use crate::LogError;
+
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Number of slots of the wheel.
+const WHEEL_SLOTS: usize = 256;
+
+/// Duration covered by a slot of the wheel.
+const TICK: Duration = Duration::from_millis(1);
+
+/// Delayed items, hashed by the tick they are due at.
+pub(crate) struct TimerWheel<T> {
+    start: Instant,
+    /// Nanoseconds from `start` to the next tick to check, or `u64::MAX` if the wheel is empty.
+    next_check: AtomicU64,
+    inner: Mutex<Wheel<T>>,
+}
+
+struct Wheel<T> {
+    slots: Vec<Vec<(Instant, T)>>,
+    /// Every slot before this tick has been checked.
+    tick: u64,
+    len: usize,
+}
+
+impl<T> TimerWheel<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            next_check: AtomicU64::new(u64::MAX),
+            inner: Mutex::new(Wheel {
+                slots: (0..WHEEL_SLOTS).map(|_| Vec::new()).collect(),
+                tick: 0,
+                len: 0,
+            }),
+        }
+    }
+
+    /// Get the number of items waiting in the wheel.
+    pub(crate) fn len(&self) -> usize {
+        self.inner.lock().len
+    }
+
+    #[inline]
+    fn nanos(&self, at: Instant) -> u64 {
+        at.saturating_duration_since(self.start).as_nanos() as u64
+    }
+
+    #[inline]
+    fn tick_of(&self, at: Instant) -> u64 {
+        self.nanos(at) / TICK.as_nanos() as u64
+    }
+
+    /// Hold an item until `at`.
+    pub(crate) fn schedule(&self, value: T, at: Instant) {
+        let mut wheel = self.inner.lock();
+
+        let tick = self.tick_of(at).max(wheel.tick);
+        wheel.slots[tick as usize % WHEEL_SLOTS].push((at, value));
+        wheel.len += 1;
+
+        self.next_check
+            .fetch_min(tick * TICK.as_nanos() as u64, Ordering::Release);
+    }
+
+    /// Hand every item due at `now` to `publish`, in deadline order.
+    ///
+    /// `publish` is called with the wheel locked, so concurrent callers do not interleave their
+    /// items.
+    pub(crate) fn take_due(&self, now: Instant, mut publish: impl FnMut(T)) {
+        if self.nanos(now) < self.next_check.load(Ordering::Acquire) {
+            return;
+        }
+
+        let mut wheel = self.inner.lock();
+        let target = self.tick_of(now);
+
+        // After a long pause, every slot is checked once instead of once per elapsed tick.
+        let ticks = (target.saturating_sub(wheel.tick) + 1).min(WHEEL_SLOTS as u64);
+
+        let mut due = Vec::new();
+        for tick in wheel.tick..wheel.tick + ticks {
+            let slot = &mut wheel.slots[tick as usize % WHEEL_SLOTS];
+
+            let mut index = 0;
+            while index < slot.len() {
+                if slot[index].0 <= now {
+                    due.push(slot.swap_remove(index));
+                } else {
+                    index += 1;
+                }
+            }
+        }
+
+        wheel.tick = wheel.tick.max(target);
+        wheel.len -= due.len();
+
+        // Items due later within the current tick are published on the next tick.
+        let next_check = if wheel.len > 0 {
+            (wheel.tick + 1) * TICK.as_nanos() as u64
+        } else {
+            u64::MAX
+        };
+        self.next_check.store(next_check, Ordering::Release);
+
+        due.sort_by_key(|&(at, _)| at);
+        for (_, value) in due {
+            publish(value);
+        }
+    }
+}
+
+/// Delayed publication.
+///
+/// A delayed item is not in the channel until it is due: readers never see it early, and it gets
+/// its index when it is published. Due items are published by the next push on the channel, or by
+/// a call to `publish_due`, in deadline order. Deadlines are tracked with a resolution of 1ms.
+impl<T> Channel<T> {
+    /// Append an item once `delay` has elapsed, if it passes the validators of the channel.
+    ///
+    /// # Returns
+    /// An error containing the item if it was rejected or the channel is closed.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    ///
+    /// use fremkit::unbounded::Channel;
+    ///
+    /// let channel: Channel<&str> = Channel::new();
+    ///
+    /// channel.push_after("retry", Duration::from_millis(10)).unwrap();
+    /// channel.push("now").unwrap();
+    ///
+    /// assert_eq!(channel.iter().collect::<Vec<_>>(), vec![&"now"]);
+    /// assert_eq!(channel.scheduled_len(), 1);
+    ///
+    /// thread::sleep(Duration::from_millis(20));
+    /// channel.publish_due();
+    ///
+    /// assert_eq!(channel.iter().collect::<Vec<_>>(), vec![&"now", &"retry"]);
+    /// ```
+    pub fn push_after(&self, value: T, delay: Duration) -> Result<(), LogError<T>> {
+        self.push_at(value, Instant::now() + delay)
+    }
+
+    /// Append an item once `at` is reached, if it passes the validators of the channel. An item due
+    /// already is appended right away.
+    ///
+    /// # Returns
+    /// An error containing the item if it was rejected or the channel is closed.
+    pub fn push_at(&self, value: T, at: Instant) -> Result<(), LogError<T>> {
+        if at <= Instant::now() {
+            return self.push(value).map(|_| ());
+        }
+
+        if self.is_closed() {
+            return Err(LogError::LogClosed(value));
+        }
+
+        self.timer_wheel().schedule(self.validate(value)?, at);
+
+        Ok(())
+    }
+
+    /// Get the number of delayed items which have not been published yet.
+    pub fn scheduled_len(&self) -> usize {
+        self.scheduled().map_or(0, TimerWheel::len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_channel_push_after() {
+        init();
+
+        let channel = Channel::with_segment_capacity(2);
+        let now = Instant::now();
+
+        channel.push_at(3, now + Duration::from_millis(30)).unwrap();
+        channel.push_at(2, now + Duration::from_millis(20)).unwrap();
+        channel.push_at(9, now + Duration::from_secs(3600)).unwrap();
+        channel.push_at(0, now).unwrap();
+
+        assert_eq!(channel.iter().collect::<Vec<_>>(), vec![&0]);
+        assert_eq!(channel.scheduled_len(), 3);
+
+        thread::sleep(Duration::from_millis(40));
+
+        // The push publishes the due items first, in deadline order.
+        channel.push(4).unwrap();
+
+        assert_eq!(channel.iter().collect::<Vec<_>>(), vec![&0, &2, &3, &4]);
+        assert_eq!(channel.scheduled_len(), 1);
+
+        channel.publish_due();
+        assert_eq!(channel.len(), 4);
+    }
+
+    #[test]
+    fn test_channel_push_at_closed() {
+        init();
+
+        let channel = Channel::new();
+        let later = Instant::now() + Duration::from_secs(3600);
+
+        assert!(channel.push_at(1, later).is_ok());
+        channel.close();
+
+        assert!(matches!(
+            channel.push_at(2, later),
+            Err(LogError::LogClosed(2))
+        ));
+        assert!(matches!(
+            channel.push_after(3, Duration::ZERO),
+            Err(LogError::LogClosed(3))
+        ));
+        assert_eq!(channel.scheduled_len(), 1);
+    }
+}
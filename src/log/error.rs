@@ -1,9 +1,124 @@
+use crate::log::growth::GrowthPolicy;
+
+#[cfg(feature = "wal")]
+use std::io;
+
 use thiserror::Error;
 
 /// Error type for Log
+///
+/// This is the error type of every log of the crate, bounded or not. New variants may be added.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum LogError<T> {
     /// Log is full. Push operation are not allowed anymore.
     #[error("Log is full.")]
     LogCapacityExceeded(T),
+
+    /// The item at this index has been overwritten by a RingLog which wrapped around.
+    #[error("Log has overwritten the item at index {0}.")]
+    LogLapped(usize),
+
+    /// The item was rejected by the validator of a Channel.
+    #[error("Item rejected by the Channel validator.")]
+    LogRejected(T),
+
+    /// The Channel has been closed. Push operations are not allowed anymore.
+    #[error("Channel is closed.")]
+    LogClosed(T),
+
+    /// Every Receiver of the Log has been dropped. Send operations are not allowed anymore.
+    #[error("Log has no receiver left.")]
+    LogDisconnected(T),
+}
+
+impl<T> LogError<T> {
+    /// Recover the item which could not be pushed, if the error holds one.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(1);
+    /// log.push(1).unwrap();
+    ///
+    /// let err = log.push(2).unwrap_err();
+    /// assert_eq!(err.into_inner(), Some(2));
+    /// ```
+    pub fn into_inner(self) -> Option<T> {
+        match self {
+            LogError::LogCapacityExceeded(value)
+            | LogError::LogRejected(value)
+            | LogError::LogClosed(value)
+            | LogError::LogDisconnected(value) => Some(value),
+            LogError::LogLapped(_) => None,
+        }
+    }
+
+    /// Convert the item held by the error, if any, keeping the variant.
+    ///
+    /// This is for wrappers pushing their items in an envelope, to hand the item back to the caller.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> LogError<U> {
+        match self {
+            LogError::LogCapacityExceeded(value) => LogError::LogCapacityExceeded(f(value)),
+            LogError::LogLapped(index) => LogError::LogLapped(index),
+            LogError::LogRejected(value) => LogError::LogRejected(f(value)),
+            LogError::LogClosed(value) => LogError::LogClosed(f(value)),
+            LogError::LogDisconnected(value) => LogError::LogDisconnected(f(value)),
+        }
+    }
+}
+
+/// Error type for the builders of the crate: the configuration cannot be used.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// The capacity of a Log, or of the first segment of a Channel, must be greater than 0.
+    #[error("Invalid capacity: {0}.")]
+    InvalidCapacity(usize),
+
+    /// The growth policy of a Channel cannot size its segments.
+    #[error("Invalid growth policy: {0:?}.")]
+    InvalidGrowthPolicy(GrowthPolicy),
+
+    /// The directory of a persisted Channel cannot be opened, or holds invalid segment files.
+    #[cfg(feature = "wal")]
+    #[error("Cannot open the persisted Channel: {0}")]
+    Persistence(#[from] io::Error),
+}
+
+/// Error returned by `Log::verify`: the slot at this index, below the reserved length of the Log,
+/// has not been written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Log has an unwritten slot at index {0}.")]
+pub struct GapError(pub usize);
+
+/// Error type for reads which can wait for an item, or find it unavailable.
+///
+/// Locks recover from poisoning: `Closed` only means that no item can ever be pushed at the index
+/// which was read, because it is beyond the capacity of a Log, its slot was skipped by a dropped
+/// claim, or the Channel has been closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum RecvError {
+    /// No item is available yet.
+    #[error("No item is available yet.")]
+    Empty,
+
+    /// The subscriber fell too far behind, and was dropped.
+    #[error("Subscriber {0} lagged behind and was dropped.")]
+    Lagged(usize),
+
+    /// The index is beyond the capacity of the Log, or the Channel was closed before it: no item
+    /// will ever be available.
+    #[error("No item can be pushed at index {0}.")]
+    Closed(usize),
+
+    /// No item became available before the timeout.
+    #[error("No item became available before the timeout.")]
+    Timeout,
+
+    /// The wait was cancelled through its `CancelToken`.
+    #[error("The wait was cancelled.")]
+    Cancelled,
 }
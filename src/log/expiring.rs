@@ -0,0 +1,104 @@
+//! This module contains the `Expiring` wrapper, giving entries of a `Channel` a time to live.
+
+use crate::unbounded::Channel;
+use crate::LogError;
+
+use std::time::{Duration, Instant};
+
+/// An item which expires after a deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expiring<T> {
+    value: T,
+    deadline: Instant,
+}
+
+impl<T> Expiring<T> {
+    /// Wrap an item, expiring `ttl` from now.
+    pub fn new(value: T, ttl: Duration) -> Self {
+        Self {
+            value,
+            deadline: Instant::now() + ttl,
+        }
+    }
+
+    /// Get the instant after which the item is expired.
+    #[inline]
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Is the item expired at the given instant ?
+    #[inline]
+    pub fn is_expired_at(&self, now: Instant) -> bool {
+        now >= self.deadline
+    }
+
+    /// Get the item, if it is not expired at the given instant.
+    #[inline]
+    pub fn live_at(&self, now: Instant) -> Option<&T> {
+        (!self.is_expired_at(now)).then_some(&self.value)
+    }
+}
+
+/// A Channel of expiring entries.
+///
+/// Expired entries are never removed, since the channel is append-only, but `get_live` and
+/// `iter_live` skip them.
+impl<T> Channel<Expiring<T>> {
+    /// Append an item which expires `ttl` from now.
+    ///
+    /// # Returns
+    /// The index of the item, or an error containing the item if it was rejected or the channel is
+    /// closed.
+    pub fn push_with_ttl(&self, value: T, ttl: Duration) -> Result<usize, LogError<T>> {
+        self.push(Expiring::new(value, ttl))
+            .map_err(|err| err.map(|expiring| expiring.value))
+    }
+
+    /// Get the item at an index, if it is available and not expired.
+    pub fn get_live(&self, index: usize) -> Option<&T> {
+        self.get(index)?.live_at(Instant::now())
+    }
+
+    /// Iterate over the items which are not expired, along with their index.
+    ///
+    /// Expiration is checked against the instant the iterator was created.
+    pub fn iter_live(&self) -> impl Iterator<Item = (usize, &T)> {
+        let now = Instant::now();
+
+        self.iter()
+            .enumerate()
+            .filter_map(move |(index, item)| Some((index, item.live_at(now)?)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_channel_ttl() {
+        init();
+
+        let channel = Channel::with_segment_capacity(2);
+
+        channel.push_with_ttl("ephemeral", Duration::ZERO).unwrap();
+        channel
+            .push_with_ttl("durable", Duration::from_secs(3600))
+            .unwrap();
+        channel.push_with_ttl("ephemeral", Duration::ZERO).unwrap();
+
+        assert_eq!(channel.len(), 3);
+        assert_eq!(channel.get_live(0), None);
+        assert_eq!(channel.get_live(1), Some(&"durable"));
+        assert_eq!(channel.get_live(3), None);
+        assert_eq!(
+            channel.iter_live().collect::<Vec<_>>(),
+            vec![(1, &"durable")]
+        );
+    }
+}
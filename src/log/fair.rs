@@ -0,0 +1,166 @@
+//! This module contains the implementation of the `FairLog` type.
+
+use crate::bounded::Log;
+use crate::sync::{AtomicUsize, Ordering};
+use crate::LogError;
+
+use std::fmt;
+
+use cache_padded::CachePadded;
+
+/// A bounded Log shared by a fixed set of producers, each entitled to an equal share of its slots.
+///
+/// In a plain Log, the fastest producer takes as many slots as it can reserve, and can starve the
+/// others once the log fills up. A FairLog gives every producer a quota of `capacity / producers`
+/// slots: a producer which used up its quota gets an error, while the others can still push. Slots
+/// left unused by a producer are not handed over to the others.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::FairLog;
+///
+/// let log = FairLog::new(4, 2);
+///
+/// log.push(0, "hot").unwrap();
+/// log.push(0, "hot").unwrap();
+/// assert!(log.push(0, "hot").is_err());
+///
+/// assert!(log.push(1, "cold").is_ok());
+/// ```
+pub struct FairLog<T> {
+    log: Log<T>,
+    quota: usize,
+    pushed: Box<[CachePadded<AtomicUsize>]>,
+}
+
+impl<T> FairLog<T> {
+    /// Create a new FairLog.
+    ///
+    /// # Arguments
+    /// * `capacity` - The capacity of the underlying Log.
+    /// * `producers` - The number of producers, at least 1.
+    pub fn new(capacity: usize, producers: usize) -> Self {
+        let producers = producers.max(1);
+
+        Self {
+            log: Log::new(capacity),
+            quota: capacity / producers,
+            pushed: (0..producers)
+                .map(|_| CachePadded::new(AtomicUsize::new(0)))
+                .collect(),
+        }
+    }
+
+    /// Get the underlying Log, to read from it.
+    #[inline]
+    pub fn log(&self) -> &Log<T> {
+        &self.log
+    }
+
+    /// Get the number of producers.
+    #[inline]
+    pub fn producers(&self) -> usize {
+        self.pushed.len()
+    }
+
+    /// Get the number of slots each producer is entitled to.
+    #[inline]
+    pub fn quota(&self) -> usize {
+        self.quota
+    }
+
+    /// Get the number of items pushed by a producer. Unknown producers never pushed any.
+    pub fn pushed(&self, producer: usize) -> usize {
+        self.pushed
+            .get(producer)
+            .map_or(0, |pushed| pushed.load(Ordering::Relaxed))
+    }
+
+    /// Append an item to the log, on behalf of a producer.
+    ///
+    /// # Returns
+    /// The index of the item, or an error if the producer used up its quota. A producer which is not
+    /// lower than the number of producers has no quota.
+    pub fn push(&self, producer: usize, value: T) -> Result<usize, LogError<T>> {
+        let quota = self.quota;
+
+        let Some(pushed) = self.pushed.get(producer) else {
+            return Err(LogError::LogCapacityExceeded(value));
+        };
+
+        if pushed
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                (n < quota).then_some(n + 1)
+            })
+            .is_err()
+        {
+            return Err(LogError::LogCapacityExceeded(value));
+        }
+
+        self.log.push(value)
+    }
+}
+
+impl<T> fmt::Debug for FairLog<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FairLog")
+            .field("quota", &self.quota)
+            .field(
+                "pushed",
+                &(0..self.producers())
+                    .map(|p| self.pushed(p))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_fair_log_skew() {
+        init();
+
+        const PRODUCERS: usize = 4;
+
+        let log = FairLog::new(4_000, PRODUCERS);
+
+        // Producer 0 pushes in a tight loop, the others yield between pushes.
+        thread::scope(|s| {
+            for id in 0..PRODUCERS {
+                let log = &log;
+                s.spawn(move || {
+                    while log.push(id, id).is_ok() {
+                        if id > 0 {
+                            thread::yield_now();
+                        }
+                    }
+                });
+            }
+        });
+
+        let mut counts = [0; PRODUCERS];
+        for &id in log.log().iter() {
+            counts[id] += 1;
+        }
+
+        // The hot producer did not take more than its share.
+        assert_eq!(counts, [1_000; PRODUCERS]);
+        assert_eq!(log.log().len(), 4_000);
+
+        // An unknown producer has no quota.
+        assert!(matches!(
+            log.push(PRODUCERS, 0),
+            Err(LogError::LogCapacityExceeded(0))
+        ));
+        assert_eq!(log.pushed(PRODUCERS), 0);
+    }
+}
@@ -0,0 +1,243 @@
+//! This module contains the filtered subscriptions of a `Channel`.
+//!
+//! A filtered subscription holds a predicate and a Channel of indices. Every item published on the
+//! channel is checked once against the predicate of each subscription, and the index of a matching
+//! item is pushed to the subscription. Readers then only go through the items they are interested
+//! in, instead of each of them scanning the whole channel.
+
+use crate::sync::{AtomicUsize, Ordering};
+use crate::unbounded::Channel;
+
+use std::fmt;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+/// A filtered subscription: its predicate, and the indices of the items matching it.
+struct Filter<T> {
+    predicate: Box<dyn Fn(&T) -> bool + Send + Sync>,
+    indices: Channel<usize>,
+}
+
+/// The filtered subscriptions of a Channel.
+pub(crate) struct Filters<T> {
+    count: AtomicUsize,
+    list: RwLock<Vec<Arc<Filter<T>>>>,
+}
+
+impl<T> Filters<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+            list: RwLock::new(Vec::new()),
+        }
+    }
+
+    fn add(&self, filter: Arc<Filter<T>>) {
+        let mut list = self.list.write();
+
+        list.push(filter);
+        self.count.store(list.len(), Ordering::Release);
+    }
+
+    fn remove(&self, filter: &Arc<Filter<T>>) {
+        let mut list = self.list.write();
+
+        list.retain(|f| !Arc::ptr_eq(f, filter));
+        self.count.store(list.len(), Ordering::Release);
+    }
+
+    /// Is there any subscription to route items to ?
+    #[inline]
+    pub(crate) fn is_active(&self) -> bool {
+        self.count.load(Ordering::Acquire) > 0
+    }
+
+    /// Push the index of a newly published item to every subscription it matches.
+    pub(crate) fn route(&self, index: usize, item: &T) {
+        for filter in self.list.read().iter() {
+            if (filter.predicate)(item) {
+                // The subscription is only closed along with the channel.
+                let _ = filter.indices.push(index);
+            }
+        }
+    }
+
+    /// Close every subscription, so their readers stop waiting.
+    pub(crate) fn close(&self) {
+        for filter in self.list.read().iter() {
+            filter.indices.close();
+        }
+    }
+}
+
+/// Filtered subscriptions.
+impl<T: 'static> Channel<T> {
+    /// Subscribe to the items matching a predicate.
+    ///
+    /// The predicate is run once on every item published after the subscription is created, by the
+    /// producer publishing it. Items which were published before are not part of the subscription.
+    ///
+    /// Matching items are received in the order they were published. Between concurrent producers,
+    /// this can differ slightly from the order of their indices.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::unbounded::Channel;
+    ///
+    /// let channel: Channel<(&str, u64)> = Channel::new();
+    /// let mut orders = channel.subscribe_filtered(|&(topic, _)| topic == "orders");
+    ///
+    /// channel.push(("orders", 1)).unwrap();
+    /// channel.push(("audit", 2)).unwrap();
+    /// channel.push(("orders", 3)).unwrap();
+    ///
+    /// assert_eq!(
+    ///     orders.by_ref().collect::<Vec<_>>(),
+    ///     vec![(0, &("orders", 1)), (2, &("orders", 3))]
+    /// );
+    /// ```
+    pub fn subscribe_filtered<F>(&self, predicate: F) -> FilteredSubscription<'_, T>
+    where
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        let filter = Arc::new(Filter {
+            predicate: Box::new(predicate),
+            indices: Channel::new(),
+        });
+
+        self.filters().add(filter.clone());
+
+        // The channel may have been closed before the subscription was registered.
+        if self.is_closed() {
+            filter.indices.close();
+        }
+
+        FilteredSubscription {
+            idx: 0,
+            filter,
+            channel: self,
+        }
+    }
+}
+
+/// Reader of the items of a Channel matching a predicate, returned by `Channel::subscribe_filtered`.
+///
+/// Like a cursor, reaching the end of the subscription is not final: `next` returns `None` until
+/// another matching item is published. Dropping the subscription stops the filtering.
+pub struct FilteredSubscription<'a, T> {
+    idx: usize,
+    filter: Arc<Filter<T>>,
+    channel: &'a Channel<T>,
+}
+
+impl<'a, T> FilteredSubscription<'a, T> {
+    /// Get the number of matching items read so far.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.idx
+    }
+
+    /// Read the next matching item, blocking until it is published.
+    ///
+    /// # Returns
+    /// The index and the item, or `None` once the channel is closed and every matching item has
+    /// been read.
+    pub fn recv(&mut self) -> Option<(usize, &'a T)> {
+        let index = *self.filter.indices.wait_for(self.idx)?;
+        self.idx += 1;
+
+        Some((index, self.item(index)?))
+    }
+
+    /// Get a matching item. Its index is only routed once the item has been written, so it is
+    /// always found.
+    fn item(&self, index: usize) -> Option<&'a T> {
+        self.channel.get(index)
+    }
+}
+
+impl<'a, T> Iterator for FilteredSubscription<'a, T> {
+    type Item = (usize, &'a T);
+
+    /// Read the next matching item, if it has been published.
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = *self.filter.indices.get(self.idx)?;
+        self.idx += 1;
+
+        Some((index, self.item(index)?))
+    }
+}
+
+impl<'a, T> Drop for FilteredSubscription<'a, T> {
+    fn drop(&mut self) {
+        self.channel.filters().remove(&self.filter);
+    }
+}
+
+impl<'a, T> fmt::Debug for FilteredSubscription<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilteredSubscription")
+            .field("position", &self.idx)
+            .field("matched", &self.filter.indices.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_channel_subscribe_filtered() {
+        init();
+
+        let channel: Channel<usize> = Channel::with_segment_capacity(16);
+
+        channel.push(0).unwrap();
+
+        let mut even = channel.subscribe_filtered(|i| i % 2 == 0);
+        let mut tens = channel.subscribe_filtered(|i| i % 10 == 0);
+
+        thread::scope(|s| {
+            for t in 0..4 {
+                let channel = &channel;
+                s.spawn(move || {
+                    for i in 0..250 {
+                        channel.push(1 + t * 250 + i).unwrap();
+                    }
+                });
+            }
+
+            let mut received = Vec::new();
+            while received.len() < 500 {
+                let (index, &item) = even.recv().unwrap();
+                assert_eq!(channel.get(index), Some(&item));
+                received.push(item);
+            }
+
+            // The item pushed before the subscription is not part of it.
+            received.sort_unstable();
+            assert_eq!(
+                received,
+                (1..=1000).filter(|i| i % 2 == 0).collect::<Vec<_>>()
+            );
+        });
+
+        assert_eq!(tens.by_ref().count(), 100);
+
+        drop(tens);
+        channel.close();
+
+        assert_eq!(even.recv(), None);
+        assert!(channel.filters().is_active());
+        drop(even);
+        assert!(!channel.filters().is_active());
+    }
+}
@@ -0,0 +1,196 @@
+//! This module contains the `GrowthPolicy` type, sizing the segments of a Channel.
+
+/// How the capacity of each new segment of a Channel is chosen, from the capacity of the segment it
+/// follows.
+///
+/// A channel holding `n` items in segments of constant size links `n / segment_capacity` segments.
+/// Doubling the capacity keeps it to about `log2(n)` segments, at the cost of larger segments, and
+/// capping it bounds the size of a single segment.
+///
+/// # Examples
+/// ```
+/// use fremkit::unbounded::{Channel, GrowthPolicy};
+///
+/// let channel: Channel<u64> = Channel::with_policy(2, GrowthPolicy::Capped(8));
+/// for i in 0..30 {
+///     channel.push(i).unwrap();
+/// }
+///
+/// assert_eq!(channel.segment_sizes().collect::<Vec<_>>(), vec![2, 4, 8, 8, 8]);
+/// assert_eq!(channel.get(29), Some(&29));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GrowthPolicy {
+    /// Every segment has the capacity of the first one.
+    #[default]
+    Constant,
+    /// Every segment is twice as large as the previous one.
+    Doubling,
+    /// Every segment is twice as large as the previous one, up to the given capacity.
+    ///
+    /// A first segment larger than the cap is followed by segments of the cap.
+    Capped(usize),
+}
+
+impl GrowthPolicy {
+    /// Get the capacity of the segment following a segment of `previous` items.
+    #[inline]
+    pub fn next_capacity(&self, previous: usize) -> usize {
+        match *self {
+            GrowthPolicy::Constant => previous,
+            GrowthPolicy::Doubling => previous.saturating_mul(2),
+            GrowthPolicy::Capped(max) => previous.saturating_mul(2).min(max.max(1)),
+        }
+    }
+}
+
+/// The capacity and offset of every segment of a Channel, from the capacity of its first segment and
+/// its growth policy.
+///
+/// Every policy doubles the capacity of a number of segments, possibly none, before keeping it
+/// constant, so the segment holding an index is found in constant time.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Layout {
+    first: usize,
+    policy: GrowthPolicy,
+    /// Number of segments doubling their capacity, from the first one.
+    doubling: usize,
+    /// Offset of the first segment of constant capacity.
+    boundary: usize,
+    /// Capacity of the segments from `boundary`.
+    steady: usize,
+}
+
+impl Layout {
+    pub(crate) fn new(first: usize, policy: GrowthPolicy) -> Self {
+        let first = first.max(1);
+
+        let (doubling, steady) = match policy {
+            GrowthPolicy::Constant => (0, first),
+            GrowthPolicy::Doubling => (usize::BITS as usize, usize::MAX),
+            GrowthPolicy::Capped(max) => {
+                let max = max.max(1);
+
+                // The first segment reaching the cap is clipped to it, as well as the next ones.
+                let doubling = (1..usize::BITS as usize)
+                    .find(|&k| first.checked_mul(1 << k).is_none_or(|size| size >= max))
+                    .unwrap_or(usize::BITS as usize);
+
+                (doubling, max)
+            }
+        };
+
+        // The doubling segments hold `first * (2^doubling - 1)` items.
+        let boundary = 1usize
+            .checked_shl(doubling as u32)
+            .and_then(|pow| first.checked_mul(pow - 1))
+            .unwrap_or(usize::MAX);
+
+        Self {
+            first,
+            policy,
+            doubling,
+            boundary,
+            steady,
+        }
+    }
+
+    /// Get the capacity of the first segment.
+    #[inline]
+    pub(crate) fn first(&self) -> usize {
+        self.first
+    }
+
+    /// Get the policy sizing the segments after the first one.
+    #[inline]
+    pub(crate) fn policy(&self) -> GrowthPolicy {
+        self.policy
+    }
+
+    /// Get the capacity of a segment.
+    #[inline]
+    pub(crate) fn capacity(&self, number: usize) -> usize {
+        if number >= self.doubling {
+            self.steady
+        } else {
+            self.first << number
+        }
+    }
+
+    /// Get the index of the first item of a segment.
+    #[inline]
+    pub(crate) fn offset(&self, number: usize) -> usize {
+        if number >= self.doubling {
+            self.boundary + (number - self.doubling) * self.steady
+        } else {
+            self.first * ((1 << number) - 1)
+        }
+    }
+
+    /// Get the number of the segment holding an index.
+    #[inline]
+    pub(crate) fn locate(&self, index: usize) -> usize {
+        if index >= self.boundary {
+            self.doubling + (index - self.boundary) / self.steady
+        } else {
+            // Segment `k` starts at `first * (2^k - 1)`.
+            let shifted = index / self.first + 1;
+
+            (usize::BITS - 1 - shifted.leading_zeros()) as usize
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_growth_policy() {
+        init();
+
+        assert_eq!(GrowthPolicy::Constant.next_capacity(3), 3);
+        assert_eq!(GrowthPolicy::Doubling.next_capacity(3), 6);
+        assert_eq!(GrowthPolicy::Doubling.next_capacity(usize::MAX), usize::MAX);
+        assert_eq!(GrowthPolicy::Capped(5).next_capacity(2), 4);
+        assert_eq!(GrowthPolicy::Capped(5).next_capacity(4), 5);
+        assert_eq!(GrowthPolicy::Capped(5).next_capacity(16), 5);
+        assert_eq!(GrowthPolicy::Capped(0).next_capacity(4), 1);
+    }
+
+    #[test]
+    fn test_layout() {
+        init();
+
+        let policies = [
+            GrowthPolicy::Constant,
+            GrowthPolicy::Doubling,
+            GrowthPolicy::Capped(0),
+            GrowthPolicy::Capped(5),
+            GrowthPolicy::Capped(8),
+            GrowthPolicy::Capped(100),
+        ];
+
+        for policy in policies {
+            for first in [1, 2, 3, 8, 10] {
+                let layout = Layout::new(first, policy);
+                let (mut offset, mut capacity) = (0, first);
+
+                // The layout agrees with growing the segments one by one.
+                for number in 0..20 {
+                    assert_eq!(layout.offset(number), offset);
+                    assert_eq!(layout.capacity(number), capacity);
+                    assert_eq!(layout.locate(offset), number);
+                    assert_eq!(layout.locate(offset + capacity - 1), number);
+
+                    offset += capacity;
+                    capacity = policy.next_capacity(capacity);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! This module reports the activity of Logs and Channels through the `metrics` facade, enabled by
+//! the `metrics` feature.
+//!
+//! Nothing is recorded until the application installs a `metrics` recorder, such as a Prometheus
+//! exporter. The metrics are:
+//!
+//! * `fremkit_channel_pushes_total` - Items pushed on a Channel.
+//! * `fremkit_channel_push_errors_total` - Pushes refused by a Channel, labelled by `reason`:
+//!   `closed`, `rejected` or `other`.
+//! * `fremkit_channel_gets_total` - Reads of an item of a Channel, labelled by `hit`: whether the
+//!   item was available.
+//! * `fremkit_channel_wait_seconds` - Time spent in the blocking reads of a Channel.
+//! * `fremkit_channel_segments_total` - Segments allocated by Channels.
+//! * `fremkit_log_full_total` - Pushes refused by a bounded Log because it is full.
+
+use crate::LogError;
+
+use std::time::Duration;
+
+use metrics::{counter, histogram};
+
+#[inline]
+pub(crate) fn channel_push<T>(result: &Result<usize, LogError<T>>) {
+    let reason = match result {
+        Ok(_) => {
+            counter!("fremkit_channel_pushes_total").increment(1);
+            return;
+        }
+        Err(LogError::LogClosed(_)) => "closed",
+        Err(LogError::LogRejected(_)) => "rejected",
+        Err(_) => "other",
+    };
+
+    counter!("fremkit_channel_push_errors_total", "reason" => reason).increment(1);
+}
+
+#[inline]
+pub(crate) fn channel_get(hit: bool) {
+    let hit = if hit { "true" } else { "false" };
+
+    counter!("fremkit_channel_gets_total", "hit" => hit).increment(1);
+}
+
+#[inline]
+pub(crate) fn channel_wait(elapsed: Duration) {
+    histogram!("fremkit_channel_wait_seconds").record(elapsed);
+}
+
+#[inline]
+pub(crate) fn channel_segment() {
+    counter!("fremkit_channel_segments_total").increment(1);
+}
+
+#[inline]
+pub(crate) fn log_full() {
+    counter!("fremkit_log_full_total").increment(1);
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use metrics::{
+        Counter, CounterFn, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+    };
+
+    use crate::bounded::Log;
+    use crate::unbounded::Channel;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    /// Counts the increments of every counter, by name and labels.
+    #[derive(Default)]
+    struct CountingRecorder {
+        counters: Mutex<HashMap<String, Arc<Count>>>,
+    }
+
+    #[derive(Default)]
+    struct Count(AtomicU64);
+
+    impl CounterFn for Count {
+        fn increment(&self, value: u64) {
+            self.0.fetch_add(value, Ordering::Relaxed);
+        }
+
+        fn absolute(&self, value: u64) {
+            self.0.store(value, Ordering::Relaxed);
+        }
+    }
+
+    impl CountingRecorder {
+        fn get(&self, key: &str) -> u64 {
+            self.counters
+                .lock()
+                .unwrap()
+                .get(key)
+                .map_or(0, |count| count.0.load(Ordering::Relaxed))
+        }
+    }
+
+    impl Recorder for CountingRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            let mut name = key.name().to_string();
+            for label in key.labels() {
+                name += &format!(",{}={}", label.key(), label.value());
+            }
+
+            Counter::from_arc(
+                self.counters
+                    .lock()
+                    .unwrap()
+                    .entry(name)
+                    .or_default()
+                    .clone(),
+            )
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn test_metrics() {
+        init();
+
+        let recorder = CountingRecorder::default();
+
+        metrics::with_local_recorder(&recorder, || {
+            let channel = Channel::with_segment_capacity(2);
+            for i in 0..5 {
+                channel.push(i).unwrap();
+            }
+            channel.close();
+            assert!(channel.push(5).is_err());
+
+            assert_eq!(channel.get(4), Some(&4));
+            assert_eq!(channel.get(5), None);
+            assert_eq!(channel.wait_for(5), None);
+
+            let log = Log::new(1);
+            log.push(0).unwrap();
+            assert!(log.push(1).is_err());
+        });
+
+        // Filling up the segments of the channel is not reported as a full log.
+        assert_eq!(recorder.get("fremkit_channel_pushes_total"), 5);
+        assert_eq!(
+            recorder.get("fremkit_channel_push_errors_total,reason=closed"),
+            1
+        );
+        assert_eq!(recorder.get("fremkit_channel_gets_total,hit=true"), 1);
+        assert_eq!(recorder.get("fremkit_channel_gets_total,hit=false"), 1);
+        assert_eq!(recorder.get("fremkit_channel_segments_total"), 2);
+        assert_eq!(recorder.get("fremkit_log_full_total"), 1);
+    }
+}
@@ -0,0 +1,317 @@
+//! This module contains the implementation of the `MmapLog` type, enabled by the `mmap` feature.
+//!
+//! The file starts with a header holding the layout of the log and its two counters, followed by one
+//! ready flag per slot, and finally the slots themselves. Counters and flags live in the mapping, so
+//! every process mapping the same file shares them. They are accessed through `std` atomics, which
+//! the `loom` shims cannot stand in for.
+
+use crate::LogError;
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::mem;
+use std::path::Path;
+use std::sync::atomic::{fence, AtomicU64, AtomicU8, Ordering};
+
+use bytemuck::Pod;
+use memmap2::MmapMut;
+
+/// Magic number identifying a MmapLog file.
+const MAGIC: u64 = u64::from_le_bytes(*b"FREMKIT\x01");
+
+/// Size of the header, in bytes.
+const HEADER: usize = 64;
+
+/// Word offsets of the header fields.
+const MAGIC_WORD: usize = 0;
+const ITEM_SIZE_WORD: usize = 1;
+const CAPACITY_WORD: usize = 2;
+const RESERVED_WORD: usize = 3;
+const COMMITTED_WORD: usize = 4;
+
+/// A bounded Log of plain-old-data items, stored in a memory-mapped file.
+///
+/// The log survives process restarts, and can be shared between processes mapping the same file.
+/// Like the in-memory Log, slots are written once and never modified afterwards.
+///
+/// If a producer dies between reserving a slot and writing it, the slot stays unwritten and the
+/// length of the log stops advancing at this index.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::MmapLog;
+///
+/// let path = std::env::temp_dir().join(format!("fremkit-doc-{}.log", std::process::id()));
+///
+/// let log: MmapLog<u64> = MmapLog::create(&path, 100).unwrap();
+/// log.push(1).unwrap();
+/// drop(log);
+///
+/// let log: MmapLog<u64> = MmapLog::open(&path).unwrap();
+/// assert_eq!(log.get(0), Some(&1));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct MmapLog<T> {
+    map: MmapMut,
+    base: *mut u8,
+    capacity: usize,
+    data: usize,
+    _item: std::marker::PhantomData<T>,
+}
+
+impl<T: Pod> MmapLog<T> {
+    /// Create a new log in a file, truncating it if it already exists.
+    ///
+    /// # Arguments
+    /// * `path` - The file backing the log.
+    /// * `capacity` - The maximum number of items that can be pushed on the log.
+    pub fn create<P: AsRef<Path>>(path: P, capacity: usize) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+
+        let data = Self::data_offset(capacity);
+        file.set_len((data + capacity * mem::size_of::<T>()) as u64)?;
+
+        let log = Self::map(&file, capacity)?;
+        log.word(ITEM_SIZE_WORD)
+            .store(mem::size_of::<T>() as u64, Ordering::Relaxed);
+        log.word(CAPACITY_WORD)
+            .store(capacity as u64, Ordering::Relaxed);
+        log.word(MAGIC_WORD).store(MAGIC, Ordering::Release);
+        log.map.flush()?;
+
+        Ok(log)
+    }
+
+    /// Open a log previously created with `create`.
+    ///
+    /// # Returns
+    /// An `InvalidData` error if the file is not a log of items of this size.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        if file.metadata()?.len() < HEADER as u64 {
+            return Err(invalid("fremkit: file is too short to hold a log"));
+        }
+
+        let header = Self::map(&file, 0)?;
+        if header.word(MAGIC_WORD).load(Ordering::Acquire) != MAGIC {
+            return Err(invalid("fremkit: file is not a log"));
+        }
+        if header.word(ITEM_SIZE_WORD).load(Ordering::Relaxed) != mem::size_of::<T>() as u64 {
+            return Err(invalid("fremkit: log items have a different size"));
+        }
+
+        let capacity = header.word(CAPACITY_WORD).load(Ordering::Relaxed) as usize;
+        let data = Self::data_offset(capacity);
+        if file.metadata()?.len() < (data + capacity * mem::size_of::<T>()) as u64 {
+            return Err(invalid("fremkit: file is too short for its capacity"));
+        }
+
+        Self::map(&file, capacity)
+    }
+
+    /// Offset of the first slot: after the header and the ready flags, aligned for `T`.
+    fn data_offset(capacity: usize) -> usize {
+        let align = mem::align_of::<T>().max(HEADER);
+
+        (HEADER + capacity).div_ceil(align) * align
+    }
+
+    fn map(file: &File, capacity: usize) -> io::Result<Self> {
+        // SAFETY: The file may be modified by other processes, but only through the atomic counters
+        // and flags, and through slots which are not readable until their flag is set.
+        let mut map = unsafe { MmapMut::map_mut(file)? };
+
+        Ok(Self {
+            base: map.as_mut_ptr(),
+            map,
+            capacity,
+            data: Self::data_offset(capacity),
+            _item: std::marker::PhantomData,
+        })
+    }
+
+    #[inline]
+    fn word(&self, word: usize) -> &AtomicU64 {
+        // SAFETY: The header is within the mapping, and the mapping is page aligned.
+        unsafe { &*(self.base.add(word * 8) as *const AtomicU64) }
+    }
+
+    #[inline]
+    fn ready(&self, index: usize) -> &AtomicU8 {
+        // SAFETY: The flags are within the mapping, right after the header.
+        unsafe { &*(self.base.add(HEADER + index) as *const AtomicU8) }
+    }
+
+    #[inline]
+    fn slot(&self, index: usize) -> *mut T {
+        // INVARIANT: The data offset is aligned for `T`, and the index is lower than the capacity.
+        unsafe { (self.base.add(self.data) as *mut T).add(index) }
+    }
+
+    /// Get the capacity of the log.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the number of items that can be read from the log.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.word(COMMITTED_WORD).load(Ordering::Acquire) as usize
+    }
+
+    /// Is the log empty ?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get an item from the log, if it is available.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.capacity || self.ready(index).load(Ordering::Acquire) == 0 {
+            return None;
+        }
+
+        // SAFETY: The slot has been written, and is never written again.
+        Some(unsafe { &*self.slot(index) })
+    }
+
+    /// Append an item to the log.
+    ///
+    /// # Returns
+    /// The index of the item, or an error if the log is full.
+    pub fn push(&self, value: T) -> Result<usize, LogError<T>> {
+        let token = self.word(RESERVED_WORD).fetch_add(1, Ordering::Relaxed) as usize;
+
+        if token >= self.capacity {
+            return Err(LogError::LogCapacityExceeded(value));
+        }
+
+        // SAFETY: The token is unique, so we are the only writer of this slot, and it cannot be
+        // read before its flag is set.
+        unsafe { self.slot(token).write(value) };
+        self.ready(token).store(1, Ordering::Release);
+        self.commit();
+
+        Ok(token)
+    }
+
+    /// Advance the committed length over every written slot.
+    fn commit(&self) {
+        // Same protocol as `Log::commit`.
+        fence(Ordering::SeqCst);
+
+        let committed = self.word(COMMITTED_WORD);
+        let mut len = committed.load(Ordering::Acquire) as usize;
+
+        while len < self.capacity && self.ready(len).load(Ordering::Acquire) != 0 {
+            len = match committed.compare_exchange_weak(
+                len as u64,
+                len as u64 + 1,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => len + 1,
+                Err(current) => current as usize,
+            };
+        }
+    }
+
+    /// Iterate over the committed items of the log.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        (0..self.len()).filter_map(move |index| self.get(index))
+    }
+
+    /// Flush the written items to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        self.map.flush()
+    }
+}
+
+// SAFETY: Slots are written once through unique tokens, and published through atomic flags, like the
+// slots of a Log.
+unsafe impl<T: Pod + Send> Send for MmapLog<T> {}
+unsafe impl<T: Pod + Sync> Sync for MmapLog<T> {}
+
+impl<T: Pod> fmt::Debug for MmapLog<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MmapLog")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("fremkit-{}-{}.log", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_mmap_log_reopen() {
+        init();
+
+        let path = path("reopen");
+
+        let log: MmapLog<u64> = MmapLog::create(&path, 64).unwrap();
+        thread::scope(|s| {
+            for t in 0..4 {
+                let log = &log;
+                s.spawn(move || {
+                    for i in 0..16 {
+                        log.push(t * 16 + i).unwrap();
+                    }
+                });
+            }
+        });
+        assert!(log.push(0).is_err());
+        drop(log);
+
+        let log: MmapLog<u64> = MmapLog::open(&path).unwrap();
+        let mut items: Vec<_> = log.iter().copied().collect();
+        items.sort_unstable();
+
+        assert_eq!(log.len(), 64);
+        assert_eq!(items, (0..64).collect::<Vec<_>>());
+
+        assert!(MmapLog::<u32>::open(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_mmap_log_shared() {
+        init();
+
+        let path = path("shared");
+
+        let writer: MmapLog<u32> = MmapLog::create(&path, 8).unwrap();
+        let reader: MmapLog<u32> = MmapLog::open(&path).unwrap();
+
+        writer.push(7).unwrap();
+
+        assert_eq!(reader.len(), 1);
+        assert_eq!(reader.get(0), Some(&7));
+        assert_eq!(reader.get(1), None);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,2 +1,32 @@
 pub mod bounded;
+pub mod capacity;
 pub mod error;
+pub mod projection;
+pub mod unbounded;
+
+mod delayed;
+mod expiring;
+mod fair;
+mod filtered;
+mod growth;
+#[cfg(feature = "metrics")]
+mod instrument;
+#[cfg(all(feature = "mmap", not(feature = "safe-impl")))]
+mod mmap;
+mod pages;
+mod ring;
+mod sealed;
+mod seq;
+#[cfg(feature = "serde")]
+mod serialize;
+mod sharded;
+mod slot;
+#[cfg(feature = "stats")]
+mod stats;
+mod storage;
+#[cfg(feature = "async")]
+mod stream;
+mod table;
+mod timestamped;
+#[cfg(feature = "wal")]
+mod wal;
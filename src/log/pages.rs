@@ -0,0 +1,268 @@
+//! This module contains the paged storage backing a Log.
+//!
+//! Slots are grouped in fixed-size pages, which are only allocated when the first slot they hold is
+//! written. A Log with a large capacity costs a small table of pages until it actually fills up.
+
+use crate::log::slot::Slots;
+
+use std::fmt;
+#[cfg(not(feature = "safe-impl"))]
+use std::mem::MaybeUninit;
+#[cfg(not(feature = "safe-impl"))]
+use std::ops::Range;
+use std::sync::OnceLock;
+
+/// Maximum number of slots held by a page.
+const PAGE_SIZE: usize = 4096;
+
+/// A fixed number of slots, allocated page by page on first write. This is the default `Storage` of
+/// a Log.
+///
+/// Reading a slot is lock-free: it is a load of the page pointer, followed by a read of the slot.
+/// Allocating a page goes through a `OnceLock`, so producers racing for a new page wait for the
+/// first one to allocate it.
+pub struct Pages<T> {
+    capacity: usize,
+    /// A power of two, so the page and position of a slot are found with a shift and a mask.
+    page_size: usize,
+    page_shift: u32,
+    pages: Box<[OnceLock<Slots<T>>]>,
+}
+
+impl<T> Pages<T> {
+    /// Create the table of pages for `capacity` slots. No page is allocated yet.
+    pub fn new(capacity: usize) -> Self {
+        // A Log smaller than a page gets a single page, rounded up but holding `capacity` slots.
+        let page_size = capacity.clamp(1, PAGE_SIZE).next_power_of_two();
+
+        let pages = Self {
+            capacity,
+            page_size,
+            page_shift: page_size.trailing_zeros(),
+            pages: (0..capacity.div_ceil(page_size))
+                .map(|_| OnceLock::new())
+                .collect(),
+        };
+
+        // Loom cannot see a page being published through a std `OnceLock`, and would report the
+        // first access to its slots from another thread as a data race. Allocate them upfront.
+        #[cfg(loom)]
+        for index in (0..capacity).step_by(page_size) {
+            pages.page(index);
+        }
+
+        pages
+    }
+
+    /// Get the number of slots.
+    #[inline]
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the number of slots held by a page.
+    #[cfg(not(feature = "safe-impl"))]
+    #[inline]
+    pub(crate) fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Get the page holding a slot, if it has been allocated, and the position of the slot in it.
+    #[inline]
+    pub(crate) fn get(&self, index: usize) -> Option<(&Slots<T>, usize)> {
+        if index >= self.capacity {
+            return None;
+        }
+
+        let page = self.pages.get(index >> self.page_shift)?.get()?;
+
+        Some((page, index & (self.page_size - 1)))
+    }
+
+    /// Get the page holding a slot, allocating it if needed, and the position of the slot in it.
+    ///
+    /// # Returns
+    /// The page and the position, or `None` if `index` is not lower than the capacity.
+    #[inline]
+    pub(crate) fn page(&self, index: usize) -> Option<(&Slots<T>, usize)> {
+        if index >= self.capacity {
+            return None;
+        }
+
+        let page = index >> self.page_shift;
+        let slots = self
+            .pages
+            .get(page)?
+            .get_or_init(|| Slots::new(self.page_size.min(self.capacity - page * self.page_size)));
+
+        Some((slots, index & (self.page_size - 1)))
+    }
+
+    /// Read the value stored in a slot.
+    #[inline]
+    pub(crate) fn read(&self, index: usize) -> Option<&T> {
+        let (page, position) = self.get(index)?;
+
+        page.read(position, index)
+    }
+
+    /// Write a value to a slot.
+    ///
+    /// # Returns
+    /// An error containing the value if the index is out of bounds, or has already been written to.
+    #[inline]
+    pub(crate) fn write(&self, index: usize, value: T) -> Result<(), T> {
+        match self.page(index) {
+            Some((page, position)) => page.write(position, index, value),
+            None => Err(value),
+        }
+    }
+
+    /// Has the value been completely written to a slot ?
+    #[inline]
+    pub(crate) fn is_written(&self, index: usize) -> bool {
+        self.get(index)
+            .is_some_and(|(page, position)| page.is_written(position))
+    }
+
+    /// Claim a range of slots held by a single page, to write their values in place.
+    ///
+    /// # Returns
+    /// The values, or `None` if the range spans several pages, or one of the slots is out of bounds
+    /// or has already been claimed. See `Slots::claim`.
+    ///
+    /// # Safety
+    /// The values must not be used once the slots are published or released.
+    #[cfg(not(feature = "safe-impl"))]
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) unsafe fn claim(&self, indices: Range<usize>) -> Option<&mut [MaybeUninit<T>]> {
+        let (page, position) = self.page(indices.start)?;
+
+        // SAFETY: Forwarded to the caller.
+        unsafe { page.claim(position..position + indices.len()) }
+    }
+
+    /// Give back claimed slots, so they can be written to again.
+    ///
+    /// # Safety
+    /// See `Slots::release`.
+    #[cfg(not(feature = "safe-impl"))]
+    pub(crate) unsafe fn release(&self, indices: Range<usize>) {
+        if let Some((page, position)) = self.get(indices.start) {
+            // SAFETY: Forwarded to the caller.
+            unsafe { page.release(position..position + indices.len()) };
+        }
+    }
+
+    /// Make the values of claimed slots readable, from the last to the first.
+    ///
+    /// # Safety
+    /// See `Slots::publish`.
+    #[cfg(not(feature = "safe-impl"))]
+    pub(crate) unsafe fn publish(&self, indices: Range<usize>) {
+        if let Some((page, position)) = self.get(indices.start) {
+            // SAFETY: Forwarded to the caller.
+            unsafe { page.publish(position..position + indices.len(), indices.start) };
+        }
+    }
+
+    /// Drop the values of the slots from an index on, making them writable again.
+    pub(crate) fn clear_from(&mut self, index: usize) {
+        let (page_size, page_shift) = (self.page_size, self.page_shift);
+
+        for (i, page) in self.pages.iter_mut().enumerate().skip(index >> page_shift) {
+            if let Some(slots) = page.get_mut() {
+                slots.clear_from(index.saturating_sub(i * page_size));
+            }
+        }
+    }
+
+    /// Take the values out of every slot, in index order.
+    pub(crate) fn into_values(self) -> impl Iterator<Item = Option<T>> {
+        let (capacity, page_size) = (self.capacity, self.page_size);
+
+        self.pages
+            .into_vec()
+            .into_iter()
+            .enumerate()
+            .flat_map(move |(i, page)| match page.into_inner() {
+                Some(slots) => slots.into_values(),
+                None => (0..page_size.min(capacity - i * page_size))
+                    .map(|_| None)
+                    .collect(),
+            })
+    }
+}
+
+#[cfg(not(feature = "safe-impl"))]
+unsafe impl<T: Sync + Send> Send for Pages<T> {}
+#[cfg(not(feature = "safe-impl"))]
+unsafe impl<T: Sync + Send> Sync for Pages<T> {}
+
+impl<T> fmt::Debug for Pages<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pages")
+            .field("capacity", &self.capacity)
+            .field("page_size", &self.page_size)
+            .field(
+                "allocated",
+                &self
+                    .pages
+                    .iter()
+                    .filter(|page| page.get().is_some())
+                    .count(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_pages_lazy() {
+        init();
+
+        let pages: Pages<u64> = Pages::new(PAGE_SIZE * 2 + 1);
+
+        assert!(pages.get(0).is_none());
+        assert_eq!(pages.read(PAGE_SIZE), None);
+
+        pages.write(PAGE_SIZE * 2, 7).unwrap();
+
+        // Only the last page, holding a single slot, has been allocated.
+        assert!(pages.get(0).is_none());
+        assert!(pages.is_written(PAGE_SIZE * 2));
+        assert_eq!(pages.read(PAGE_SIZE * 2), Some(&7));
+        assert_eq!(pages.get(PAGE_SIZE * 2 + 1).map(|_| ()), None);
+
+        let values: Vec<_> = pages.into_values().collect();
+        assert_eq!(values.len(), PAGE_SIZE * 2 + 1);
+        assert_eq!(values[PAGE_SIZE * 2], Some(7));
+    }
+
+    #[test]
+    fn test_pages_small_capacity() {
+        init();
+
+        // The page is rounded up to 8, but only holds 5 slots.
+        let pages: Pages<u64> = Pages::new(5);
+
+        pages.write(4, 4).unwrap();
+
+        assert_eq!(pages.read(4), Some(&4));
+        assert!(pages.get(5).is_none());
+        assert!(pages.get(7).is_none());
+        assert!(pages.get(8).is_none());
+
+        // Slots beyond the capacity cannot be written to.
+        assert!(pages.page(5).is_none());
+        assert!(pages.page(8).is_none());
+        assert_eq!(pages.into_values().count(), 5);
+    }
+}
@@ -0,0 +1,141 @@
+//! This module contains the `Projection` type, an incremental fold over a Log.
+
+use crate::bounded::Log;
+
+/// A state built by folding the items of a Log, kept up to date incrementally.
+///
+/// The Projection remembers how far into the log it has folded, so each call to `update` only applies
+/// the items appended since the previous call. This is the catch-up-then-follow loop every
+/// materialized view over a log needs.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::Log;
+/// use fremkit::Projection;
+///
+/// let log: Log<u64> = Log::new(100);
+/// let mut sum = Projection::new(0);
+///
+/// log.push(1).unwrap();
+/// log.push(2).unwrap();
+/// assert_eq!(sum.update(&log, |s, x| *s += x), 2);
+/// assert_eq!(*sum.state(), 3);
+///
+/// log.push(3).unwrap();
+/// assert_eq!(sum.update(&log, |s, x| *s += x), 1);
+/// assert_eq!(*sum.state(), 6);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Projection<S> {
+    state: S,
+    cursor: usize,
+}
+
+impl<S> Projection<S> {
+    /// Create a new Projection, starting from the beginning of the log with the given state.
+    pub fn new(init: S) -> Self {
+        Self {
+            state: init,
+            cursor: 0,
+        }
+    }
+
+    /// Apply the items appended to the log since the last update.
+    ///
+    /// Folding stops at the first slot which has not been written yet; it will be picked up by the
+    /// next update.
+    ///
+    /// # Arguments
+    /// * `log` - The log to fold. It should always be the same log for a given Projection.
+    /// * `f` - The reducer, applied to the state and each new item in order.
+    ///
+    /// # Returns
+    /// The number of items applied.
+    pub fn update<T, F>(&mut self, log: &Log<T>, mut f: F) -> usize
+    where
+        F: FnMut(&mut S, &T),
+    {
+        let start = self.cursor;
+
+        while let Some(item) = log.get(self.cursor) {
+            f(&mut self.state, item);
+            self.cursor += 1;
+        }
+
+        self.cursor - start
+    }
+
+    /// Get the current state.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Get the index of the next item to be applied.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Convert the Projection into its state.
+    pub fn into_state(self) -> S {
+        self.state
+    }
+}
+
+impl<T> Log<T> {
+    /// Fold the items of the log into a state.
+    ///
+    /// # Arguments
+    /// * `init` - The initial state.
+    /// * `f` - The reducer, applied to the state and each item in order.
+    ///
+    /// # Returns
+    /// The final state.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    /// log.push(2).unwrap();
+    ///
+    /// assert_eq!(log.fold_state(0, |s, x| s + x), 3);
+    /// ```
+    pub fn fold_state<S, F>(&self, init: S, f: F) -> S
+    where
+        F: FnMut(S, &T) -> S,
+    {
+        self.iter().fold(init, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_projection_follow() {
+        init();
+
+        let log = Log::new(10);
+        let mut proj = Projection::new(Vec::new());
+
+        assert_eq!(proj.update(&log, |s, x| s.push(*x)), 0);
+
+        log.push(1).unwrap();
+        log.push(2).unwrap();
+
+        assert_eq!(proj.update(&log, |s, x| s.push(*x)), 2);
+        assert_eq!(proj.cursor(), 2);
+
+        log.push(3).unwrap();
+
+        assert_eq!(proj.update(&log, |s, x| s.push(*x)), 1);
+        assert_eq!(proj.into_state(), vec![1, 2, 3]);
+        assert_eq!(log.fold_state(0, |s, x| s + x), 6);
+    }
+}
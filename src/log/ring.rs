@@ -0,0 +1,216 @@
+//! This module contains the implementation of the `RingLog` type.
+
+use crate::capacity::Capacity;
+use crate::log::seq::SeqSlot;
+use crate::sync::{AtomicUsize, Ordering};
+use crate::LogError;
+
+use cache_padded::CachePadded;
+
+/// This Log stores the last `capacity` items of an unbounded sequence of small `Copy` items.
+///
+/// Unlike `Log`, pushing never fails: once the log is full, new items overwrite the oldest ones.
+/// Indices keep increasing across wrap-arounds, so an index always refers to the same item, and
+/// `index / capacity()` is the lap it was written in. Reading an index which has been overwritten
+/// returns a `LogLapped` error, so a slow reader can tell it has been lapped by the producers.
+///
+/// Like `SeqLog`, reads return a copy of the item, protected by a sequence lock.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::RingLog;
+/// use fremkit::LogError;
+///
+/// let log: RingLog<u64> = RingLog::new(2);
+/// log.push(1);
+/// log.push(2);
+/// log.push(3);
+///
+/// assert!(matches!(log.get_copy(0), Err(LogError::LogLapped(0))));
+/// assert_eq!(log.get_copy(1).unwrap(), Some(2));
+/// assert_eq!(log.get_copy(2).unwrap(), Some(3));
+/// assert_eq!(log.get_copy(3).unwrap(), None);
+/// ```
+pub struct RingLog<T> {
+    len: CachePadded<AtomicUsize>,
+    capacity: usize,
+    /// `capacity - 1` if the capacity is a power of two, to find slots with a mask.
+    mask: Option<usize>,
+    data: Vec<SeqSlot<(usize, T)>>,
+}
+
+impl<T: Copy> RingLog<T> {
+    /// Create a new empty RingLog. It will hold the last `capacity` items pushed on it.
+    /// If `capacity` is 0, the RingLog will be created with a capacity of 1.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+
+        Self {
+            len: CachePadded::new(AtomicUsize::new(0)),
+            capacity,
+            mask: capacity.is_power_of_two().then(|| capacity - 1),
+            data: (0..capacity).map(|_| SeqSlot::new()).collect(),
+        }
+    }
+
+    /// Create a new empty RingLog, with the capacity of a preset checked at compile time.
+    ///
+    /// See the `capacity` module.
+    pub fn with_preset<C: Capacity>() -> Self {
+        Self::new(C::CAPACITY)
+    }
+
+    /// Get the slot holding an index.
+    #[inline]
+    fn slot(&self, index: usize) -> &SeqSlot<(usize, T)> {
+        match self.mask {
+            Some(mask) => &self.data[index & mask],
+            None => &self.data[index % self.capacity],
+        }
+    }
+
+    /// Get the number of items pushed on the log since it was created.
+    ///
+    /// Unlike other logs, this can be greater than the capacity.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// Get the capacity of the log.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Is the log empty ?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the index of the oldest item which has not been overwritten yet.
+    #[inline]
+    pub fn oldest(&self) -> usize {
+        self.len().saturating_sub(self.capacity)
+    }
+
+    /// Get a copy of an item from the log.
+    ///
+    /// # Returns
+    /// The item at the given index, or `None` if it is not written yet.
+    /// An error if the item has already been overwritten.
+    pub fn get_copy(&self, index: usize) -> Result<Option<T>, LogError<T>> {
+        if index >= self.len() {
+            return Ok(None);
+        }
+
+        match self.slot(index).read() {
+            Some((_, (written, value))) if written == index => Ok(Some(value)),
+            Some((_, (written, _))) if written > index => Err(LogError::LogLapped(index)),
+            // The slot still holds an item from a previous lap: ours is not written yet.
+            _ => Ok(None),
+        }
+    }
+
+    /// Append an item to the log, overwriting the oldest item if the log is full.
+    ///
+    /// A producer stalled for a whole lap can finish its write after a newer item was written to
+    /// the same slot. The newer item then reads as not written, until it is overwritten in turn.
+    ///
+    /// # Returns
+    /// The index of the item in the log.
+    pub fn push(&self, value: T) -> usize {
+        let index = self.len.fetch_add(1, Ordering::Relaxed);
+
+        self.slot(index).write((index, value));
+
+        index
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::sync::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    #[cfg(loom)]
+    fn test_loom() {
+        loom::model(test_ring_log_lapped);
+    }
+
+    #[test]
+    fn test_ring_log() {
+        init();
+
+        let log = RingLog::new(3);
+
+        for i in 0..5 {
+            assert_eq!(log.push(i * 10), i);
+        }
+
+        assert_eq!(log.len(), 5);
+        assert_eq!(log.oldest(), 2);
+
+        assert!(matches!(log.get_copy(1), Err(LogError::LogLapped(1))));
+        assert_eq!(log.get_copy(2).unwrap(), Some(20));
+        assert_eq!(log.get_copy(4).unwrap(), Some(40));
+        assert_eq!(log.get_copy(5).unwrap(), None);
+    }
+
+    #[test]
+    fn test_ring_log_preset() {
+        init();
+
+        let log: RingLog<usize> = RingLog::with_preset::<crate::capacity::Pow2<4>>();
+
+        for i in 0..10 {
+            log.push(i);
+        }
+
+        assert_eq!(log.capacity(), 4);
+        assert!(matches!(log.get_copy(5), Err(LogError::LogLapped(5))));
+        assert_eq!(
+            (6..10)
+                .map(|i| log.get_copy(i).unwrap())
+                .collect::<Vec<_>>(),
+            vec![Some(6), Some(7), Some(8), Some(9)]
+        );
+    }
+
+    #[test]
+    fn test_ring_log_lapped() {
+        init();
+
+        let log = Arc::new(RingLog::new(1));
+        let producer = log.clone();
+
+        let h1 = thread::spawn(move || {
+            producer.push(1);
+        });
+
+        log.push(2);
+
+        h1.join().unwrap();
+
+        // Only the item written last is readable.
+        let items: Vec<_> = (0..2).map(|index| log.get_copy(index)).collect();
+
+        assert_eq!(
+            items
+                .iter()
+                .filter(|item| matches!(item, Ok(Some(_))))
+                .count(),
+            1
+        );
+        assert_eq!(log.oldest(), 1);
+    }
+}
@@ -0,0 +1,249 @@
+//! This module contains the implementation of the `SealedLog` and `FrozenChannel` types, and the other
+//! immutable views of a Log.
+
+use crate::bounded::Log;
+use crate::unbounded::Channel;
+
+use std::ops::Deref;
+
+/// An immutable Log, which no longer accepts writes.
+///
+/// Sealing a Log moves its items into a single contiguous buffer, so the whole slice API (`windows`,
+/// `chunks`, `binary_search`, ...) is available without copying the items into a `Vec` first.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::Log;
+///
+/// let log: Log<u64> = Log::new(100);
+/// log.push(1).unwrap();
+/// log.push(2).unwrap();
+/// log.push(3).unwrap();
+///
+/// let sealed = log.seal();
+///
+/// assert_eq!(sealed.len(), 3);
+/// assert_eq!(sealed.binary_search(&2), Ok(1));
+/// assert_eq!(sealed.windows(2).count(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SealedLog<T> {
+    items: Box<[T]>,
+}
+
+impl<T> Log<T> {
+    /// Seal the log, moving its items into a contiguous buffer.
+    ///
+    /// Only the committed prefix of the log is kept: if a producer reserved a slot but never wrote
+    /// to it, the items after it are dropped.
+    pub fn seal(self) -> SealedLog<T> {
+        let len = self.committed_len();
+
+        SealedLog {
+            items: self.into_values().take(len).flatten().collect(),
+        }
+    }
+
+    /// Freeze the log into a boxed slice of its committed items.
+    ///
+    /// This is `seal`, without the wrapper. The slice can be converted into an `Arc<[T]>` to share
+    /// it with other threads.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    /// log.push(2).unwrap();
+    ///
+    /// let frozen: Arc<[u64]> = log.freeze().into();
+    /// assert_eq!(&*frozen, &[1, 2]);
+    /// ```
+    pub fn freeze(self) -> Box<[T]> {
+        self.seal().items
+    }
+
+    /// Copy the committed items of the log into a `Vec`.
+    ///
+    /// The committed length is read once: the snapshot holds exactly the first `len()` items as of
+    /// that point, even if producers keep appending while it is taken.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    ///
+    /// let snapshot = log.snapshot();
+    /// log.push(2).unwrap();
+    ///
+    /// assert_eq!(snapshot, vec![1]);
+    /// ```
+    pub fn snapshot(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.get_range(..).cloned().collect()
+    }
+}
+
+/// An immutable Channel, which no longer accepts writes.
+///
+/// Sealing a Channel compacts its segments into a single contiguous buffer, for random access and
+/// slice algorithms over the whole history, once producers are done with it. With the `serde`
+/// feature, it is serialized as the sequence of its items.
+///
+/// # Examples
+/// ```
+/// use fremkit::unbounded::Channel;
+///
+/// let channel: Channel<u64> = Channel::with_segment_capacity(2);
+/// for i in 0..5 {
+///     channel.push(i).unwrap();
+/// }
+///
+/// let frozen = channel.seal();
+///
+/// assert_eq!(frozen.len(), 5);
+/// assert_eq!(frozen[3], 3);
+/// assert_eq!(frozen.binary_search(&4), Ok(4));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrozenChannel<T> {
+    items: Box<[T]>,
+}
+
+impl<T> Channel<T> {
+    /// Seal the channel, moving its items into a contiguous buffer.
+    ///
+    /// Only the committed prefix of the channel is kept: if a producer reserved a slot but never
+    /// wrote to it, the items after it are dropped. Delayed items which have not been published yet
+    /// are dropped as well.
+    pub fn seal(self) -> FrozenChannel<T> {
+        let mut items = Vec::with_capacity(self.len());
+
+        for log in self.into_logs() {
+            let complete = log.committed_len() == log.capacity();
+
+            items.extend(log.freeze().into_vec());
+
+            if !complete {
+                break;
+            }
+        }
+
+        FrozenChannel {
+            items: items.into_boxed_slice(),
+        }
+    }
+}
+
+impl<T> From<Vec<T>> for FrozenChannel<T> {
+    fn from(items: Vec<T>) -> Self {
+        Self {
+            items: items.into_boxed_slice(),
+        }
+    }
+}
+
+impl<T> Deref for FrozenChannel<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+impl<T> AsRef<[T]> for FrozenChannel<T> {
+    fn as_ref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+impl<T> Deref for SealedLog<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+impl<T> AsRef<[T]> for SealedLog<T> {
+    fn as_ref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_log_seal() {
+        init();
+
+        let log = Log::new(4);
+
+        log.push(1).unwrap();
+        log.push(2).unwrap();
+
+        let sealed = log.seal();
+
+        assert_eq!(sealed.as_ref(), &[1, 2]);
+        assert_eq!(sealed.iter().sum::<i32>(), 3);
+        assert_eq!(sealed.first(), Some(&1));
+    }
+
+    #[test]
+    fn test_log_freeze_snapshot() {
+        init();
+
+        let log = Log::new(4);
+
+        log.push(1).unwrap();
+        log.push(2).unwrap();
+
+        let snapshot = log.snapshot();
+
+        log.push(3).unwrap();
+
+        assert_eq!(snapshot, vec![1, 2]);
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(&*log.freeze(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_channel_seal() {
+        init();
+
+        let channel = Channel::with_segment_capacity(3);
+
+        for i in 0..10 {
+            channel.push(i).unwrap();
+        }
+
+        let frozen = channel.seal();
+
+        assert_eq!(frozen.as_ref(), (0..10).collect::<Vec<_>>().as_slice());
+        assert_eq!(frozen.windows(2).count(), 9);
+        assert_eq!(frozen.last(), Some(&9));
+        assert_eq!(FrozenChannel::from(vec![1, 2]).as_ref(), &[1, 2]);
+    }
+
+    #[test]
+    fn test_channel_seal_empty() {
+        init();
+
+        let frozen = Channel::<u64>::new().seal();
+
+        assert!(frozen.is_empty());
+    }
+}
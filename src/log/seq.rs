@@ -0,0 +1,361 @@
+//! This module contains the implementation of the `SeqLog` type, and its sequence-locked slots.
+
+#[cfg(not(feature = "safe-impl"))]
+use crate::sync::spin_loop;
+use crate::sync::{fence, AtomicUsize, Ordering};
+use crate::LogError;
+
+#[cfg(not(feature = "safe-impl"))]
+use std::cell::UnsafeCell;
+#[cfg(not(feature = "safe-impl"))]
+use std::mem::MaybeUninit;
+#[cfg(not(feature = "safe-impl"))]
+use std::ptr;
+
+use cache_padded::CachePadded;
+#[cfg(feature = "safe-impl")]
+use parking_lot::Mutex;
+
+/// A storage cell protected by a sequence lock.
+///
+/// The sequence is even when the slot is stable, and odd while a write is in progress.
+/// Readers copy the value out, and retry if the sequence changed in the meantime.
+/// A sequence of 0 means the slot has never been written to.
+#[cfg(not(feature = "safe-impl"))]
+pub(crate) struct SeqSlot<T> {
+    seq: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+#[cfg(not(feature = "safe-impl"))]
+impl<T: Copy> SeqSlot<T> {
+    /// Create a new empty slot.
+    pub(crate) fn new() -> Self {
+        Self {
+            seq: AtomicUsize::new(0),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Read the value stored in the slot.
+    ///
+    /// # Returns
+    /// The sequence the value was read at, and the value, or `None` if the slot has never been written to.
+    #[inline]
+    pub(crate) fn read(&self) -> Option<(usize, T)> {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+
+            if seq == 0 {
+                return None;
+            }
+
+            if seq & 1 == 1 {
+                spin_loop();
+                continue;
+            }
+
+            // SAFETY: The value has been written at least once, as the sequence is not 0.
+            // A concurrent write may tear the copy, in which case the sequence check below fails and the
+            // copy is discarded without being used. `T: Copy`, so the discarded copy needs no drop.
+            let value = unsafe { ptr::read_volatile(self.value.get()) };
+
+            fence(Ordering::Acquire);
+
+            if self.seq.load(Ordering::Relaxed) == seq {
+                // SAFETY: The sequence did not change, so the copy is consistent.
+                return Some((seq, unsafe { value.assume_init() }));
+            }
+        }
+    }
+
+    /// Write a value to the slot, waiting for any concurrent writer to finish first.
+    ///
+    /// # Returns
+    /// The sequence the value was written at.
+    #[inline]
+    pub(crate) fn write(&self, value: T) -> usize {
+        let mut seq = self.seq.load(Ordering::Relaxed);
+
+        loop {
+            if seq & 1 == 1 {
+                spin_loop();
+                seq = self.seq.load(Ordering::Relaxed);
+                continue;
+            }
+
+            match self
+                .seq
+                .compare_exchange_weak(seq, seq + 1, Ordering::Acquire, Ordering::Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => seq = current,
+            }
+        }
+
+        fence(Ordering::Release);
+
+        // SAFETY: The odd sequence gives us exclusive write access to the slot.
+        unsafe { ptr::write_volatile(self.value.get(), MaybeUninit::new(value)) };
+
+        self.seq.store(seq + 2, Ordering::Release);
+
+        seq + 2
+    }
+
+    /// Has a value been completely written to the slot ?
+    #[inline]
+    pub(crate) fn is_written(&self) -> bool {
+        self.seq.load(Ordering::Acquire) >= 2
+    }
+}
+
+/// A storage cell protected by a mutex, standing in for the sequence lock with the `safe-impl` feature.
+#[cfg(feature = "safe-impl")]
+pub(crate) struct SeqSlot<T> {
+    inner: Mutex<(usize, Option<T>)>,
+}
+
+#[cfg(feature = "safe-impl")]
+impl<T: Copy> SeqSlot<T> {
+    /// Create a new empty slot.
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: Mutex::new((0, None)),
+        }
+    }
+
+    /// Read the value stored in the slot.
+    ///
+    /// # Returns
+    /// The sequence the value was read at, and the value, or `None` if the slot has never been written to.
+    #[inline]
+    pub(crate) fn read(&self) -> Option<(usize, T)> {
+        let inner = self.inner.lock();
+
+        inner.1.map(|value| (inner.0, value))
+    }
+
+    /// Write a value to the slot.
+    ///
+    /// # Returns
+    /// The sequence the value was written at.
+    #[inline]
+    pub(crate) fn write(&self, value: T) -> usize {
+        let mut inner = self.inner.lock();
+
+        inner.0 += 2;
+        inner.1 = Some(value);
+
+        inner.0
+    }
+
+    /// Has a value been completely written to the slot ?
+    #[inline]
+    pub(crate) fn is_written(&self) -> bool {
+        self.inner.lock().1.is_some()
+    }
+}
+
+/// This Log stores an append-only, bounded, concurrent sequence of small `Copy` items.
+///
+/// Unlike `Log`, reads return a copy of the item instead of a reference, so readers do not borrow the
+/// log. Each slot is guarded by a sequence lock: a read is a single pair of loads around the copy on
+/// the fast path, and never observes a partially written item.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::SeqLog;
+///
+/// let log: SeqLog<u64> = SeqLog::new(100);
+/// log.push(1).unwrap();
+/// log.push(2).unwrap();
+///
+/// assert_eq!(log.get_copy(0), Some(1));
+/// assert_eq!(log.get_copy(1), Some(2));
+/// assert_eq!(log.get_copy(2), None);
+/// ```
+pub struct SeqLog<T> {
+    len: CachePadded<AtomicUsize>,
+    committed: CachePadded<AtomicUsize>,
+    capacity: usize,
+    data: Vec<SeqSlot<T>>,
+}
+
+impl<T: Copy> SeqLog<T> {
+    /// Create a new empty SeqLog. It will be able to hold at least `capacity` items.
+    /// If `capacity` is 0, the SeqLog will be created with a capacity of 1.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+
+        Self {
+            len: CachePadded::new(AtomicUsize::new(0)),
+            committed: CachePadded::new(AtomicUsize::new(0)),
+            capacity,
+            data: (0..capacity).map(|_| SeqSlot::new()).collect(),
+        }
+    }
+
+    /// Get the current length of the log.
+    ///
+    /// This is the number of items that have been pushed on the log and are fully written: every
+    /// index below `len()` is available for `get_copy`. Like for a `Log`, slots reserved by producers
+    /// still in the middle of a write are not counted.
+    /// It will never be greater than the capacity of the log.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.committed.load(Ordering::Acquire)
+    }
+
+    /// Get the capacity of the log.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Is the log empty ?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get a copy of an item from the log.
+    ///
+    /// # Returns
+    /// The item at the given index, or `None` if the index is out of bounds or the item is not written yet.
+    #[inline]
+    pub fn get_copy(&self, index: usize) -> Option<T> {
+        self.data.get(index)?.read().map(|(_, value)| value)
+    }
+
+    /// Append an item to the log.
+    ///
+    /// # Returns
+    /// The index of the item in the log, or an error containing the item if the log is full.
+    pub fn push(&self, value: T) -> Result<usize, LogError<T>> {
+        let token = self.len.fetch_add(1, Ordering::Relaxed);
+
+        if token >= self.capacity {
+            return Err(LogError::LogCapacityExceeded(value));
+        }
+
+        self.data[token].write(value);
+        self.commit();
+
+        Ok(token)
+    }
+
+    /// Advance the committed length over every slot written since the last commit, like
+    /// `Log::commit`.
+    fn commit(&self) {
+        // Pairs with the fence of the other committing producers: either they see our slot as
+        // written, or we see theirs, so the committed length cannot get stuck behind a written slot.
+        fence(Ordering::SeqCst);
+
+        let mut committed = self.committed.load(Ordering::Acquire);
+
+        while committed < self.capacity && self.data[committed].is_written() {
+            match self.committed.compare_exchange_weak(
+                committed,
+                committed + 1,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => committed += 1,
+                Err(current) => committed = current,
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "safe-impl"))]
+unsafe impl<T: Send> Send for SeqSlot<T> {}
+#[cfg(not(feature = "safe-impl"))]
+unsafe impl<T: Send> Sync for SeqSlot<T> {}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::sync::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    #[cfg(loom)]
+    fn test_loom() {
+        loom::model(test_seq_slot_overwrite);
+        loom::model(test_seq_log_committed_len);
+    }
+
+    #[test]
+    fn test_seq_log() {
+        init();
+
+        let log = SeqLog::new(2);
+
+        assert_eq!(log.push((1, 2)).unwrap(), 0);
+        assert_eq!(log.push((3, 4)).unwrap(), 1);
+        assert!(log.push((5, 6)).is_err());
+
+        assert_eq!(log.get_copy(0), Some((1, 2)));
+        assert_eq!(log.get_copy(1), Some((3, 4)));
+        assert_eq!(log.get_copy(2), None);
+        assert_eq!(log.len(), 2);
+    }
+
+    #[test]
+    fn test_seq_log_committed_len() {
+        init();
+
+        let log = Arc::new(SeqLog::new(2));
+        let producer = log.clone();
+
+        let h1 = thread::spawn(move || {
+            producer.push(1).unwrap();
+        });
+
+        log.push(2).unwrap();
+
+        // Every index below the length is readable, even while the other push is being written.
+        let len = log.len();
+        for index in 0..len {
+            assert!(log.get_copy(index).is_some());
+        }
+
+        h1.join().unwrap();
+
+        assert_eq!(log.len(), 2);
+    }
+
+    #[test]
+    fn test_seq_slot_overwrite() {
+        init();
+
+        let slot = Arc::new(SeqSlot::new());
+        let s1 = slot.clone();
+
+        assert_eq!(slot.read(), None);
+
+        let h1 = thread::spawn(move || {
+            s1.write((1, 1));
+        });
+
+        slot.write((2, 2));
+
+        if let Some((_, (a, b))) = slot.read() {
+            assert_eq!(a, b, "reads are never torn");
+        }
+
+        h1.join().unwrap();
+
+        let (seq, value) = slot.read().unwrap();
+
+        assert_eq!(seq, 4);
+        assert!(value == (1, 1) || value == (2, 2));
+    }
+}
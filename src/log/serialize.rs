@@ -0,0 +1,168 @@
+//! This module contains the serde implementations of `Log`, `Channel` and `FrozenChannel`, enabled by
+//! the `serde` feature.
+//!
+//! Logs and Channels are serialized as their capacity and their committed items. Items which are
+//! reserved but not written yet are left out, so a checkpoint never contains a gap. A FrozenChannel
+//! is serialized as the sequence of its items.
+
+use crate::bounded::{LocalLog, Log};
+use crate::unbounded::{Channel, FrozenChannel};
+
+use serde::de::Error;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serializes the items returned by an iterator factory as a sequence.
+struct Items<F>(F);
+
+impl<F, I> Serialize for Items<F>
+where
+    F: Fn() -> I,
+    I: Iterator,
+    I::Item: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq((self.0)())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Log")]
+struct LogRepr<T> {
+    capacity: usize,
+    items: Vec<T>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Channel")]
+struct ChannelRepr<T> {
+    segment_capacity: usize,
+    items: Vec<T>,
+}
+
+impl<T: Serialize> Serialize for Log<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Log", 2)?;
+        state.serialize_field("capacity", &self.capacity())?;
+        state.serialize_field("items", &Items(|| self.get_range(..)))?;
+        state.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Log<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = LogRepr::deserialize(deserializer)?;
+
+        if repr.items.len() > repr.capacity {
+            return Err(D::Error::custom(format!(
+                "{} items do not fit in a Log of capacity {}",
+                repr.items.len(),
+                repr.capacity
+            )));
+        }
+
+        let mut local = LocalLog::new(repr.capacity);
+        for item in repr.items {
+            local.push(item).map_err(D::Error::custom)?;
+        }
+
+        Ok(local.into())
+    }
+}
+
+impl<T: Serialize> Serialize for Channel<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Channel", 2)?;
+        state.serialize_field("segment_capacity", &self.segment_capacity())?;
+        state.serialize_field("items", &Items(|| self.iter()))?;
+        state.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Channel<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = ChannelRepr::deserialize(deserializer)?;
+
+        let channel = Channel::with_segment_capacity(repr.segment_capacity);
+        for item in repr.items {
+            channel.append(item);
+        }
+
+        Ok(channel)
+    }
+}
+
+impl<T: Serialize> Serialize for FrozenChannel<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for FrozenChannel<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(FrozenChannel::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_log_serde() {
+        init();
+
+        let log = Log::new(4);
+        log.push(1).unwrap();
+        log.push(2).unwrap();
+
+        let json = serde_json::to_string(&log).unwrap();
+        assert_eq!(json, r#"{"capacity":4,"items":[1,2]}"#);
+
+        let restored: Log<u64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.capacity(), 4);
+        assert_eq!(restored.snapshot(), vec![1, 2]);
+
+        let overflow = serde_json::from_str::<Log<u64>>(r#"{"capacity":1,"items":[1,2]}"#);
+        assert!(overflow.is_err());
+    }
+
+    #[test]
+    fn test_channel_serde() {
+        init();
+
+        let channel = Channel::with_segment_capacity(2);
+        for i in 0..5 {
+            channel.push(i).unwrap();
+        }
+
+        let json = serde_json::to_string(&channel).unwrap();
+        assert_eq!(json, r#"{"segment_capacity":2,"items":[0,1,2,3,4]}"#);
+
+        let restored: Channel<u64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.len(), 5);
+        assert_eq!(restored.get(4), Some(&4));
+    }
+
+    #[test]
+    fn test_frozen_channel_serde() {
+        init();
+
+        let channel = Channel::with_segment_capacity(2);
+        for i in 0..5 {
+            channel.push(i).unwrap();
+        }
+
+        let frozen = channel.seal();
+
+        let json = serde_json::to_string(&frozen).unwrap();
+        assert_eq!(json, "[0,1,2,3,4]");
+
+        let restored: FrozenChannel<u64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, frozen);
+    }
+}
@@ -0,0 +1,305 @@
+//! This module contains the implementation of the `ShardedLog` type.
+
+use crate::bounded::Log;
+use crate::sync::{AtomicU64, Ordering};
+use crate::LogError;
+
+use std::fmt;
+
+use cache_padded::CachePadded;
+
+/// A bounded Log split in shards, so that producers do not contend on a single length counter.
+///
+/// Every push on a Log goes through the same reservation counter, which becomes the bottleneck once
+/// many producers push concurrently. A ShardedLog stripes its producers over independent Logs: a
+/// producer only contends with the producers sharing its shard.
+///
+/// Readers see the shards merged in round-robin order: the item at index `i` of shard `s` is at
+/// position `i * shards + s`. Items pushed by the same producer keep their order, but there is no
+/// global order across shards: an item pushed after another one on a different shard may come
+/// before it in the merged view.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::ShardedLog;
+///
+/// let log = ShardedLog::new(8, 2);
+///
+/// assert_eq!(log.push(0, "a").unwrap(), 0);
+/// assert_eq!(log.push(1, "b").unwrap(), 1);
+/// assert_eq!(log.push(0, "c").unwrap(), 2);
+///
+/// assert_eq!(log.get(2), Some(&"c"));
+/// assert_eq!(log.iter().collect::<Vec<_>>(), vec![&"a", &"b", &"c"]);
+/// ```
+pub struct ShardedLog<T> {
+    shards: Box<[Log<T>]>,
+    sequence: Option<Sequence>,
+}
+
+/// Sequence stamps of the items of a ShardedLog, recording the global order of their pushes.
+struct Sequence {
+    next: CachePadded<AtomicU64>,
+    /// Stamp of each slot of each shard, plus one. 0 means the stamp has not been recorded yet.
+    stamps: Box<[Box<[AtomicU64]>]>,
+}
+
+impl<T> ShardedLog<T> {
+    /// Create a new ShardedLog.
+    ///
+    /// # Arguments
+    /// * `capacity` - The total capacity, split evenly between the shards.
+    /// * `shards` - The number of shards, at least 1.
+    pub fn new(capacity: usize, shards: usize) -> Self {
+        let shards = shards.max(1);
+
+        Self {
+            shards: (0..shards).map(|_| Log::new(capacity / shards)).collect(),
+            sequence: None,
+        }
+    }
+
+    /// Create a new ShardedLog stamping every item with a global sequence number.
+    ///
+    /// The stamps give the order in which items were pushed across shards, read back with
+    /// `iter_ordered`. Taking a stamp goes through a counter shared by every producer, which brings
+    /// back some of the contention the shards avoid: only use this when the global order is needed.
+    ///
+    /// # Arguments
+    /// * `capacity` - The total capacity, split evenly between the shards.
+    /// * `shards` - The number of shards, at least 1.
+    pub fn with_sequence(capacity: usize, shards: usize) -> Self {
+        let mut log = Self::new(capacity, shards);
+
+        log.sequence = Some(Sequence {
+            next: CachePadded::new(AtomicU64::new(0)),
+            stamps: log
+                .shards
+                .iter()
+                .map(|shard| (0..shard.capacity()).map(|_| AtomicU64::new(0)).collect())
+                .collect(),
+        });
+
+        log
+    }
+
+    /// Get the number of shards.
+    #[inline]
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Get a shard, to read from it.
+    ///
+    /// # Panics
+    /// If `shard` is not lower than the number of shards.
+    #[inline]
+    pub fn shard(&self, shard: usize) -> &Log<T> {
+        &self.shards[shard]
+    }
+
+    /// Get the total capacity of the shards.
+    pub fn capacity(&self) -> usize {
+        self.shards.iter().map(Log::capacity).sum()
+    }
+
+    /// Get the total number of items in the shards.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(Log::len).sum()
+    }
+
+    /// Is every shard empty ?
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(Log::is_empty)
+    }
+
+    /// Append an item to the shard of a producer.
+    ///
+    /// Producers are striped over the shards: producer `p` pushes to shard `p % shards`.
+    ///
+    /// # Returns
+    /// The position of the item in the merged view, or an error if the shard is full.
+    pub fn push(&self, producer: usize, value: T) -> Result<usize, LogError<T>> {
+        let shard = producer % self.shards.len();
+
+        let Some(sequence) = &self.sequence else {
+            let index = self.shards[shard].push(value)?;
+
+            return Ok(index * self.shards.len() + shard);
+        };
+
+        let stamp = sequence.next.fetch_add(1, Ordering::Relaxed);
+        let index = self.shards[shard].push(value)?;
+
+        sequence.stamps[shard][index].store(stamp + 1, Ordering::Release);
+
+        Ok(index * self.shards.len() + shard)
+    }
+
+    /// Get the sequence stamp of the item at a position of the merged view.
+    ///
+    /// # Returns
+    /// The stamp, or `None` if the log was not created with `with_sequence`, or if the item or its
+    /// stamp has not been written yet.
+    pub fn stamp(&self, position: usize) -> Option<u64> {
+        let shards = self.shards.len();
+        let stamp = self.sequence.as_ref()?.stamps[position % shards]
+            .get(position / shards)?
+            .load(Ordering::Acquire);
+
+        stamp.checked_sub(1)
+    }
+
+    /// Get the item at a position of the merged view.
+    pub fn get(&self, position: usize) -> Option<&T> {
+        let shards = self.shards.len();
+
+        self.shards[position % shards].get(position / shards)
+    }
+
+    /// Iterate over the items of every shard, in the order of the merged view.
+    ///
+    /// The iterator stops after the longest shard, as it was when the iterator was created.
+    /// Positions missing from the shorter shards are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let rounds = self.shards.iter().map(Log::len).max().unwrap_or(0);
+
+        (0..rounds).flat_map(move |index| self.shards.iter().filter_map(move |s| s.get(index)))
+    }
+
+    /// Collect the items of every shard in the order they were pushed, along with their stamp.
+    ///
+    /// The order is given by the sequence stamps, so it is the same on every call. This is meant for
+    /// offline consumers: the items are collected and sorted first. Items pushed while this runs may
+    /// be missing, as well as any item after the first one of its shard whose stamp is not recorded.
+    ///
+    /// # Returns
+    /// The stamped items, or `None` if the log was not created with `with_sequence`.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::ShardedLog;
+    ///
+    /// let log = ShardedLog::with_sequence(8, 2);
+    ///
+    /// log.push(0, "a").unwrap();
+    /// log.push(0, "b").unwrap();
+    /// log.push(1, "c").unwrap();
+    ///
+    /// // The merged view interleaves the shards, the ordered view follows the pushes.
+    /// assert_eq!(log.iter().collect::<Vec<_>>(), vec![&"a", &"c", &"b"]);
+    /// assert_eq!(
+    ///     log.iter_ordered().unwrap().map(|(_, item)| item).collect::<Vec<_>>(),
+    ///     vec![&"a", &"b", &"c"]
+    /// );
+    /// ```
+    pub fn iter_ordered(&self) -> Option<impl Iterator<Item = (u64, &T)>> {
+        let sequence = self.sequence.as_ref()?;
+        let mut items = Vec::with_capacity(self.len());
+
+        for (shard, stamps) in self.shards.iter().zip(sequence.stamps.iter()) {
+            for (item, stamp) in shard.get_range(..).zip(stamps.iter()) {
+                match stamp.load(Ordering::Acquire).checked_sub(1) {
+                    Some(stamp) => items.push((stamp, item)),
+                    None => break,
+                }
+            }
+        }
+
+        items.sort_unstable_by_key(|&(stamp, _)| stamp);
+
+        Some(items.into_iter())
+    }
+}
+
+impl<T> fmt::Debug for ShardedLog<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedLog")
+            .field("shards", &self.shards.len())
+            .field("capacity", &self.capacity())
+            .field("len", &self.shards.iter().map(Log::len).collect::<Vec<_>>())
+            .field("sequenced", &self.sequence.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_sharded_log_merged_view() {
+        init();
+
+        const PRODUCERS: usize = 8;
+
+        let log = ShardedLog::new(8_000, 4);
+
+        thread::scope(|s| {
+            for id in 0..PRODUCERS {
+                let log = &log;
+                s.spawn(move || {
+                    for i in 0..1_000 {
+                        let position = log.push(id, (id, i)).unwrap();
+                        assert_eq!(log.get(position), Some(&(id, i)));
+                    }
+                });
+            }
+        });
+
+        assert_eq!(log.len(), 8_000);
+        assert!(log.push(0, (0, 0)).is_err());
+
+        // Every producer sees its own items in order, whatever the interleaving.
+        let mut next = [0; PRODUCERS];
+        for &(id, i) in log.iter() {
+            assert_eq!(next[id], i);
+            next[id] += 1;
+        }
+        assert_eq!(next, [1_000; PRODUCERS]);
+    }
+
+    #[test]
+    fn test_sharded_log_iter_ordered() {
+        init();
+
+        const PRODUCERS: usize = 4;
+
+        let log = ShardedLog::with_sequence(4_000, 2);
+        assert!(ShardedLog::<u8>::new(4, 2).iter_ordered().is_none());
+
+        thread::scope(|s| {
+            for id in 0..PRODUCERS {
+                let log = &log;
+                s.spawn(move || {
+                    for i in 0..1_000 {
+                        log.push(id, (id, i)).unwrap();
+                    }
+                });
+            }
+        });
+
+        let ordered: Vec<_> = log.iter_ordered().unwrap().collect();
+
+        // Every item is stamped once, and each producer's items keep their order.
+        assert_eq!(ordered.len(), 4_000);
+        assert!(ordered.iter().map(|&(stamp, _)| stamp).eq(0..4_000));
+
+        let mut next = [0; PRODUCERS];
+        for &(_, &(id, i)) in &ordered {
+            assert_eq!(next[id], i);
+            next[id] += 1;
+        }
+
+        for position in 0..4_000 {
+            let stamp = log.stamp(position).unwrap() as usize;
+            assert_eq!(ordered[stamp].1, log.get(position).unwrap());
+        }
+    }
+}
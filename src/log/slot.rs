@@ -0,0 +1,377 @@
+//! This module contains the storage cells backing every index of a Log.
+//!
+//! Slots are grouped in pages. The values of a page are kept in a contiguous array, apart from the
+//! flags of the slots, so that consecutive slots can be handed out as a slice.
+//!
+//! By default, a value is an `UnsafeCell` holding a `MaybeUninit`, and the ready flag of its slot
+//! tells whether it is initialized. With the `safe-impl` feature, it is a `OnceLock` instead, and the
+//! crate does not contain any unsafe code.
+
+#[cfg(not(feature = "safe-impl"))]
+use std::cell::UnsafeCell;
+#[cfg(not(feature = "safe-impl"))]
+use std::mem::MaybeUninit;
+#[cfg(not(feature = "safe-impl"))]
+use std::ops::Range;
+#[cfg(feature = "safe-impl")]
+use std::sync::OnceLock;
+
+#[cfg(feature = "paranoid")]
+use crate::sync::AtomicUsize;
+use crate::sync::{AtomicBool, Ordering};
+
+/// The flags of a slot.
+#[derive(Debug)]
+struct Flags {
+    claimed: AtomicBool,
+    ready: AtomicBool,
+    #[cfg(feature = "paranoid")]
+    stamp: AtomicUsize,
+}
+
+impl Flags {
+    fn new() -> Self {
+        Self {
+            claimed: AtomicBool::new(false),
+            ready: AtomicBool::new(false),
+            #[cfg(feature = "paranoid")]
+            stamp: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// A page of write-once storage cells.
+///
+/// A slot must only be written to once, by the producer holding its token. The Log upholds this
+/// contract, and the slots enforce it: a write first claims its slot, and hands the value back if it
+/// was already claimed. A ready flag is set once the write is complete, so a read racing with the
+/// write sees an empty slot instead of a partially written value.
+///
+/// With the `paranoid` feature enabled, every slot is stamped with the index it was written at.
+/// Reads check the stamp and panic with a diagnostic if they observe a torn or misplaced write,
+/// instead of silently returning whatever is in the cell.
+#[derive(Debug)]
+pub(crate) struct Slots<T> {
+    #[cfg(not(feature = "safe-impl"))]
+    values: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    #[cfg(feature = "safe-impl")]
+    values: Box<[OnceLock<T>]>,
+    flags: Box<[Flags]>,
+}
+
+impl<T> Slots<T> {
+    /// Create `len` empty slots.
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            #[cfg(not(feature = "safe-impl"))]
+            values: (0..len)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+            #[cfg(feature = "safe-impl")]
+            values: (0..len).map(|_| OnceLock::new()).collect(),
+            flags: (0..len).map(|_| Flags::new()).collect(),
+        }
+    }
+
+    /// Read the value stored at a position, written for the item at `_index`.
+    #[inline]
+    pub(crate) fn read(&self, position: usize, _index: usize) -> Option<&T> {
+        let flags = self.flags.get(position)?;
+
+        // The stamp must be loaded first: once it is observed, the write it covers is visible too.
+        #[cfg(feature = "paranoid")]
+        let stamp = flags.stamp.load(Ordering::Acquire);
+
+        let value = if flags.ready.load(Ordering::Acquire) {
+            self.load(position)
+        } else {
+            None
+        };
+
+        #[cfg(feature = "paranoid")]
+        check(_index, stamp, value.is_some());
+
+        value
+    }
+
+    /// Write a value at a position, for the item at `_index`.
+    ///
+    /// # Returns
+    /// An error containing the value if the position is out of bounds, or has already been written to.
+    #[inline]
+    pub(crate) fn write(&self, position: usize, _index: usize, value: T) -> Result<(), T> {
+        let Some(flags) = self.flags.get(position) else {
+            return Err(value);
+        };
+
+        if flags.claimed.swap(true, Ordering::Relaxed) {
+            return Err(value);
+        }
+
+        self.store(position, value);
+        flags.ready.store(true, Ordering::Release);
+
+        #[cfg(feature = "paranoid")]
+        flags.stamp.store(_index + 1, Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Has the value at a position been completely written ?
+    #[inline]
+    pub(crate) fn is_written(&self, position: usize) -> bool {
+        self.flags
+            .get(position)
+            .is_some_and(|flags| flags.ready.load(Ordering::Acquire))
+    }
+
+    /// Drop the values from a position on, making their slots writable again.
+    pub(crate) fn clear_from(&mut self, position: usize) {
+        for position in position..self.flags.len() {
+            #[cfg(not(feature = "safe-impl"))]
+            self.drop_value(position);
+            #[cfg(feature = "safe-impl")]
+            {
+                self.values[position].take();
+            }
+
+            self.flags[position] = Flags::new();
+        }
+    }
+
+    /// Take the values out of the slots, in position order.
+    #[cfg(not(feature = "safe-impl"))]
+    pub(crate) fn into_values(self) -> Vec<Option<T>> {
+        let mut slots = self;
+
+        (0..slots.flags.len())
+            .map(|position| {
+                // The value is moved out: the slot must not drop it again.
+                let ready = slots.flags[position].ready.swap(false, Ordering::Relaxed);
+
+                // SAFETY: The slot is ready, so the value is initialized, and we own the slots.
+                ready.then(|| unsafe { slots.values[position].get_mut().assume_init_read() })
+            })
+            .collect()
+    }
+
+    /// Take the values out of the slots, in position order.
+    #[cfg(feature = "safe-impl")]
+    pub(crate) fn into_values(self) -> Vec<Option<T>> {
+        self.values
+            .into_vec()
+            .into_iter()
+            .map(OnceLock::into_inner)
+            .collect()
+    }
+
+    /// Claim a range of positions, to write their values in place.
+    ///
+    /// # Returns
+    /// The values, or `None` if one of the positions is out of bounds or has already been claimed.
+    /// The values are only readable once they are published. A position is only claimed once, so
+    /// the values handed out never alias, although the slots are shared.
+    ///
+    /// # Safety
+    /// The values must not be used once the positions are published or released.
+    #[cfg(not(feature = "safe-impl"))]
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) unsafe fn claim(&self, positions: Range<usize>) -> Option<&mut [MaybeUninit<T>]> {
+        let cells = self.values.get(positions.clone())?;
+
+        for position in positions.clone() {
+            if self.flags[position].claimed.swap(true, Ordering::Relaxed) {
+                // Give back the positions claimed so far: the others belong to their writer.
+                for claimed in positions.start..position {
+                    self.flags[claimed].claimed.store(false, Ordering::Relaxed);
+                }
+
+                return None;
+            }
+        }
+
+        // SAFETY: `UnsafeCell<MaybeUninit<T>>` has the same layout as `MaybeUninit<T>`, so the cells
+        // are a slice of values. Every position has been claimed above, so nobody else writes to
+        // them, and they are not read from until they are published.
+        Some(unsafe {
+            std::slice::from_raw_parts_mut(UnsafeCell::raw_get(cells.as_ptr()), cells.len())
+        })
+    }
+
+    /// Give back claimed positions, so they can be written to again.
+    ///
+    /// # Safety
+    /// The values handed out when claiming the positions must not be used anymore.
+    #[cfg(not(feature = "safe-impl"))]
+    pub(crate) unsafe fn release(&self, positions: Range<usize>) {
+        for flags in &self.flags[positions] {
+            flags.claimed.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Make the values of claimed positions readable, for the items from `_index` on.
+    ///
+    /// The positions are marked as written from the last to the first: once the first one is seen
+    /// as written, all of them are.
+    ///
+    /// # Safety
+    /// The values handed out when claiming the positions must not be used anymore, and must all have
+    /// been initialized.
+    #[cfg(not(feature = "safe-impl"))]
+    pub(crate) unsafe fn publish(&self, positions: Range<usize>, _index: usize) {
+        for position in positions.clone().rev() {
+            let flags = &self.flags[position];
+            flags.ready.store(true, Ordering::Release);
+
+            #[cfg(feature = "paranoid")]
+            flags
+                .stamp
+                .store(_index + position - positions.start + 1, Ordering::Release);
+        }
+    }
+
+    #[cfg(not(feature = "safe-impl"))]
+    #[inline]
+    fn load(&self, position: usize) -> Option<&T> {
+        // SAFETY: The cell is only loaded from once the ready flag is set, after the write completed,
+        // so the value is initialized. Once written, it is never modified again while shared, so the
+        // reference stays valid for as long as the slots are borrowed.
+        Some(unsafe { (*self.values[position].get()).assume_init_ref() })
+    }
+
+    #[cfg(not(feature = "safe-impl"))]
+    #[inline]
+    fn store(&self, position: usize, value: T) {
+        // SAFETY: The slot has been claimed by this write, so we are the only writer.
+        // It cannot be read from until the write is complete.
+        unsafe { (*self.values[position].get()).write(value) };
+    }
+
+    /// Drop the value at a position, if it has been written.
+    #[cfg(not(feature = "safe-impl"))]
+    fn drop_value(&mut self, position: usize) {
+        if self.flags[position].ready.load(Ordering::Relaxed) {
+            // SAFETY: The slot is ready, so the value is initialized, and we have exclusive access.
+            unsafe { self.values[position].get_mut().assume_init_drop() };
+        }
+    }
+
+    #[cfg(feature = "safe-impl")]
+    #[inline]
+    fn load(&self, position: usize) -> Option<&T> {
+        self.values[position].get()
+    }
+
+    #[cfg(feature = "safe-impl")]
+    #[inline]
+    fn store(&self, position: usize, value: T) {
+        let written = self.values[position].set(value).is_ok();
+
+        debug_assert!(written, "fremkit: slot written to twice");
+    }
+}
+
+#[cfg(not(feature = "safe-impl"))]
+impl<T> Drop for Slots<T> {
+    fn drop(&mut self) {
+        for position in 0..self.flags.len() {
+            self.drop_value(position);
+        }
+    }
+}
+
+/// Check the stamp of a slot against what a read observed.
+#[cfg(feature = "paranoid")]
+fn check(index: usize, stamp: usize, written: bool) {
+    // A stamp of 0 means the slot has been reserved but the write is not visible yet.
+    if stamp == 0 {
+        return;
+    }
+
+    assert_eq!(
+        stamp,
+        index + 1,
+        "fremkit: out-of-order read at index {}: slot is stamped for index {}",
+        index,
+        stamp - 1
+    );
+    assert!(
+        written,
+        "fremkit: torn read at index {}: slot is stamped as written but holds no value",
+        index
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_slot_drop() {
+        init();
+
+        let item = Arc::new(());
+
+        // Written slots drop their value once, whether dropped, cleared or taken.
+        let slots = Slots::new(2);
+        slots.write(0, 0, item.clone()).unwrap();
+        drop(slots);
+
+        let mut slots = Slots::new(2);
+        slots.write(1, 1, item.clone()).unwrap();
+        slots.clear_from(0);
+        slots.write(1, 1, item.clone()).unwrap();
+
+        // A second write hands the value back instead of overwriting the first one.
+        assert!(slots.write(1, 1, item.clone()).is_err());
+        assert!(slots.write(2, 2, item.clone()).is_err());
+        assert!(slots.into_values()[1].is_some());
+
+        // Empty slots have nothing to drop.
+        drop(Slots::<Arc<()>>::new(2));
+        assert!(Slots::<Arc<()>>::new(2)
+            .into_values()
+            .iter()
+            .all(Option::is_none));
+
+        assert_eq!(Arc::strong_count(&item), 1);
+    }
+
+    #[test]
+    #[cfg(not(feature = "safe-impl"))]
+    fn test_slot_claim() {
+        init();
+
+        let slots = Slots::new(4);
+        slots.write(3, 3, 3).unwrap();
+
+        // Claimed values are written in place, and only readable once published.
+        // SAFETY: The values are not used once published.
+        let values = unsafe { slots.claim(0..2) }.unwrap();
+        values[0].write(0);
+        values[1].write(1);
+
+        // SAFETY: The claims fail, no values are handed out.
+        unsafe {
+            assert!(slots.claim(1..3).is_none());
+            assert!(slots.claim(3..4).is_none());
+            assert!(slots.claim(3..5).is_none());
+        }
+        assert_eq!(slots.read(0, 0), None);
+
+        // SAFETY: The values have been initialized, and are not used anymore.
+        unsafe { slots.publish(0..2, 0) };
+
+        assert_eq!(slots.read(0, 0), Some(&0));
+        assert_eq!(slots.read(1, 1), Some(&1));
+
+        // The position left unclaimed by the failed claim can still be written to.
+        assert_eq!(slots.write(2, 2, 2), Ok(()));
+    }
+}
@@ -0,0 +1,367 @@
+//! This module contains the contention counters of a Log, and the `PushProfiler`, enabled by the
+//! `stats` feature.
+
+use crate::bounded::Log;
+use crate::replay::Append;
+use crate::sync::{AtomicU64, AtomicUsize, Ordering};
+use crate::LogError;
+
+use std::fmt;
+use std::time::Instant;
+
+/// Number of buckets of a histogram: one for 0, and one per power of two of a `u64`.
+const BUCKETS: usize = 65;
+
+/// Fill level and contention counters of a Log.
+///
+/// The counters are updated with relaxed atomics, and are only meant to tell where producers spend
+/// their time. They are not synchronized with the content of the log, nor with each other.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::Log;
+///
+/// let log: Log<u64> = Log::new(1);
+/// log.push(1).unwrap();
+/// assert!(log.push(2).is_err());
+///
+/// let stats = log.stats();
+/// assert_eq!(stats.committed, 1);
+/// assert_eq!(stats.pending, 0);
+/// assert_eq!(stats.failed_pushes, 1);
+/// assert_eq!(stats.first_gap, None);
+/// assert_eq!(stats.commit_retries, 0);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogStats {
+    /// Number of committed items: the length of the log.
+    pub committed: usize,
+    /// Number of reserved slots not committed yet, because a producer is writing to them or is
+    /// behind one which is.
+    pub pending: usize,
+    /// Highest number of pending slots seen by a producer when it reserved its slots.
+    pub max_pending: usize,
+    /// First unwritten slot below the reserved length, see `Log::first_gap`.
+    pub first_gap: Option<usize>,
+    /// Number of pushes and claims rejected because the log was full.
+    pub failed_pushes: usize,
+    /// Number of times a producer lost the race to advance the committed length, and had to retry.
+    pub commit_retries: usize,
+    /// Number of pushes which had to wake up threads or tasks waiting on the log.
+    pub contended_notifies: usize,
+}
+
+/// The live counters behind `LogStats`.
+#[derive(Debug, Default)]
+pub(crate) struct StatsCounters {
+    max_pending: AtomicUsize,
+    failed_pushes: AtomicUsize,
+    commit_retries: AtomicUsize,
+    contended_notifies: AtomicUsize,
+}
+
+impl StatsCounters {
+    /// Record the number of pending slots seen by a producer after its reservation.
+    #[inline]
+    pub(crate) fn pending(&self, pending: usize) {
+        self.max_pending.fetch_max(pending, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn failed_push(&self) {
+        self.failed_pushes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn commit_retry(&self) {
+        self.commit_retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn contended_notify(&self) {
+        self.contended_notifies.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl<T> Log<T> {
+    /// Get the fill level and contention counters of the log.
+    pub fn stats(&self) -> LogStats {
+        let counters = self.counters();
+        let committed = self.committed_len();
+
+        LogStats {
+            committed,
+            pending: self.reserved_len().saturating_sub(committed),
+            max_pending: counters.max_pending.load(Ordering::Relaxed),
+            first_gap: self.first_gap(),
+            failed_pushes: counters.failed_pushes.load(Ordering::Relaxed),
+            commit_retries: counters.commit_retries.load(Ordering::Relaxed),
+            contended_notifies: counters.contended_notifies.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A histogram with power-of-two buckets, snapshotted from a `PushProfiler`.
+///
+/// Bucket `i` counts the values in `[2^(i-1), 2^i)`, and bucket 0 counts the zeros.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    counts: [u64; BUCKETS],
+}
+
+impl Histogram {
+    /// Get the number of recorded values.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Iterate over the non-empty buckets, as the largest value of the bucket and its count.
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(bucket, &count)| (upper_bound(bucket), count))
+    }
+
+    /// Get an upper bound of the value below which a fraction `q` of the recorded values fall.
+    ///
+    /// # Returns
+    /// The largest value of the bucket holding the quantile, or `None` if no value was recorded.
+    pub fn quantile(&self, q: f64) -> Option<u64> {
+        let rank = (q.clamp(0.0, 1.0) * self.count() as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+
+        for (bucket, &count) in self.counts.iter().enumerate() {
+            seen += count;
+
+            if seen >= rank {
+                return Some(upper_bound(bucket));
+            }
+        }
+
+        None
+    }
+}
+
+/// The live counters behind `Histogram`.
+struct HistogramCounters {
+    counts: Box<[AtomicU64]>,
+}
+
+impl HistogramCounters {
+    fn new() -> Self {
+        Self {
+            counts: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    #[inline]
+    fn record(&self, value: u64) {
+        let bucket = (u64::BITS - value.leading_zeros()) as usize;
+
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> Histogram {
+        let mut counts = [0; BUCKETS];
+
+        for (count, counter) in counts.iter_mut().zip(self.counts.iter()) {
+            *count = counter.load(Ordering::Relaxed);
+        }
+
+        Histogram { counts }
+    }
+}
+
+/// Get the largest value counted by a bucket.
+fn upper_bound(bucket: usize) -> u64 {
+    match bucket {
+        0 => 0,
+        64.. => u64::MAX,
+        _ => (1 << bucket) - 1,
+    }
+}
+
+/// Records histograms of the size of the items pushed on a Log or Channel, and of the time between
+/// two pushes.
+///
+/// Producers push through `record` instead of pushing on the target directly. Recording costs a
+/// clock read and a few relaxed atomics per push, and the histograms can be read at any time, to
+/// size segments or retention windows from the actual traffic.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::PushProfiler;
+/// use fremkit::unbounded::Channel;
+///
+/// let channel: Channel<String> = Channel::new();
+/// let profiler = PushProfiler::new(String::len);
+///
+/// profiler.record(&channel, "hello".to_string()).unwrap();
+/// profiler.record(&channel, "hello, world".to_string()).unwrap();
+///
+/// let sizes = profiler.entry_sizes();
+/// assert_eq!(sizes.count(), 2);
+/// assert_eq!(sizes.quantile(0.5), Some(7));
+/// assert_eq!(sizes.quantile(1.0), Some(15));
+///
+/// assert_eq!(profiler.inter_arrival_nanos().count(), 1);
+/// ```
+pub struct PushProfiler<T> {
+    size: Box<dyn Fn(&T) -> u64 + Send + Sync>,
+    sizes: HistogramCounters,
+    gaps: HistogramCounters,
+    start: Instant,
+    /// Nanoseconds from `start` to the last push, plus one. Zero before the first push.
+    last: AtomicU64,
+}
+
+impl<T> PushProfiler<T> {
+    /// Create a new PushProfiler, measuring the size of an item with `size`.
+    pub fn new<F>(size: F) -> Self
+    where
+        F: Fn(&T) -> usize + Send + Sync + 'static,
+    {
+        Self {
+            size: Box::new(move |value| size(value) as u64),
+            sizes: HistogramCounters::new(),
+            gaps: HistogramCounters::new(),
+            start: Instant::now(),
+            last: AtomicU64::new(0),
+        }
+    }
+
+    /// Push an item on a target, recording its size and the time since the previous push.
+    ///
+    /// Rejected items are not recorded.
+    ///
+    /// # Returns
+    /// The result of the push.
+    pub fn record<A: Append<T>>(&self, target: &A, value: T) -> Result<usize, LogError<T>> {
+        let size = (self.size)(&value);
+        let index = target.append(value)?;
+
+        self.sizes.record(size);
+
+        let now = self.start.elapsed().as_nanos() as u64 + 1;
+        let last = self.last.swap(now, Ordering::Relaxed);
+        if last > 0 {
+            self.gaps.record(now.saturating_sub(last));
+        }
+
+        Ok(index)
+    }
+
+    /// Get the histogram of the sizes of the recorded items.
+    pub fn entry_sizes(&self) -> Histogram {
+        self.sizes.snapshot()
+    }
+
+    /// Get the histogram of the time between two recorded pushes, in nanoseconds.
+    pub fn inter_arrival_nanos(&self) -> Histogram {
+        self.gaps.snapshot()
+    }
+}
+
+impl<T> fmt::Debug for PushProfiler<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PushProfiler")
+            .field("entry_sizes", &self.entry_sizes())
+            .field("inter_arrival_nanos", &self.inter_arrival_nanos())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_log_stats() {
+        init();
+
+        let log = Arc::new(Log::new(2));
+        let consumer = log.clone();
+
+        let h1 = thread::spawn(move || consumer.wait_for(1).copied());
+
+        log.push(1).unwrap();
+        log.push(2).unwrap();
+
+        assert_eq!(h1.join().unwrap(), Some(2));
+        assert_eq!(log.stats().commit_retries, 0);
+        assert!(log.stats().contended_notifies <= 2);
+    }
+
+    #[test]
+    #[cfg(not(feature = "safe-impl"))]
+    fn test_log_stats_pending() {
+        init();
+
+        let log: Log<u64> = Log::new(4);
+        log.push(0).unwrap();
+
+        // Claimed slots stay pending, and hold back the committed length, until they are written.
+        let mut claim = log.claim(2).unwrap();
+        log.push(3).unwrap();
+
+        assert!(log.claim(1).is_none());
+        assert!(log.push(4).is_err());
+
+        let stats = log.stats();
+        assert_eq!(stats.committed, 1);
+        assert_eq!(stats.pending, 3);
+        assert_eq!(stats.max_pending, 3);
+        assert_eq!(stats.first_gap, Some(1));
+        assert_eq!(stats.failed_pushes, 2);
+
+        for slot in claim.slots() {
+            slot.write(1);
+        }
+        // SAFETY: Every slot has been written above.
+        unsafe { claim.commit() };
+
+        let stats = log.stats();
+        assert_eq!((stats.committed, stats.pending), (4, 0));
+        assert_eq!(stats.first_gap, None);
+    }
+
+    #[test]
+    fn test_push_profiler() {
+        init();
+
+        let log = Log::new(10);
+        let profiler = PushProfiler::new(|item: &Vec<u8>| item.len());
+
+        thread::scope(|s| {
+            for len in [0, 1, 2, 3, 4, 1024] {
+                let (log, profiler) = (&log, &profiler);
+                s.spawn(move || profiler.record(log, vec![0; len]).unwrap());
+            }
+        });
+
+        let sizes = profiler.entry_sizes();
+        assert_eq!(
+            sizes.buckets().collect::<Vec<_>>(),
+            vec![(0, 1), (1, 1), (3, 2), (7, 1), (2047, 1)]
+        );
+        assert_eq!(sizes.quantile(0.0), Some(0));
+        assert_eq!(sizes.quantile(0.5), Some(3));
+        assert_eq!(sizes.quantile(0.9), Some(2047));
+        assert_eq!(profiler.inter_arrival_nanos().count(), 5);
+
+        // A failed push is not recorded.
+        let full = Log::new(1);
+        profiler.record(&full, vec![0]).unwrap();
+        assert!(profiler.record(&full, vec![0]).is_err());
+        assert_eq!(profiler.entry_sizes().count(), 7);
+    }
+}
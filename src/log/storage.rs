@@ -0,0 +1,120 @@
+//! This module contains the `Storage` trait, the backing store of a Log.
+
+use crate::log::pages::Pages;
+
+/// A fixed number of write-once slots, backing a Log.
+///
+/// The Log handles reservation, commit and notification, and only asks its storage to hold items.
+/// It writes each index at most once, and only reads indices it has reserved. Implementations must
+/// stay sound if these rules are broken: writing an index twice should hand the item back rather
+/// than overwrite an item which may be borrowed. The Log skips an index whose write failed.
+///
+/// `Pages` is the default storage. Other implementations can back a Log with an inline array, or
+/// with memory shared with other processes.
+///
+/// # Examples
+/// ```
+/// use std::sync::OnceLock;
+///
+/// use fremkit::bounded::{Log, Storage};
+///
+/// struct Cells(Vec<OnceLock<u64>>);
+///
+/// impl Storage<u64> for Cells {
+///     fn capacity(&self) -> usize {
+///         self.0.len()
+///     }
+///
+///     fn read(&self, index: usize) -> Option<&u64> {
+///         self.0.get(index)?.get()
+///     }
+///
+///     fn write(&self, index: usize, value: u64) -> Result<(), u64> {
+///         match self.0.get(index) {
+///             Some(cell) => cell.set(value),
+///             None => Err(value),
+///         }
+///     }
+///
+///     fn is_written(&self, index: usize) -> bool {
+///         self.read(index).is_some()
+///     }
+///
+///     fn clear(&mut self) {
+///         self.0.iter_mut().for_each(|cell| drop(cell.take()));
+///     }
+/// }
+///
+/// let log = Log::with_storage(Cells((0..4).map(|_| OnceLock::new()).collect()));
+/// log.push(1).unwrap();
+///
+/// assert_eq!(log.get(0), Some(&1));
+/// assert_eq!(log.capacity(), 4);
+/// ```
+pub trait Storage<T> {
+    /// Get the number of slots.
+    fn capacity(&self) -> usize;
+
+    /// Read the item at an index, if it has been completely written.
+    fn read(&self, index: usize) -> Option<&T>;
+
+    /// Write the item at an index.
+    ///
+    /// # Returns
+    /// An error containing the item if the index is out of bounds, or has already been written to.
+    fn write(&self, index: usize, value: T) -> Result<(), T>;
+
+    /// Has the item at an index been completely written ?
+    fn is_written(&self, index: usize) -> bool;
+
+    /// Drop every item, making all the slots writable again.
+    fn clear(&mut self);
+}
+
+impl<T> Storage<T> for Pages<T> {
+    #[inline]
+    fn capacity(&self) -> usize {
+        Pages::capacity(self)
+    }
+
+    #[inline]
+    fn read(&self, index: usize) -> Option<&T> {
+        Pages::read(self, index)
+    }
+
+    #[inline]
+    fn write(&self, index: usize, value: T) -> Result<(), T> {
+        Pages::write(self, index, value)
+    }
+
+    #[inline]
+    fn is_written(&self, index: usize) -> bool {
+        Pages::is_written(self, index)
+    }
+
+    fn clear(&mut self) {
+        self.clear_from(0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_pages_write_twice() {
+        init();
+
+        let pages = Pages::new(2);
+
+        assert_eq!(pages.write(1, 1), Ok(()));
+        assert_eq!(pages.write(1, 2), Err(2));
+        assert_eq!(pages.write(2, 3), Err(3));
+
+        assert_eq!(Storage::read(&pages, 1), Some(&1));
+    }
+}
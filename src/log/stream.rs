@@ -0,0 +1,248 @@
+//! This module contains the async API of the bounded `Log`, enabled by the `async` feature.
+
+use crate::bounded::Log;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+
+/// Number of items a `LogStream` yields in a row before yielding to the executor, by default.
+pub const STREAM_BUDGET: usize = 128;
+
+impl<T> Log<T> {
+    /// Get an item from the log, waiting asynchronously until it becomes available.
+    ///
+    /// This is the async counterpart of `wait_for`: the task is suspended, instead of the thread.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the item to wait for.
+    ///
+    /// # Returns
+    /// A future resolving to a reference to the item at the given index, or `None` if the index is
+    /// beyond the capacity of the log.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(100);
+    /// log.push(1).unwrap();
+    ///
+    /// let item = futures::executor::block_on(log.wait_for_async(0));
+    /// assert_eq!(item, Some(&1));
+    /// ```
+    pub fn wait_for_async(&self, index: usize) -> WaitFor<'_, T> {
+        WaitFor { index, log: self }
+    }
+
+    /// Create a stream over the log.
+    ///
+    /// The stream starts at the beginning of the log, and waits for new items as they are pushed.
+    /// It ends once every slot of the log has been read.
+    ///
+    /// After `STREAM_BUDGET` items read in a row without waiting, the stream yields to the executor
+    /// once, so a log which always has items ready does not starve the other tasks of its thread.
+    /// See `LogStream::with_budget`.
+    ///
+    /// # Examples
+    /// ```
+    /// use futures::StreamExt;
+    ///
+    /// use fremkit::bounded::Log;
+    ///
+    /// let log: Log<u64> = Log::new(2);
+    /// log.push(1).unwrap();
+    /// log.push(2).unwrap();
+    ///
+    /// let items: Vec<&u64> = futures::executor::block_on(log.stream().collect());
+    /// assert_eq!(items, vec![&1, &2]);
+    /// ```
+    pub fn stream(&self) -> LogStream<'_, T> {
+        LogStream {
+            idx: 0,
+            budget: STREAM_BUDGET,
+            used: 0,
+            log: self,
+        }
+    }
+
+    /// Poll for the item at the given index, registering the task if it is not available yet.
+    fn poll_index(&self, index: usize, cx: &mut Context<'_>) -> Poll<Option<&T>> {
+        if index >= self.capacity() {
+            return Poll::Ready(None);
+        }
+
+        if let Some(item) = self.get(index) {
+            return Poll::Ready(Some(item));
+        }
+
+        self.notifier().register(index, cx.waker());
+
+        // The item may have been pushed before the waker was registered.
+        match self.get(index) {
+            Some(item) => Poll::Ready(Some(item)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Future returned by `Log::wait_for_async`.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WaitFor<'a, T> {
+    index: usize,
+    log: &'a Log<T>,
+}
+
+impl<'a, T> Future for WaitFor<'a, T> {
+    type Output = Option<&'a T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.log.poll_index(self.index, cx)
+    }
+}
+
+/// Stream over the items in a Log, returned by `Log::stream`.
+#[must_use = "streams do nothing unless polled"]
+pub struct LogStream<'a, T> {
+    idx: usize,
+    /// Number of items yielded in a row before yielding to the executor.
+    budget: usize,
+    /// Number of items yielded since the stream last returned `Pending`.
+    used: usize,
+    log: &'a Log<T>,
+}
+
+impl<'a, T> LogStream<'a, T> {
+    /// Set the number of items the stream yields in a row before yielding to the executor.
+    ///
+    /// A budget of `usize::MAX` disables yielding: the stream only returns `Pending` when it waits
+    /// for an item.
+    ///
+    /// # Arguments
+    /// * `budget` - The number of items per poll, at least 1.
+    pub fn with_budget(mut self, budget: usize) -> Self {
+        self.budget = budget.max(1);
+        self
+    }
+
+    /// Get the index of the next item to be read.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.idx
+    }
+}
+
+impl<'a, T> Stream for LogStream<'a, T> {
+    type Item = &'a T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.used >= self.budget {
+            // Yield to the executor, asking to be polled again right away. The notifier is not
+            // involved: the next item may be ready already.
+            self.used = 0;
+            cx.waker().wake_by_ref();
+
+            return Poll::Pending;
+        }
+
+        let poll = self.log.poll_index(self.idx, cx);
+
+        match poll {
+            Poll::Ready(Some(_)) => {
+                self.idx += 1;
+                self.used += 1;
+            }
+            Poll::Ready(None) => {}
+            // The task yields while waiting, which resets its budget.
+            Poll::Pending => self.used = 0,
+        }
+
+        poll
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::thread;
+
+    use futures::executor::block_on;
+    use futures::task::noop_waker;
+    use futures::StreamExt;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_wait_for_async() {
+        init();
+
+        let log = Arc::new(Log::new(2));
+        let producer = log.clone();
+
+        let h1 = thread::spawn(move || {
+            producer.push(1).unwrap();
+            producer.push(2).unwrap();
+        });
+
+        assert_eq!(block_on(log.wait_for_async(1)), Some(&2));
+        assert_eq!(block_on(log.wait_for_async(2)), None);
+
+        h1.join().unwrap();
+    }
+
+    #[test]
+    fn test_log_stream() {
+        init();
+
+        let log = Arc::new(Log::new(3));
+        let producer = log.clone();
+
+        let h1 = thread::spawn(move || {
+            for i in 0..3 {
+                producer.push(i).unwrap();
+            }
+        });
+
+        let items: Vec<_> = block_on(log.stream().map(|x| *x).collect());
+
+        assert_eq!(items, vec![0, 1, 2]);
+
+        h1.join().unwrap();
+    }
+
+    #[test]
+    fn test_log_stream_budget() {
+        init();
+
+        let log = Log::new(5);
+        for i in 0..5 {
+            log.push(i).unwrap();
+        }
+
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut stream = log.stream().with_budget(2);
+
+        let mut polls = Vec::new();
+        loop {
+            match Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Ready(Some(&item)) => polls.push(Some(item)),
+                Poll::Ready(None) => break,
+                Poll::Pending => polls.push(None),
+            }
+        }
+
+        // The stream yields after every 2 items, even though the next ones are ready.
+        assert_eq!(
+            polls,
+            vec![Some(0), Some(1), None, Some(2), Some(3), None, Some(4)]
+        );
+        assert_eq!(stream.position(), 5);
+    }
+}
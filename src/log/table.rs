@@ -0,0 +1,126 @@
+//! This module contains the `SegmentTable`, the indexable store of the segments of a Channel.
+//!
+//! Segments are numbered in the order they are linked. The table is split in buckets whose sizes are
+//! successive powers of two, so the bucket and position of a segment are found from its number with
+//! a single `leading_zeros`, and buckets are only allocated once the segments before them are linked.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Number of buckets: bucket `b` holds `2^b` segments, so every segment number has a bucket.
+const BUCKETS: usize = usize::BITS as usize;
+
+/// The entries of a bucket.
+type Bucket<S> = Box<[OnceLock<S>]>;
+
+/// An append-only table of segments, indexed by segment number.
+///
+/// Reading a segment is lock-free: it is a load of the bucket pointer, followed by a load of the
+/// entry. Segments never move once stored, so references to them stay valid as long as the table.
+pub(crate) struct SegmentTable<S> {
+    buckets: [OnceLock<Bucket<S>>; BUCKETS],
+}
+
+impl<S> SegmentTable<S> {
+    /// Create an empty table. No bucket is allocated yet.
+    pub(crate) fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| OnceLock::new()),
+        }
+    }
+
+    /// Get the bucket and the position in the bucket of a segment number.
+    #[inline]
+    fn locate(number: usize) -> (usize, usize) {
+        // Numbers are shifted by one, so bucket `b` starts at `2^b - 1`. Every segment holds at least
+        // one item, so numbers stay below the largest index.
+        let shifted = number + 1;
+        let bucket = (usize::BITS - 1 - shifted.leading_zeros()) as usize;
+
+        (bucket, shifted - (1 << bucket))
+    }
+
+    /// Get a segment, if it has been stored.
+    #[inline]
+    pub(crate) fn get(&self, number: usize) -> Option<&S> {
+        let (bucket, position) = Self::locate(number);
+
+        self.buckets[bucket].get()?[position].get()
+    }
+
+    /// Get a segment, storing the one returned by `init` if there is none yet.
+    ///
+    /// Like `OnceLock::get_or_init`, callers racing for the same number wait for the first one to
+    /// store its segment, and get it.
+    pub(crate) fn get_or_init<F: FnOnce() -> S>(&self, number: usize, init: F) -> &S {
+        let (bucket, position) = Self::locate(number);
+
+        let entries = self.buckets[bucket]
+            .get_or_init(|| (0..1usize << bucket).map(|_| OnceLock::new()).collect());
+
+        entries[position].get_or_init(init)
+    }
+
+    /// Consume the table, returning its segments in number order, up to the first missing one.
+    pub(crate) fn into_segments(self) -> impl Iterator<Item = S> {
+        self.buckets
+            .into_iter()
+            .map_while(OnceLock::into_inner)
+            .flat_map(|entries| entries.into_vec())
+            .map_while(OnceLock::into_inner)
+    }
+}
+
+impl<S> fmt::Debug for SegmentTable<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SegmentTable")
+            .field(
+                "allocated",
+                &self
+                    .buckets
+                    .iter()
+                    .filter(|bucket| bucket.get().is_some())
+                    .count(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_segment_table() {
+        init();
+
+        assert_eq!(SegmentTable::<()>::locate(0), (0, 0));
+        assert_eq!(SegmentTable::<()>::locate(1), (1, 0));
+        assert_eq!(SegmentTable::<()>::locate(2), (1, 1));
+        assert_eq!(SegmentTable::<()>::locate(3), (2, 0));
+        assert_eq!(SegmentTable::<()>::locate(6), (2, 3));
+        assert_eq!(SegmentTable::<()>::locate(7), (3, 0));
+
+        let table = SegmentTable::new();
+        for number in 0..100 {
+            assert_eq!(table.get(number), None);
+            assert_eq!(*table.get_or_init(number, || number * 10), number * 10);
+        }
+
+        // A stored segment is never replaced.
+        assert_eq!(*table.get_or_init(42, || 0), 420);
+        assert_eq!(table.get(99), Some(&990));
+        assert_eq!(table.get(100), None);
+        assert_eq!(format!("{:?}", table), "SegmentTable { allocated: 7 }");
+
+        let segments: Vec<_> = table.into_segments().collect();
+        assert_eq!(
+            segments,
+            (0..100).map(|number| number * 10).collect::<Vec<_>>()
+        );
+    }
+}
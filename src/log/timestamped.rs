@@ -0,0 +1,224 @@
+//! This module contains the implementation of the `TimestampedLog` type.
+
+use crate::bounded::Log;
+use crate::LogError;
+
+use std::fmt;
+use std::ops::{Bound, Range, RangeBounds};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// A bounded Log recording the instant each item was pushed at.
+///
+/// Timestamps are non-decreasing with indices, so the items pushed during a time range are a range
+/// of indices, found by binary search over the committed items. To keep them ordered, pushes are
+/// serialized by a lock; reads do not take it.
+///
+/// # Examples
+/// ```
+/// use std::thread;
+/// use std::time::{Duration, Instant};
+///
+/// use fremkit::bounded::TimestampedLog;
+///
+/// let log = TimestampedLog::new(100);
+/// log.push("old").unwrap();
+///
+/// thread::sleep(Duration::from_millis(1));
+/// let start = Instant::now();
+/// log.push("new").unwrap();
+///
+/// assert_eq!(log.range_by_time(start..).map(|(_, item)| item).collect::<Vec<_>>(), vec![&"new"]);
+/// assert_eq!(log.since(Duration::from_secs(60)).count(), 2);
+/// ```
+pub struct TimestampedLog<T> {
+    log: Log<(Instant, T)>,
+    /// Held while taking a timestamp and pushing, so timestamps follow the order of the indices.
+    push: Mutex<()>,
+}
+
+impl<T> TimestampedLog<T> {
+    /// Create a new empty TimestampedLog.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            log: Log::new(capacity),
+            push: Mutex::new(()),
+        }
+    }
+
+    /// Get the underlying Log of timestamped items, to read from it.
+    #[inline]
+    pub fn log(&self) -> &Log<(Instant, T)> {
+        &self.log
+    }
+
+    /// Get the number of items in the log.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    /// Is the log empty ?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.log.is_empty()
+    }
+
+    /// Get the capacity of the log.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.log.capacity()
+    }
+
+    /// Append an item to the log, stamped with the current instant.
+    ///
+    /// # Returns
+    /// The index of the item in the log, or an error containing the item if the log is full.
+    pub fn push(&self, value: T) -> Result<usize, LogError<T>> {
+        let _guard = self.push.lock();
+
+        self.log
+            .push((Instant::now(), value))
+            .map_err(|err| err.map(|(_, value)| value))
+    }
+
+    /// Get an item and the instant it was pushed at, if it is available.
+    pub fn get(&self, index: usize) -> Option<(Instant, &T)> {
+        let (at, item) = self.log.get(index)?;
+
+        Some((*at, item))
+    }
+
+    /// Get the range of indices of the items pushed during a time range.
+    ///
+    /// Only the items committed when this is called are considered.
+    pub fn indices_by_time<R: RangeBounds<Instant>>(&self, range: R) -> Range<usize> {
+        let len = self.log.committed_len();
+
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.partition_point(len, |at| at < start),
+            Bound::Excluded(start) => self.partition_point(len, |at| at <= start),
+            Bound::Unbounded => 0,
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.partition_point(len, |at| at <= end),
+            Bound::Excluded(end) => self.partition_point(len, |at| at < end),
+            Bound::Unbounded => len,
+        };
+
+        start..end.max(start)
+    }
+
+    /// Iterate over the items pushed during a time range, along with their timestamp.
+    ///
+    /// Only the items committed when this is called are considered.
+    pub fn range_by_time<R: RangeBounds<Instant>>(
+        &self,
+        range: R,
+    ) -> impl Iterator<Item = (Instant, &T)> {
+        self.entries(self.indices_by_time(range))
+    }
+
+    /// Iterate over the items pushed during the last `period`, along with their timestamp.
+    pub fn since(&self, period: Duration) -> impl Iterator<Item = (Instant, &T)> {
+        let indices = match Instant::now().checked_sub(period) {
+            Some(start) => self.indices_by_time(start..),
+            None => self.indices_by_time(..),
+        };
+
+        self.entries(indices)
+    }
+
+    fn entries(&self, indices: Range<usize>) -> impl Iterator<Item = (Instant, &T)> {
+        self.log.get_range(indices).map(|(at, item)| (*at, item))
+    }
+
+    /// Find the first index among the first `len` items whose timestamp does not match `pred`.
+    ///
+    /// `pred` must hold for a prefix of the items, as for `slice::partition_point`.
+    fn partition_point<P>(&self, len: usize, pred: P) -> usize
+    where
+        P: Fn(&Instant) -> bool,
+    {
+        let (mut low, mut high) = (0, len);
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+
+            // Items below `len` are committed, so they are always found.
+            match self.log.get(mid) {
+                Some((at, _)) if pred(at) => low = mid + 1,
+                _ => high = mid,
+            }
+        }
+
+        low
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TimestampedLog<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimestampedLog")
+            .field("log", &self.log)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_timestamped_log_range_by_time() {
+        init();
+
+        let log = TimestampedLog::new(401);
+
+        thread::scope(|s| {
+            for t in 0..4 {
+                let log = &log;
+                s.spawn(move || {
+                    for i in 0..100 {
+                        log.push(t * 100 + i).unwrap();
+                    }
+                });
+            }
+        });
+
+        // Timestamps follow the indices, even between concurrent producers.
+        let stamps: Vec<_> = log.log().iter().map(|&(at, _)| at).collect();
+        assert!(stamps.windows(2).all(|w| w[0] <= w[1]));
+
+        let (mid, _) = log.get(200).unwrap();
+        let before = log.indices_by_time(..mid);
+        let after = log.indices_by_time(mid..);
+
+        assert_eq!(before.end, after.start);
+        assert!(after.start <= 200);
+        assert_eq!(after.end, 400);
+        assert_eq!(
+            log.range_by_time(mid..=mid).count(),
+            after.len()
+                - log
+                    .indices_by_time((Bound::Excluded(mid), Bound::Unbounded))
+                    .len()
+        );
+
+        thread::sleep(Duration::from_millis(1));
+        let start = Instant::now();
+        log.push(400).unwrap();
+
+        assert_eq!(log.indices_by_time(start..), 400..401);
+        assert_eq!(log.range_by_time(..start).count(), 400);
+        assert_eq!(log.since(Duration::from_secs(3600)).count(), 401);
+        assert!(log.push(401).is_err());
+    }
+}
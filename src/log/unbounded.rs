@@ -0,0 +1,1464 @@
+//! This module contains the implementation of the unbounded `Channel` type.
+
+use crate::bounded::Log;
+use crate::capacity::Capacity;
+use crate::log::bounded::debug_entries;
+use crate::log::delayed::TimerWheel;
+use crate::log::filtered::Filters;
+use crate::log::growth::Layout;
+#[cfg(feature = "metrics")]
+use crate::log::instrument;
+use crate::log::table::SegmentTable;
+#[cfg(feature = "wal")]
+use crate::log::wal::Wal;
+use crate::notifier::Listeners;
+#[cfg(not(feature = "safe-impl"))]
+use crate::sync::AtomicPtr;
+use crate::sync::{AtomicBool, AtomicUsize, Ordering};
+use crate::{CancelToken, ConfigError, LogError, Notifier, RecvError};
+
+use std::fmt;
+#[cfg(feature = "wal")]
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use cache_padded::CachePadded;
+
+pub use crate::log::expiring::Expiring;
+pub use crate::log::filtered::FilteredSubscription;
+pub use crate::log::growth::GrowthPolicy;
+pub use crate::log::sealed::FrozenChannel;
+#[cfg(feature = "wal")]
+pub use crate::log::wal::Frame;
+
+/// Default number of items held by each segment of a Channel.
+pub const SEGMENT_CAPACITY: usize = 1024;
+
+/// Longest time a cancellable wait goes without checking its token.
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A check run on every item before it is pushed on a Channel.
+type Validator<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+
+/// A segment of a Channel: a bounded Log, holding the items from its offset.
+struct Segment<T> {
+    /// Position of the segment in the channel, from 0 for the first one.
+    number: usize,
+    offset: usize,
+    log: Log<T>,
+    /// Has the segment been written to disk ?
+    #[cfg(feature = "wal")]
+    persisted: AtomicBool,
+}
+
+impl<T> Segment<T> {
+    fn new(number: usize, layout: &Layout) -> Self {
+        Self {
+            number,
+            offset: layout.offset(number),
+            log: Log::new(layout.capacity(number)),
+            #[cfg(feature = "wal")]
+            persisted: AtomicBool::new(false),
+        }
+    }
+
+    /// Does this segment hold the given index ?
+    #[inline]
+    fn contains(&self, index: usize) -> bool {
+        index >= self.offset && index - self.offset < self.log.capacity()
+    }
+}
+
+/// This Channel stores an immutable, append-only, unbounded, concurrent sequence of items.
+///
+/// It is a sequence of bounded `Log` segments: when the last segment is full, a new one is linked
+/// after it. Segments are stored in a table indexed by their number, and their sizes follow the
+/// growth policy of the channel, so the segment holding an index is found in constant time. Items
+/// never move once pushed, so references returned by `get` stay valid for as long as the Channel is
+/// borrowed, like with a `Log`.
+///
+/// Pushes are lock-free, except for the producer which links a new segment, and the producers
+/// racing with it for the same segment.
+///
+/// # Examples
+/// ```
+/// use fremkit::unbounded::Channel;
+///
+/// let channel: Channel<u64> = Channel::with_segment_capacity(2);
+///
+/// for i in 0..5 {
+///     assert_eq!(channel.push(i).unwrap(), i as usize);
+/// }
+///
+/// assert_eq!(channel.get(4), Some(&4));
+/// assert_eq!(channel.get(5), None);
+/// assert_eq!(channel.len(), 5);
+/// ```
+pub struct Channel<T> {
+    segments: SegmentTable<Segment<T>>,
+    /// Number of the last linked segment.
+    #[cfg(feature = "safe-impl")]
+    last: AtomicUsize,
+    /// The last linked segment, cached so that pushes and reads of recent items skip the table.
+    ///
+    /// Segments never move nor get dropped before the channel, so the pointer is always valid. It
+    /// only moves forward, to a segment with a higher number.
+    #[cfg(not(feature = "safe-impl"))]
+    tail: AtomicPtr<Segment<T>>,
+    layout: Layout,
+    notifier: Notifier,
+    /// Wait sets watching the channel.
+    listeners: Listeners,
+    /// Filtered subscriptions, checked on every publication.
+    filters: Filters<T>,
+    closed: AtomicBool,
+    validator: Option<Validator<T>>,
+    /// Delayed items, created on the first delayed push.
+    scheduled: OnceLock<TimerWheel<T>>,
+    #[cfg(feature = "wal")]
+    wal: Option<Wal<T>>,
+}
+
+impl<T> Channel<T> {
+    /// Create a new empty Channel, with segments of `SEGMENT_CAPACITY` items.
+    pub fn new() -> Self {
+        Self::with_segment_capacity(SEGMENT_CAPACITY)
+    }
+
+    /// Create a new empty Channel, with segments of `segment_capacity` items.
+    /// If `segment_capacity` is 0, segments will be created with a capacity of 1: use a
+    /// `ChannelBuilder` to have it reported as an error instead.
+    pub fn with_segment_capacity(segment_capacity: usize) -> Self {
+        Self::with_policy(segment_capacity, GrowthPolicy::Constant)
+    }
+
+    /// Create a new empty Channel, whose first segment holds `segment_capacity` items and whose next
+    /// segments are sized by a growth policy.
+    /// If `segment_capacity` is 0, the first segment will be created with a capacity of 1, and a
+    /// cap of 0 is raised to 1: use a `ChannelBuilder` to have them reported as errors instead.
+    pub fn with_policy(segment_capacity: usize, policy: GrowthPolicy) -> Self {
+        let layout = Layout::new(segment_capacity, policy);
+        let segments = SegmentTable::new();
+        // Buckets are allocated on the heap: moving the table does not move its segments.
+        #[cfg_attr(feature = "safe-impl", allow(unused_variables))]
+        let head = segments.get_or_init(0, || Segment::new(0, &layout)) as *const Segment<T>;
+
+        Self {
+            segments,
+            #[cfg(feature = "safe-impl")]
+            last: AtomicUsize::new(0),
+            #[cfg(not(feature = "safe-impl"))]
+            tail: AtomicPtr::new(head as *mut Segment<T>),
+            layout,
+            notifier: Notifier::new(),
+            listeners: Listeners::new(),
+            filters: Filters::new(),
+            closed: AtomicBool::new(false),
+            validator: None,
+            scheduled: OnceLock::new(),
+            #[cfg(feature = "wal")]
+            wal: None,
+        }
+    }
+
+    /// Create a new empty Channel, with segments sized by a preset checked at compile time.
+    ///
+    /// See the `capacity` module.
+    pub fn with_segment_preset<C: Capacity>() -> Self {
+        Self::with_segment_capacity(C::CAPACITY)
+    }
+
+    /// Get the number of items held by the first segment, and by every segment with a constant
+    /// growth policy.
+    #[inline]
+    pub fn segment_capacity(&self) -> usize {
+        self.layout.first()
+    }
+
+    /// Get the policy sizing the segments of the channel.
+    #[inline]
+    pub fn policy(&self) -> GrowthPolicy {
+        self.layout.policy()
+    }
+
+    /// Get the number of segments linked so far.
+    pub fn segment_count(&self) -> usize {
+        self.tail().number + 1
+    }
+
+    /// Iterate over the capacities of the segments linked so far, in order.
+    pub fn segment_sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..)
+            .map_while(|number| self.segments.get(number))
+            .map(|segment| segment.log.capacity())
+    }
+
+    /// Get the current length of the channel.
+    ///
+    /// This is the number of items pushed on the channel up to the committed length of its last
+    /// segment. Items of the previous segments can still be in the middle of a write.
+    pub fn len(&self) -> usize {
+        let tail = self.tail();
+
+        tail.offset + tail.log.len()
+    }
+
+    /// Is the channel empty ?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Close the channel, signaling the end of the stream to its readers.
+    ///
+    /// Pushing on a closed channel returns a `LogClosed` error holding the item, and readers waiting
+    /// for an item past the end of the channel stop waiting. Items pushed before the channel was
+    /// closed stay readable. A push racing with `close` may still succeed, but readers are not
+    /// guaranteed to wait for it. Delayed items which have not been published yet are never
+    /// published.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::thread;
+    ///
+    /// use fremkit::unbounded::Channel;
+    ///
+    /// let channel: Channel<u64> = Channel::new();
+    ///
+    /// thread::scope(|s| {
+    ///     s.spawn(|| {
+    ///         channel.push(1).unwrap();
+    ///         channel.close();
+    ///     });
+    ///
+    ///     let mut cursor = channel.cursor();
+    ///     assert_eq!(cursor.next_blocking(), Some(&1));
+    ///     assert_eq!(cursor.next_blocking(), None);
+    /// });
+    ///
+    /// assert!(channel.push(2).is_err());
+    /// ```
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+
+        // Readers wait for a segment to be linked, or for an item of the last segment.
+        self.notifier.notify();
+        self.tail().log.notify_all();
+        self.listeners.notify();
+        self.filters.close();
+    }
+
+    /// Has the channel been closed ?
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Get the wait sets watching the channel.
+    #[inline]
+    pub(crate) fn listeners(&self) -> &Listeners {
+        &self.listeners
+    }
+
+    /// Get the filtered subscriptions of the channel.
+    #[inline]
+    pub(crate) fn filters(&self) -> &Filters<T> {
+        &self.filters
+    }
+
+    /// Is there no item to wait for at this index, because the channel was closed before it ?
+    #[inline]
+    pub(crate) fn is_closed_before(&self, index: usize) -> bool {
+        self.is_closed() && index >= self.len()
+    }
+
+    /// Get an item from the channel.
+    ///
+    /// # Returns
+    /// A reference to the item at the given index, or `None` if it has not been pushed yet.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let item = self.get_unobserved(index);
+
+        #[cfg(feature = "metrics")]
+        instrument::channel_get(item.is_some());
+
+        item
+    }
+
+    /// Get an item from the channel, without reporting the read to the metrics.
+    fn get_unobserved(&self, index: usize) -> Option<&T> {
+        // Recent items are read from the cached tail, without looking the segment up.
+        let tail = self.tail();
+        let segment = if tail.contains(index) {
+            tail
+        } else {
+            self.segment(index)?
+        };
+
+        segment.log.get(index - segment.offset)
+    }
+
+    /// Reject the items for which `validator` returns false, at push time.
+    ///
+    /// Every subscriber of a Channel holds on to every item pushed on it: a validator keeps a
+    /// misbehaving producer from pushing malformed items for all of them. Validators added with this
+    /// method and with `with_max_entry_size` are all checked.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::unbounded::Channel;
+    /// use fremkit::LogError;
+    ///
+    /// let channel = Channel::new().with_validator(|line: &String| !line.contains('\n'));
+    ///
+    /// assert_eq!(channel.push("one line".to_string()).unwrap(), 0);
+    /// assert!(matches!(
+    ///     channel.push("two\nlines".to_string()),
+    ///     Err(LogError::LogRejected(_))
+    /// ));
+    /// ```
+    pub fn with_validator<F>(mut self, validator: F) -> Self
+    where
+        T: 'static,
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        self.validator = Some(match self.validator.take() {
+            Some(previous) => Box::new(move |value| previous(value) && validator(value)),
+            None => Box::new(validator),
+        });
+        self
+    }
+
+    /// Reject the items larger than `max_size`, as measured by `size`, at push time.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::unbounded::Channel;
+    ///
+    /// let channel = Channel::new().with_max_entry_size(4, Vec::len);
+    ///
+    /// assert!(channel.push(vec![0u8; 4]).is_ok());
+    /// assert!(channel.push(vec![0u8; 1 << 20]).is_err());
+    /// assert_eq!(channel.len(), 1);
+    /// ```
+    pub fn with_max_entry_size<F>(self, max_size: usize, size: F) -> Self
+    where
+        T: 'static,
+        F: Fn(&T) -> usize + Send + Sync + 'static,
+    {
+        self.with_validator(move |value| size(value) <= max_size)
+    }
+
+    /// Append an item to the channel, if it passes the validators of the channel.
+    ///
+    /// # Returns
+    /// The index of the item in the channel, or an error containing the item if it was rejected by
+    /// a validator (`LogRejected`) or the channel is closed (`LogClosed`).
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::unbounded::Channel;
+    /// use fremkit::LogError;
+    ///
+    /// let channel: Channel<u64> = Channel::new();
+    ///
+    /// assert_eq!(channel.push(1).unwrap(), 0);
+    ///
+    /// channel.close();
+    ///
+    /// assert!(matches!(channel.push(2), Err(LogError::LogClosed(2))));
+    /// ```
+    pub fn push(&self, value: T) -> Result<usize, LogError<T>> {
+        let result = self.push_validated(value);
+
+        #[cfg(feature = "metrics")]
+        instrument::channel_push(&result);
+
+        result
+    }
+
+    fn push_validated(&self, value: T) -> Result<usize, LogError<T>> {
+        if self.is_closed() {
+            return Err(LogError::LogClosed(value));
+        }
+
+        let value = self.validate(value)?;
+
+        self.publish_due();
+
+        Ok(self.publish(value))
+    }
+
+    /// Append an item, without checking whether the channel is closed nor running its validators.
+    ///
+    /// This is for the channels kept by the crate itself, which are never closed nor validated.
+    pub(crate) fn append(&self, value: T) -> usize {
+        self.publish(value)
+    }
+
+    /// Check an item against the validators of the channel.
+    pub(crate) fn validate(&self, value: T) -> Result<T, LogError<T>> {
+        match &self.validator {
+            Some(validator) if !validator(&value) => Err(LogError::LogRejected(value)),
+            _ => Ok(value),
+        }
+    }
+
+    /// Append every delayed item which is due, in deadline order.
+    ///
+    /// This is done on every push: calling it is only needed when no producer pushes for a while.
+    pub fn publish_due(&self) {
+        if self.is_closed() {
+            return;
+        }
+
+        if let Some(wheel) = self.scheduled() {
+            wheel.take_due(Instant::now(), |value| {
+                self.publish(value);
+            });
+        }
+    }
+
+    /// Get the timer wheel of the delayed items, if any item has been delayed.
+    #[inline]
+    pub(crate) fn scheduled(&self) -> Option<&TimerWheel<T>> {
+        self.scheduled.get()
+    }
+
+    /// Get the timer wheel of the delayed items, creating it if needed.
+    pub(crate) fn timer_wheel(&self) -> &TimerWheel<T> {
+        self.scheduled.get_or_init(TimerWheel::new)
+    }
+
+    /// Append a validated item to the last segment, linking a new one if it is full.
+    fn publish(&self, value: T) -> usize {
+        let mut value = value;
+        let mut segment = self.tail();
+
+        loop {
+            match segment.log.push_unobserved(value) {
+                Ok(local) => {
+                    #[cfg(feature = "wal")]
+                    self.persist(segment);
+
+                    let index = segment.offset + local;
+
+                    if self.filters.is_active() {
+                        if let Some(item) = segment.log.get(local) {
+                            self.filters.route(index, item);
+                        }
+                    }
+                    self.listeners.notify();
+
+                    return index;
+                }
+                Err(v) => {
+                    value = v;
+                    segment = self.grow(segment);
+                }
+            }
+        }
+    }
+
+    /// Get an item from the channel, blocking until it becomes available.
+    ///
+    /// Note that if no producer ever pushes up to this index, this will block until the channel is
+    /// closed.
+    ///
+    /// # Returns
+    /// A reference to the item at the given index, or `None` if the channel was closed before it.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// use fremkit::unbounded::Channel;
+    ///
+    /// let channel: Arc<Channel<u64>> = Arc::new(Channel::with_segment_capacity(1));
+    /// let producer = channel.clone();
+    ///
+    /// thread::spawn(move || {
+    ///     producer.push(1).unwrap();
+    ///     producer.push(2).unwrap();
+    /// });
+    ///
+    /// assert_eq!(channel.wait_for(1), Some(&2));
+    /// ```
+    pub fn wait_for(&self, index: usize) -> Option<&T> {
+        self.wait(index, None).ok()
+    }
+
+    /// Get an item from the channel, blocking until it becomes available or the deadline is
+    /// reached.
+    ///
+    /// # Returns
+    /// A reference to the item at the given index, a `Timeout` error if it was not pushed before
+    /// the deadline, or a `Closed` error if the channel was closed before it.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::{Duration, Instant};
+    ///
+    /// use fremkit::unbounded::Channel;
+    /// use fremkit::RecvError;
+    ///
+    /// let channel: Channel<u64> = Channel::new();
+    /// channel.push(1).unwrap();
+    ///
+    /// let deadline = Instant::now() + Duration::from_millis(10);
+    ///
+    /// assert_eq!(channel.wait_for_deadline(0, deadline), Ok(&1));
+    /// assert_eq!(channel.wait_for_deadline(1, deadline), Err(RecvError::Timeout));
+    /// ```
+    pub fn wait_for_deadline(&self, index: usize, deadline: Instant) -> Result<&T, RecvError> {
+        self.wait(index, Some(deadline))
+    }
+
+    /// Get an item from the channel, blocking until it becomes available or the timeout elapses.
+    ///
+    /// See `wait_for_deadline`.
+    pub fn wait_for_timeout(&self, index: usize, timeout: Duration) -> Result<&T, RecvError> {
+        self.wait_for_deadline(index, Instant::now() + timeout)
+    }
+
+    /// Get an item from the channel, blocking until it becomes available or the token is
+    /// cancelled.
+    ///
+    /// Cancelling does not wake the waiting thread up: the token is checked every
+    /// 10 milliseconds, which bounds the latency of a cancellation.
+    ///
+    /// # Returns
+    /// A reference to the item at the given index, a `Cancelled` error if the token was cancelled
+    /// first, or a `Closed` error if the channel was closed before it.
+    pub fn wait_for_cancellable(&self, index: usize, token: &CancelToken) -> Result<&T, RecvError> {
+        loop {
+            if token.is_cancelled() {
+                return Err(RecvError::Cancelled);
+            }
+
+            match self.wait_for_timeout(index, CANCEL_CHECK_INTERVAL) {
+                Err(RecvError::Timeout) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// Wait for an item, until the channel is closed before it or the deadline is reached.
+    fn wait(&self, index: usize, deadline: Option<Instant>) -> Result<&T, RecvError> {
+        #[cfg(feature = "metrics")]
+        let start = Instant::now();
+
+        #[cfg(feature = "tracing")]
+        let _span = self
+            .get_unobserved(index)
+            .is_none()
+            .then(|| tracing::debug_span!("wait_for", index, ?deadline).entered());
+
+        let closed = || self.is_closed_before(index);
+        let pending = || self.segment(index).is_none() && !closed();
+
+        match deadline {
+            Some(deadline) => {
+                self.notifier.wait_while_timeout(
+                    pending,
+                    deadline.saturating_duration_since(Instant::now()),
+                );
+            }
+            None => self.notifier.wait_while(pending),
+        }
+
+        let item = self.segment(index).and_then(|segment| {
+            segment
+                .log
+                .wait_until(index - segment.offset, deadline, closed)
+        });
+
+        #[cfg(feature = "metrics")]
+        instrument::channel_wait(start.elapsed());
+
+        match item {
+            Some(item) => Ok(item),
+            None if closed() => Err(RecvError::Closed(index)),
+            None => Err(RecvError::Timeout),
+        }
+    }
+
+    /// Create an iterator over the channel.
+    ///
+    /// The iterator will start at the beginning of the channel, and stop at the first item which is
+    /// not available yet.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::unbounded::Channel;
+    ///
+    /// let channel: Channel<u64> = Channel::with_segment_capacity(2);
+    /// channel.push(1).unwrap();
+    /// channel.push(2).unwrap();
+    /// channel.push(3).unwrap();
+    ///
+    /// assert_eq!(channel.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+    /// ```
+    pub fn iter(&self) -> ChannelIterator<'_, T> {
+        ChannelIterator {
+            idx: 0,
+            segment: self.head(),
+            segments: &self.segments,
+        }
+    }
+
+    /// Iterate over the items of the channel from the newest one backwards.
+    ///
+    /// The iterator starts from the length of the channel when it is created: items pushed after
+    /// that are not returned. Like `iter`, it stops at the first item which is not available yet.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::unbounded::Channel;
+    ///
+    /// let channel: Channel<u64> = Channel::with_segment_capacity(2);
+    /// channel.push(1).unwrap();
+    /// channel.push(2).unwrap();
+    /// channel.push(3).unwrap();
+    ///
+    /// assert_eq!(channel.iter_rev().take(2).collect::<Vec<_>>(), vec![&3, &2]);
+    /// ```
+    pub fn iter_rev(&self) -> ChannelRevIterator<'_, T> {
+        let tail = self.tail();
+
+        ChannelRevIterator {
+            remaining: tail.offset + tail.log.len(),
+            segment: tail,
+            segments: &self.segments,
+        }
+    }
+
+    /// Create a cursor following the channel from its beginning.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::unbounded::Channel;
+    ///
+    /// let channel: Channel<u64> = Channel::new();
+    /// let mut cursor = channel.cursor();
+    ///
+    /// channel.push(1).unwrap();
+    ///
+    /// assert_eq!(cursor.remaining(), 1);
+    /// assert_eq!(cursor.next(), Some(&1));
+    /// assert_eq!(cursor.next(), None);
+    ///
+    /// channel.push(2).unwrap();
+    ///
+    /// assert_eq!(cursor.next_blocking(), Some(&2));
+    /// ```
+    pub fn cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            idx: 0,
+            channel: self,
+        }
+    }
+
+    /// Take a snapshot of the channel, and create a cursor following it from right after it.
+    ///
+    /// The snapshot holds every item up to the first one which is not available yet, and the cursor
+    /// starts at that item: every item of the channel is either in the snapshot or read by the
+    /// cursor, exactly once.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::unbounded::Channel;
+    ///
+    /// let channel: Channel<u64> = Channel::new();
+    /// channel.push(1).unwrap();
+    /// channel.push(2).unwrap();
+    ///
+    /// let (snapshot, mut cursor) = channel.subscribe_from_snapshot();
+    /// channel.push(3).unwrap();
+    ///
+    /// assert_eq!(snapshot, vec![&1, &2]);
+    /// assert_eq!(cursor.next(), Some(&3));
+    /// ```
+    pub fn subscribe_from_snapshot(&self) -> (Vec<&T>, Cursor<'_, T>) {
+        let mut cursor = self.cursor();
+        let snapshot = cursor.by_ref().collect();
+
+        (snapshot, cursor)
+    }
+
+    /// Create a receiver sharing the items of the channel between workers.
+    ///
+    /// Every item is received by exactly one of the workers sharing the receiver.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::unbounded::Channel;
+    ///
+    /// let channel: Channel<u64> = Channel::new();
+    /// channel.push(1).unwrap();
+    /// channel.push(2).unwrap();
+    ///
+    /// let queue = channel.work_queue();
+    ///
+    /// assert_eq!(queue.try_recv(), Some((0, &1)));
+    /// assert_eq!(queue.recv(), Some((1, &2)));
+    /// assert_eq!(queue.try_recv(), None);
+    /// ```
+    pub fn work_queue(&self) -> WorkQueueReceiver<'_, T> {
+        WorkQueueReceiver {
+            next: CachePadded::new(AtomicUsize::new(0)),
+            channel: self,
+        }
+    }
+
+    /// Consume the channel, returning the logs of its segments in order.
+    pub(crate) fn into_logs(self) -> impl Iterator<Item = Log<T>> {
+        self.segments.into_segments().map(|segment| segment.log)
+    }
+
+    /// Persist every full segment from now on.
+    #[cfg(feature = "wal")]
+    pub(crate) fn with_wal(mut self, wal: Wal<T>) -> Self {
+        self.wal = Some(wal);
+        self
+    }
+
+    /// Write a segment to disk once all of its items have been committed.
+    ///
+    /// Every producer pushing on a full segment checks it, but only the first one writes it.
+    #[cfg(feature = "wal")]
+    fn persist(&self, segment: &Segment<T>) {
+        let Some(wal) = &self.wal else {
+            return;
+        };
+
+        if segment.log.len() == segment.log.capacity()
+            && !segment.persisted.swap(true, Ordering::AcqRel)
+        {
+            if let Err(err) = wal.write_segment(segment.offset, &segment.log) {
+                ::log::error!(
+                    "fremkit: cannot persist the segment at {}: {}",
+                    segment.offset,
+                    err
+                );
+            }
+        }
+    }
+
+    /// Get the segment holding an index, if it has been linked yet.
+    #[inline]
+    fn segment(&self, index: usize) -> Option<&Segment<T>> {
+        self.segments.get(self.layout.locate(index))
+    }
+
+    /// Link the segment following a full one, or get it if another producer did it first.
+    fn grow<'a>(&'a self, full: &'a Segment<T>) -> &'a Segment<T> {
+        let number = full.number + 1;
+        let next = self.link(number);
+
+        self.advance_tail(next);
+        self.notifier.notify();
+
+        next
+    }
+
+    /// Get a segment by number, linking it if needed.
+    fn link(&self, number: usize) -> &Segment<T> {
+        self.segments.get_or_init(number, || {
+            #[cfg(feature = "metrics")]
+            instrument::channel_segment();
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                offset = self.layout.offset(number),
+                capacity = self.layout.capacity(number),
+                "linking a new channel segment"
+            );
+
+            Segment::new(number, &self.layout)
+        })
+    }
+
+    /// Get the first segment.
+    #[inline]
+    fn head(&self) -> &Segment<T> {
+        // The first segment is linked on creation: this never links it again.
+        self.link(0)
+    }
+
+    /// Get the last linked segment.
+    #[inline]
+    #[cfg(feature = "safe-impl")]
+    fn tail(&self) -> &Segment<T> {
+        // Segments are stored before being counted in `last`: this never links a new one.
+        self.link(self.last.load(Ordering::Acquire))
+    }
+
+    /// Get the last linked segment.
+    #[inline]
+    #[cfg(not(feature = "safe-impl"))]
+    fn tail(&self) -> &Segment<T> {
+        // SAFETY: the pointer is set from references to segments of the table, which are never
+        // moved nor dropped before the channel.
+        unsafe { &*self.tail.load(Ordering::Acquire) }
+    }
+
+    /// Record a newly linked segment as the last one, unless a later one was recorded first.
+    #[cfg(feature = "safe-impl")]
+    fn advance_tail(&self, segment: &Segment<T>) {
+        self.last.fetch_max(segment.number, Ordering::AcqRel);
+    }
+
+    /// Record a newly linked segment as the last one, unless a later one was recorded first.
+    #[cfg(not(feature = "safe-impl"))]
+    fn advance_tail(&self, segment: &Segment<T>) {
+        let mut current = self.tail.load(Ordering::Acquire);
+
+        // SAFETY: see `tail`.
+        while unsafe { (*current).number } < segment.number {
+            match self.tail.compare_exchange_weak(
+                current,
+                segment as *const Segment<T> as *mut Segment<T>,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+}
+
+impl<T> Default for Channel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Only the first and last few entries are shown, like for a Log.
+impl<T: fmt::Debug> fmt::Debug for Channel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel")
+            .field("segment_capacity", &self.segment_capacity())
+            .field("len", &self.len())
+            .field("entries", &DebugEntries(self))
+            .finish()
+    }
+}
+
+struct DebugEntries<'a, T>(&'a Channel<T>);
+
+impl<'a, T: fmt::Debug> fmt::Debug for DebugEntries<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let channel = self.0;
+
+        debug_entries(f, channel.len(), |index| channel.get_unobserved(index))
+    }
+}
+
+//
+// Builder API, for callers who want configuration errors to be reported instead of silently fixed.
+//
+
+/// Builder for a Channel.
+///
+/// Unlike `Channel::with_policy`, which silently raises a segment capacity of 0 to 1, the builder
+/// reports invalid configurations as errors.
+///
+/// # Examples
+/// ```
+/// use fremkit::unbounded::{ChannelBuilder, GrowthPolicy};
+///
+/// let channel = ChannelBuilder::new()
+///     .segment_capacity(2)
+///     .policy(GrowthPolicy::Doubling)
+///     .build::<u64>()
+///     .unwrap();
+///
+/// assert_eq!(channel.segment_capacity(), 2);
+/// assert!(ChannelBuilder::new().segment_capacity(0).build::<u64>().is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChannelBuilder {
+    segment_capacity: usize,
+    policy: GrowthPolicy,
+}
+
+impl ChannelBuilder {
+    /// Create a new builder, for segments of `SEGMENT_CAPACITY` items of constant size.
+    pub fn new() -> Self {
+        Self {
+            segment_capacity: SEGMENT_CAPACITY,
+            policy: GrowthPolicy::Constant,
+        }
+    }
+
+    /// Set the capacity of the first segment of the Channel.
+    ///
+    /// # Arguments
+    /// * `segment_capacity` - The number of items held by the first segment. Must be greater than 0.
+    pub fn segment_capacity(mut self, segment_capacity: usize) -> Self {
+        self.segment_capacity = segment_capacity;
+        self
+    }
+
+    /// Set the policy sizing the segments following the first one.
+    ///
+    /// # Arguments
+    /// * `policy` - The growth policy. A capped policy must have a cap greater than 0.
+    pub fn policy(mut self, policy: GrowthPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Build the Channel.
+    ///
+    /// # Returns
+    /// A new empty Channel, or an error if the configuration is invalid.
+    pub fn build<T>(self) -> Result<Channel<T>, ConfigError> {
+        self.check()?;
+
+        Ok(Channel::with_policy(self.segment_capacity, self.policy))
+    }
+
+    /// Open a Channel persisted in a directory, creating the directory if needed.
+    ///
+    /// See `Channel::open_from_dir`. The segment files found in the directory must match the
+    /// segment sizes of the builder.
+    ///
+    /// # Returns
+    /// The Channel, or an error if the configuration is invalid, or the directory cannot be read.
+    #[cfg(feature = "wal")]
+    pub fn open<T: Frame, P: AsRef<Path>>(self, path: P) -> Result<Channel<T>, ConfigError> {
+        self.check()?;
+
+        Ok(Channel::open_with(
+            path.as_ref(),
+            Some(self.segment_capacity),
+            self.policy,
+        )?)
+    }
+
+    fn check(&self) -> Result<(), ConfigError> {
+        if self.segment_capacity == 0 {
+            return Err(ConfigError::InvalidCapacity(self.segment_capacity));
+        }
+
+        if let GrowthPolicy::Capped(0) = self.policy {
+            return Err(ConfigError::InvalidGrowthPolicy(self.policy));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ChannelBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reader following a Channel at its own pace.
+///
+/// A cursor remembers the index of the next item to read. Unlike the iterator, reaching the end of
+/// the channel is not final: `next` returns `None` until another item is pushed, and then yields it.
+#[derive(Debug)]
+pub struct Cursor<'a, T> {
+    idx: usize,
+    channel: &'a Channel<T>,
+}
+
+impl<'a, T> Cursor<'a, T> {
+    /// Get the index of the next item to be read.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.idx
+    }
+
+    /// Get the number of items available to the cursor without blocking.
+    pub fn remaining(&self) -> usize {
+        self.channel.len().saturating_sub(self.idx)
+    }
+
+    /// Read the next item, blocking until it becomes available.
+    ///
+    /// # Returns
+    /// The next item, or `None` once the channel is closed and every item has been read.
+    pub fn next_blocking(&mut self) -> Option<&'a T> {
+        let item = self.channel.wait_for(self.idx)?;
+        self.idx += 1;
+
+        Some(item)
+    }
+}
+
+impl<'a, T> Iterator for Cursor<'a, T> {
+    type Item = &'a T;
+
+    /// Read the next item, if it is available.
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.channel.get(self.idx)?;
+        self.idx += 1;
+
+        Some(item)
+    }
+}
+
+/// Receiver sharing the items of a Channel between workers, returned by `Channel::work_queue`.
+///
+/// Workers share the receiver by reference, and each call claims the next unclaimed index.
+#[derive(Debug)]
+pub struct WorkQueueReceiver<'a, T> {
+    next: CachePadded<AtomicUsize>,
+    channel: &'a Channel<T>,
+}
+
+impl<'a, T> WorkQueueReceiver<'a, T> {
+    /// Claim the next item, if it is available.
+    ///
+    /// # Returns
+    /// The index and the item, or `None` if every available item has been claimed.
+    pub fn try_recv(&self) -> Option<(usize, &'a T)> {
+        let mut index = self.next.load(Ordering::Relaxed);
+
+        loop {
+            let item = self.channel.get(index)?;
+
+            match self.next.compare_exchange_weak(
+                index,
+                index + 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some((index, item)),
+                Err(current) => index = current,
+            }
+        }
+    }
+
+    /// Claim the next index, and block until its item becomes available.
+    ///
+    /// # Returns
+    /// The index and the item, or `None` if the channel was closed before the claimed index.
+    pub fn recv(&self) -> Option<(usize, &'a T)> {
+        let index = self.next.fetch_add(1, Ordering::Relaxed);
+
+        Some((index, self.channel.wait_for(index)?))
+    }
+}
+
+/// Iterator over the items in a Channel.
+pub struct ChannelIterator<'a, T> {
+    idx: usize,
+    segment: &'a Segment<T>,
+    segments: &'a SegmentTable<Segment<T>>,
+}
+
+impl<'a, T> Iterator for ChannelIterator<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.segment.contains(self.idx) {
+            self.segment = self.segments.get(self.segment.number + 1)?;
+        }
+
+        let item = self.segment.log.get(self.idx - self.segment.offset)?;
+        self.idx += 1;
+
+        Some(item)
+    }
+}
+
+/// Iterator over the items in a Channel, from the newest one backwards.
+pub struct ChannelRevIterator<'a, T> {
+    /// Number of items left to return: the next one is at `remaining - 1`.
+    remaining: usize,
+    segment: &'a Segment<T>,
+    segments: &'a SegmentTable<Segment<T>>,
+}
+
+impl<'a, T> Iterator for ChannelRevIterator<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let idx = self.remaining.checked_sub(1)?;
+
+        if idx < self.segment.offset {
+            self.segment = self.segments.get(self.segment.number - 1)?;
+        }
+
+        let item = self.segment.log.get(idx - self.segment.offset)?;
+        self.remaining = idx;
+
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::sync::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    #[cfg(loom)]
+    fn test_loom() {
+        loom::model(test_channel_grow);
+    }
+
+    #[test]
+    fn test_channel() {
+        init();
+
+        let channel = Channel::with_segment_capacity(3);
+
+        for i in 0..10 {
+            assert_eq!(channel.push(i).unwrap(), i);
+        }
+
+        assert_eq!(channel.len(), 10);
+        assert_eq!(channel.get(0), Some(&0));
+        assert_eq!(channel.get(9), Some(&9));
+        assert_eq!(channel.get(10), None);
+        assert_eq!(channel.iter().count(), 10);
+        assert_eq!(
+            format!("{:?}", channel),
+            "Channel { segment_capacity: 3, len: 10, entries: [0, 1, 2, 3, .., 6, 7, 8, 9] }"
+        );
+        assert_eq!(channel.segment_count(), 4);
+    }
+
+    #[test]
+    fn test_channel_growth_policy() {
+        init();
+
+        let channel = Arc::new(Channel::with_policy(1, GrowthPolicy::Doubling));
+        let producer = channel.clone();
+
+        let handle = thread::spawn(move || {
+            for i in 0..100 {
+                producer.push(i).unwrap();
+            }
+        });
+
+        handle.join().unwrap();
+
+        assert_eq!(
+            channel.segment_sizes().collect::<Vec<_>>(),
+            vec![1, 2, 4, 8, 16, 32, 64]
+        );
+        assert_eq!(channel.policy(), GrowthPolicy::Doubling);
+        assert_eq!(channel.len(), 100);
+        assert!((0..100).all(|i| channel.get(i) == Some(&i)));
+        assert!(channel.iter().copied().eq(0..100));
+    }
+
+    #[test]
+    fn test_channel_builder() {
+        init();
+
+        let channel: Channel<usize> = ChannelBuilder::new()
+            .segment_capacity(2)
+            .policy(GrowthPolicy::Capped(4))
+            .build()
+            .unwrap();
+        for i in 0..10 {
+            channel.push(i).unwrap();
+        }
+
+        assert_eq!(channel.segment_sizes().collect::<Vec<_>>(), vec![2, 4, 4]);
+
+        // Configurations which the constructors silently fix are reported.
+        let err = ChannelBuilder::new()
+            .segment_capacity(0)
+            .build::<usize>()
+            .unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidCapacity(0)));
+
+        let err = ChannelBuilder::new()
+            .policy(GrowthPolicy::Capped(0))
+            .build::<usize>()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::InvalidGrowthPolicy(GrowthPolicy::Capped(0))
+        ));
+    }
+
+    #[test]
+    fn test_channel_grow() {
+        init();
+
+        let channel = Arc::new(Channel::with_segment_capacity(1));
+        let producer = channel.clone();
+
+        let h1 = thread::spawn(move || producer.push(1).unwrap());
+
+        let index = channel.push(2).unwrap();
+        let other = h1.join().unwrap();
+
+        assert_eq!(index + other, 1);
+        assert_eq!(channel.get(index), Some(&2));
+        assert_eq!(channel.get(other), Some(&1));
+        assert_eq!(channel.len(), 2);
+        assert_eq!(channel.segment_count(), 2);
+    }
+
+    #[test]
+    fn test_channel_iter_rev() {
+        init();
+
+        let channel = Channel::with_policy(1, GrowthPolicy::Doubling);
+
+        assert_eq!(channel.iter_rev().next(), None);
+
+        for i in 0..10 {
+            channel.push(i).unwrap();
+        }
+
+        let mut iter = channel.iter_rev();
+        channel.push(10).unwrap();
+
+        assert_eq!(iter.next(), Some(&9));
+        assert_eq!(
+            iter.copied().collect::<Vec<_>>(),
+            (0..9).rev().collect::<Vec<_>>()
+        );
+        assert_eq!(channel.iter_rev().next(), Some(&10));
+    }
+
+    #[test]
+    fn test_channel_tail() {
+        init();
+
+        let channel = Arc::new(Channel::with_segment_capacity(4));
+
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let producer = channel.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        producer.push(t * 100 + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        // The cached tail ends up on the last segment, and reads fall back to the table before it.
+        assert_eq!(channel.segment_count(), 100);
+        assert_eq!(channel.len(), 400);
+        assert_eq!(channel.iter().count(), 400);
+        assert!((0..400).all(|index| channel.get(index).is_some()));
+        assert_eq!(channel.get(400), None);
+    }
+
+    #[test]
+    fn test_channel_validator() {
+        init();
+
+        let channel = Channel::with_segment_capacity(2)
+            .with_max_entry_size(3, String::len)
+            .with_validator(|item: &String| item.is_ascii());
+
+        assert_eq!(channel.push("abc".to_string()).unwrap(), 0);
+        assert!(matches!(
+            channel.push("abcd".to_string()),
+            Err(LogError::LogRejected(item)) if item == "abcd"
+        ));
+        assert!(channel.push("é".to_string()).is_err());
+        assert_eq!(channel.push("d".to_string()).unwrap(), 1);
+
+        // Rejected items do not take an index.
+        assert_eq!(channel.iter().collect::<Vec<_>>(), vec!["abc", "d"]);
+    }
+
+    #[test]
+    fn test_channel_push_rejected() {
+        init();
+
+        let channel = Channel::new().with_validator(|&item: &u64| item > 0);
+
+        assert!(matches!(channel.push(0), Err(LogError::LogRejected(0))));
+        assert!(channel.is_empty());
+    }
+
+    #[test]
+    fn test_channel_subscribe_from_snapshot() {
+        init();
+
+        let channel = Arc::new(Channel::with_segment_capacity(4));
+        let producer = channel.clone();
+
+        let h1 = thread::spawn(move || {
+            for i in 0..100 {
+                producer.push(i).unwrap();
+            }
+        });
+
+        // Whenever the snapshot is taken, the snapshot and the cursor see every item once.
+        let (snapshot, mut cursor) = channel.subscribe_from_snapshot();
+        assert_eq!(cursor.position(), snapshot.len());
+
+        let mut items: Vec<_> = snapshot.into_iter().copied().collect();
+        while items.len() < 100 {
+            items.push(*cursor.next_blocking().unwrap());
+        }
+
+        h1.join().unwrap();
+
+        assert_eq!(items, (0..100).collect::<Vec<_>>());
+        assert_eq!(cursor.next(), None);
+    }
+
+    #[test]
+    fn test_channel_wait_for() {
+        init();
+
+        let channel = Arc::new(Channel::with_segment_capacity(2));
+        let producer = channel.clone();
+
+        let h1 = thread::spawn(move || {
+            for i in 0..5 {
+                producer.push(i).unwrap();
+            }
+        });
+
+        assert_eq!(channel.wait_for(4), Some(&4));
+
+        h1.join().unwrap();
+    }
+
+    #[test]
+    fn test_channel_wait_for_timeout() {
+        init();
+
+        let channel = Arc::new(Channel::with_segment_capacity(2));
+        let producer = channel.clone();
+
+        assert_eq!(
+            channel.wait_for_timeout(0, Duration::from_millis(10)),
+            Err(RecvError::Timeout)
+        );
+
+        let h1 = thread::spawn(move || {
+            for i in 0..5 {
+                producer.push(i).unwrap();
+            }
+        });
+
+        assert_eq!(channel.wait_for_timeout(4, Duration::from_secs(10)), Ok(&4));
+
+        h1.join().unwrap();
+
+        let token = CancelToken::new();
+        let waiter = token.clone();
+
+        let h2 = {
+            let channel = channel.clone();
+            thread::spawn(move || channel.wait_for_cancellable(5, &waiter).copied())
+        };
+
+        token.cancel();
+        assert_eq!(h2.join().unwrap(), Err(RecvError::Cancelled));
+        assert_eq!(channel.wait_for_cancellable(4, &CancelToken::new()), Ok(&4));
+    }
+
+    #[test]
+    fn test_channel_close_race() {
+        init();
+
+        let channel = Arc::new(Channel::with_segment_capacity(4));
+
+        let producers: Vec<_> = (0..4)
+            .map(|_| {
+                let channel = channel.clone();
+                thread::spawn(move || {
+                    let mut pushed = Vec::new();
+
+                    // Producers racing with `close` stop on the first rejected push.
+                    for i in 0.. {
+                        match channel.push(i) {
+                            Ok(index) => pushed.push(index),
+                            Err(err) => {
+                                assert!(matches!(err, LogError::LogClosed(v) if v == i));
+                                return pushed;
+                            }
+                        }
+                    }
+
+                    unreachable!()
+                })
+            })
+            .collect();
+
+        // Close the channel while the producers are running.
+        while channel.len() < 100 {
+            thread::yield_now();
+        }
+        channel.close();
+
+        let pushed: usize = producers
+            .into_iter()
+            .map(|producer| producer.join().unwrap().len())
+            .sum();
+
+        // Every accepted item is readable, and nothing is pushed after the channel is closed.
+        assert_eq!(channel.len(), pushed);
+        assert_eq!(channel.iter().count(), pushed);
+        assert!(matches!(channel.push(0), Err(LogError::LogClosed(0))));
+    }
+
+    #[test]
+    fn test_channel_close() {
+        init();
+
+        let channel = Arc::new(Channel::with_segment_capacity(2));
+
+        let consumers: Vec<_> = (0..2)
+            .map(|_| {
+                let channel = channel.clone();
+                thread::spawn(move || {
+                    let mut cursor = channel.cursor();
+                    let mut items = Vec::new();
+
+                    while let Some(&item) = cursor.next_blocking() {
+                        items.push(item);
+                    }
+
+                    items
+                })
+            })
+            .collect();
+
+        for i in 0..5 {
+            channel.push(i).unwrap();
+        }
+        channel.close();
+
+        for consumer in consumers {
+            assert_eq!(consumer.join().unwrap(), vec![0, 1, 2, 3, 4]);
+        }
+
+        assert!(channel.is_closed());
+        assert!(matches!(channel.push(5), Err(LogError::LogClosed(5))));
+        assert_eq!(channel.wait_for(3), Some(&3));
+        assert_eq!(channel.wait_for(5), None);
+        assert_eq!(
+            channel.wait_for_timeout(6, Duration::from_secs(10)),
+            Err(RecvError::Closed(6))
+        );
+        assert_eq!(channel.work_queue().recv(), Some((0, &0)));
+    }
+}
@@ -0,0 +1,303 @@
+//! This module contains the on-disk persistence of `Channel` segments, enabled by the `wal` feature.
+//!
+//! Every full segment is written to its own file, named after the index of its first item. A file
+//! is a sequence of frames: the length of the encoded item and its CRC32, both as little-endian
+//! `u32`, followed by the encoded item. Files are written under a temporary name and renamed once
+//! complete, so a crash never leaves a partial segment behind.
+
+use crate::bounded::Log;
+use crate::log::growth::Layout;
+use crate::unbounded::{Channel, GrowthPolicy, SEGMENT_CAPACITY};
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Extension of segment files.
+const EXTENSION: &str = "seg";
+
+/// An item which can be written to a segment file.
+pub trait Frame: Sized {
+    /// Encode the item into bytes.
+    fn encode(&self) -> Vec<u8>;
+
+    /// Decode an item from the bytes returned by `encode`.
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+impl Frame for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+impl Frame for String {
+    fn encode(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+macro_rules! impl_frame_for_numbers {
+    ($($t:ty),*) => {
+        $(
+            impl Frame for $t {
+                fn encode(&self) -> Vec<u8> {
+                    self.to_le_bytes().to_vec()
+                }
+
+                fn decode(bytes: &[u8]) -> Option<Self> {
+                    Some(<$t>::from_le_bytes(bytes.try_into().ok()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_frame_for_numbers!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+/// Where and how a Channel persists its full segments.
+pub(crate) struct Wal<T> {
+    dir: PathBuf,
+    encode: fn(&T) -> Vec<u8>,
+}
+
+impl<T> Wal<T> {
+    /// Write a full segment to its file, unless it has already been written.
+    pub(crate) fn write_segment(&self, offset: usize, log: &Log<T>) -> io::Result<()> {
+        let path = self.dir.join(format!("{:020}.{}", offset, EXTENSION));
+
+        if path.exists() {
+            return Ok(());
+        }
+
+        let mut bytes = Vec::new();
+        for item in log.get_range(..) {
+            let frame = (self.encode)(item);
+
+            bytes.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&crc32fast::hash(&frame).to_le_bytes());
+            bytes.extend_from_slice(&frame);
+        }
+
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+
+        fs::rename(tmp, path)
+    }
+}
+
+/// Decode the frames of a segment file.
+fn read_segment<T: Frame>(path: &Path) -> io::Result<Vec<T>> {
+    let invalid = |msg: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("fremkit: {}: {}", path.display(), msg),
+        )
+    };
+
+    let bytes = fs::read(path)?;
+    let mut rest = &bytes[..];
+    let mut items = Vec::new();
+
+    while !rest.is_empty() {
+        let Some((&[l0, l1, l2, l3, c0, c1, c2, c3], tail)) = rest.split_first_chunk::<8>() else {
+            return Err(invalid("truncated frame header"));
+        };
+
+        let len = u32::from_le_bytes([l0, l1, l2, l3]) as usize;
+        let crc = u32::from_le_bytes([c0, c1, c2, c3]);
+        rest = tail;
+
+        if rest.len() < len {
+            return Err(invalid("truncated frame"));
+        }
+
+        let (frame, tail) = rest.split_at(len);
+        if crc32fast::hash(frame) != crc {
+            return Err(invalid("frame checksum mismatch"));
+        }
+
+        items.push(T::decode(frame).ok_or_else(|| invalid("frame cannot be decoded"))?);
+        rest = tail;
+    }
+
+    Ok(items)
+}
+
+impl<T: Frame> Channel<T> {
+    /// Open a Channel persisted in a directory, creating the directory if needed.
+    ///
+    /// The items of every segment file found in the directory are pushed back on the channel, and
+    /// every segment filled from now on is written to the directory. The segment capacity is the
+    /// number of items of the existing segments, or `SEGMENT_CAPACITY` if there are none.
+    ///
+    /// Items of the last segment are only written once it is full: they are lost if the process
+    /// stops before.
+    ///
+    /// # Examples
+    /// ```
+    /// use fremkit::unbounded::Channel;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("fremkit-doc-{}", std::process::id()));
+    ///
+    /// let channel: Channel<u64> = Channel::open_from_dir(&dir).unwrap();
+    /// for i in 0..2048 {
+    ///     channel.push(i).unwrap();
+    /// }
+    /// channel.push(2048).unwrap();
+    /// drop(channel);
+    ///
+    /// let channel: Channel<u64> = Channel::open_from_dir(&dir).unwrap();
+    /// assert_eq!(channel.len(), 2048);
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn open_from_dir<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::open_with(path.as_ref(), None, GrowthPolicy::Constant)
+    }
+
+    /// Open a Channel persisted in a directory, whose segments follow a growth policy from a first
+    /// segment of `segment_capacity` items, or of the size of the first existing segment if `None`.
+    pub(crate) fn open_with(
+        dir: &Path,
+        segment_capacity: Option<usize>,
+        policy: GrowthPolicy,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.extension().is_some_and(|ext| ext == EXTENSION) {
+                files.push(path);
+            }
+        }
+        files.sort_unstable();
+
+        let segments = files
+            .iter()
+            .map(|path| read_segment::<T>(path))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let segment_capacity = segment_capacity
+            .or_else(|| segments.first().map(Vec::len))
+            .unwrap_or(SEGMENT_CAPACITY);
+        let layout = Layout::new(segment_capacity, policy);
+
+        if (0..)
+            .zip(&segments)
+            .any(|(number, items)| items.len() != layout.capacity(number))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "fremkit: segment files do not match the segment sizes of the Channel",
+            ));
+        }
+
+        let channel = Channel::with_policy(segment_capacity, policy);
+        for item in segments.into_iter().flatten() {
+            channel.append(item);
+        }
+
+        Ok(channel.with_wal(Wal {
+            dir: dir.to_path_buf(),
+            encode: T::encode,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use crate::unbounded::ChannelBuilder;
+    use crate::ConfigError;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_channel_open_from_dir() {
+        init();
+
+        let dir = std::env::temp_dir().join(format!("fremkit-wal-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let channel: Channel<String> = Channel::open_from_dir(&dir).unwrap();
+        thread::scope(|s| {
+            for t in 0..4 {
+                let channel = &channel;
+                s.spawn(move || {
+                    for i in 0..1000 {
+                        channel.push(format!("{}-{}", t, i)).unwrap();
+                    }
+                });
+            }
+        });
+        let items: Vec<_> = channel.iter().cloned().collect();
+        drop(channel);
+
+        // Three full segments of 1024 items were written, the last 928 items were not.
+        let channel: Channel<String> = Channel::open_from_dir(&dir).unwrap();
+        assert_eq!(channel.len(), 3072);
+        assert_eq!(channel.iter().cloned().collect::<Vec<_>>(), items[..3072]);
+
+        // A corrupted segment is detected.
+        let path = dir.join(format!("{:020}.{}", 1024, EXTENSION));
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&path, bytes).unwrap();
+
+        assert!(Channel::<String>::open_from_dir(&dir).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_channel_builder_open() {
+        init();
+
+        let dir = std::env::temp_dir().join(format!("fremkit-wal-builder-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let builder = ChannelBuilder::new()
+            .segment_capacity(2)
+            .policy(GrowthPolicy::Doubling);
+
+        let channel: Channel<u64> = builder.clone().open(&dir).unwrap();
+        for i in 0..7 {
+            channel.push(i).unwrap();
+        }
+        drop(channel);
+
+        // The segments of 2 and 4 items were written, the last item was not.
+        let channel: Channel<u64> = builder.open(&dir).unwrap();
+        assert_eq!(
+            channel.iter().copied().collect::<Vec<_>>(),
+            (0..6).collect::<Vec<_>>()
+        );
+        drop(channel);
+
+        // The segment files do not match constant segments of 2 items.
+        let err = ChannelBuilder::new()
+            .segment_capacity(2)
+            .open::<u64, _>(&dir)
+            .unwrap_err();
+        assert!(matches!(err, ConfigError::Persistence(_)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
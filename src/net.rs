@@ -0,0 +1,293 @@
+//! Mirroring of a bounded `Log` to remote replicas over TCP, enabled by the `net` feature.
+//!
+//! A replica connects to the primary and sends the number of items it already holds, as a
+//! little-endian `u64`. The primary then streams every item from this offset as a frame: the index
+//! of the item as a little-endian `u64`, the length of the encoded item and its CRC32, both as
+//! little-endian `u32`, followed by the encoded item. Items are encoded with their `Frame`
+//! implementation, like the segment files of a Channel.
+//!
+//! The primary closes the connection once the last slot of its log has been sent. A replica which
+//! lost its connection resumes by connecting again: it only receives the items it is missing.
+//!
+//! # Examples
+//! ```
+//! use std::net::TcpListener;
+//! use std::sync::Arc;
+//! use std::thread;
+//!
+//! use fremkit::bounded::Log;
+//! use fremkit::net::{self, MirrorLog};
+//!
+//! let primary: Arc<Log<u64>> = Arc::new(Log::new(3));
+//! let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+//! let addr = listener.local_addr().unwrap();
+//!
+//! let server = primary.clone();
+//! thread::spawn(move || net::serve(server, listener));
+//!
+//! for i in 0..3 {
+//!     primary.push(i).unwrap();
+//! }
+//!
+//! let mirror: MirrorLog<u64> = MirrorLog::new(3);
+//! mirror.sync(addr).unwrap();
+//!
+//! assert_eq!(mirror.log().iter().collect::<Vec<_>>(), vec![&0, &1, &2]);
+//! ```
+
+use crate::bounded::Log;
+use crate::unbounded::Frame;
+
+use std::fmt;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread;
+
+use parking_lot::Mutex;
+
+/// Size of a frame header: the index, length and CRC32 of the item.
+const HEADER_SIZE: usize = 16;
+
+/// Accept replicas on a listener, and stream the log to each of them from its own thread.
+///
+/// This only returns if accepting a connection fails.
+pub fn serve<T>(log: Arc<Log<T>>, listener: TcpListener) -> io::Result<()>
+where
+    T: Frame + Send + Sync + 'static,
+{
+    loop {
+        let (stream, peer) = listener.accept()?;
+        let log = log.clone();
+
+        thread::spawn(move || {
+            if let Err(err) = serve_replica(&log, stream) {
+                ::log::warn!("fremkit: replica {} disconnected: {}", peer, err);
+            }
+        });
+    }
+}
+
+/// Stream the log to a single replica, from the offset it requests.
+///
+/// Items are sent as soon as they are pushed on the log. This returns once the last slot of the log
+/// has been sent, or when writing to the replica fails. A replica disconnecting while the log is
+/// idle is only noticed with the next push.
+pub fn serve_replica<T: Frame>(log: &Log<T>, stream: TcpStream) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+
+    let mut offset = [0; 8];
+    (&stream).read_exact(&mut offset)?;
+    let mut index = u64::from_le_bytes(offset) as usize;
+
+    let mut writer = BufWriter::new(&stream);
+
+    loop {
+        let item = match log.get(index) {
+            Some(item) => item,
+            None => {
+                // Send what is buffered before waiting for the next item.
+                writer.flush()?;
+
+                match log.wait_for(index) {
+                    Some(item) => item,
+                    None => break,
+                }
+            }
+        };
+
+        write_frame(&mut writer, index, &item.encode())?;
+        index += 1;
+    }
+
+    writer.flush()
+}
+
+/// Write an item as a frame.
+fn write_frame<W: Write>(writer: &mut W, index: usize, frame: &[u8]) -> io::Result<()> {
+    writer.write_all(&(index as u64).to_le_bytes())?;
+    writer.write_all(&(frame.len() as u32).to_le_bytes())?;
+    writer.write_all(&crc32fast::hash(frame).to_le_bytes())?;
+    writer.write_all(frame)
+}
+
+/// A replica of a bounded Log, filled from a primary serving it with `serve`.
+///
+/// The replica can be read from other threads while it syncs. Its capacity should be the capacity
+/// of the primary: a replica too small for the items it receives fails to sync.
+pub struct MirrorLog<T> {
+    log: Log<T>,
+    /// Held while syncing, so a single connection pushes on the replica.
+    sync: Mutex<()>,
+}
+
+impl<T: Frame> MirrorLog<T> {
+    /// Create a new empty replica.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            log: Log::new(capacity),
+            sync: Mutex::new(()),
+        }
+    }
+
+    /// Get the replicated log.
+    #[inline]
+    pub fn log(&self) -> &Log<T> {
+        &self.log
+    }
+
+    /// Connect to a primary, and replicate its items from the current length of the replica.
+    ///
+    /// Concurrent calls are serialized.
+    ///
+    /// # Returns
+    /// `Ok` once the primary has sent its whole log and closed the connection, or an error if the
+    /// connection fails or a frame is corrupted. Items received before an error are kept: calling
+    /// `sync` again resumes after them.
+    pub fn sync<A: ToSocketAddrs>(&self, addr: A) -> io::Result<()> {
+        let _guard = self.sync.lock();
+
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(&(self.log.len() as u64).to_le_bytes())?;
+
+        let mut reader = BufReader::new(stream);
+        let mut header = [0; HEADER_SIZE];
+
+        while read_header(&mut reader, &mut header)? {
+            // The index, length and checksum are little-endian: they are the successive bits of the
+            // header read as a single little-endian integer.
+            let fields = u128::from_le_bytes(header);
+            let index = fields as u64 as usize;
+            let len = (fields >> 64) as u32 as usize;
+            let crc = (fields >> 96) as u32;
+
+            let mut frame = vec![0; len];
+            reader.read_exact(&mut frame)?;
+
+            if index != self.log.len() {
+                return Err(invalid("frame received out of order"));
+            }
+            if crc32fast::hash(&frame) != crc {
+                return Err(invalid("frame checksum mismatch"));
+            }
+
+            let item = T::decode(&frame).ok_or_else(|| invalid("frame cannot be decoded"))?;
+            self.log
+                .push(item)
+                .map_err(|_| invalid("more items than the capacity of the replica"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MirrorLog<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MirrorLog").field("log", &self.log).finish()
+    }
+}
+
+/// Read a frame header.
+///
+/// # Returns
+/// `false` if the connection was closed before the header.
+fn read_header<R: Read>(reader: &mut R, header: &mut [u8; HEADER_SIZE]) -> io::Result<bool> {
+    let mut filled = 0;
+
+    while filled < HEADER_SIZE {
+        match reader.read(&mut header[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(true)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("fremkit: {}", msg))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    /// Accept a single replica, and answer with raw frames.
+    fn fake_primary(frames: Vec<(usize, Vec<u8>, u32)>) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.read_exact(&mut [0; 8]).unwrap();
+
+            for (index, frame, crc) in frames {
+                stream.write_all(&(index as u64).to_le_bytes()).unwrap();
+                stream
+                    .write_all(&(frame.len() as u32).to_le_bytes())
+                    .unwrap();
+                stream.write_all(&crc.to_le_bytes()).unwrap();
+                stream.write_all(&frame).unwrap();
+            }
+        });
+
+        addr
+    }
+
+    #[test]
+    fn test_mirror_log_resume() {
+        init();
+
+        let primary: Arc<Log<u64>> = Arc::new(Log::new(4));
+        for i in 0..4 {
+            primary.push(i * 10).unwrap();
+        }
+
+        // The first connection drops after two items.
+        let frames = (0..2u64)
+            .map(|i| {
+                let frame = (i * 10).encode();
+                let crc = crc32fast::hash(&frame);
+                (i as usize, frame, crc)
+            })
+            .collect();
+
+        let mirror: MirrorLog<u64> = MirrorLog::new(4);
+        mirror.sync(fake_primary(frames)).unwrap();
+        assert_eq!(mirror.log().len(), 2);
+
+        // The second one only sends the missing items.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve(primary, listener));
+
+        mirror.sync(addr).unwrap();
+        assert_eq!(
+            mirror.log().iter().collect::<Vec<_>>(),
+            vec![&0, &10, &20, &30]
+        );
+    }
+
+    #[test]
+    fn test_mirror_log_corrupted() {
+        init();
+
+        let frame = 1u64.encode();
+        let crc = crc32fast::hash(&frame) ^ 1;
+
+        let mirror: MirrorLog<u64> = MirrorLog::new(4);
+        let err = mirror
+            .sync(fake_primary(vec![(0, frame, crc)]))
+            .unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(mirror.log().is_empty());
+    }
+}
@@ -0,0 +1,481 @@
+//! This module contains the implementation of the `Notifier` type.
+
+use crate::sync::{fence, AtomicBool, AtomicUsize, Condvar, Mutex, Ordering};
+
+use std::fmt;
+use std::sync::{Arc, PoisonError};
+#[cfg(feature = "async")]
+use std::task::Waker;
+use std::time::{Duration, Instant};
+
+/// A Notifier lets threads block until a condition might have changed.
+///
+/// Waiters register with `wait_if`, and are woken up by `notify`. Notifying is cheap when nobody is
+/// waiting: it is a single atomic load, so it can be called on every push of a Log.
+///
+/// A waiter can be woken up spuriously, and must recheck its condition after `wait_if` returns.
+///
+/// With the `async` feature, tasks can register their `Waker` too, and are woken up by the same
+/// notifications as blocked threads.
+///
+/// # Examples
+/// ```
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// use fremkit::Notifier;
+///
+/// let notifier = Arc::new(Notifier::new());
+/// let ready = Arc::new(AtomicBool::new(false));
+///
+/// let (n, r) = (notifier.clone(), ready.clone());
+/// let handle = thread::spawn(move || {
+///     r.store(true, Ordering::SeqCst);
+///     n.notify();
+/// });
+///
+/// while !ready.load(Ordering::SeqCst) {
+///     notifier.wait_if(|| !ready.load(Ordering::SeqCst));
+/// }
+///
+/// handle.join().unwrap();
+/// ```
+pub struct Notifier {
+    waiters: AtomicUsize,
+    lock: Mutex<()>,
+    cvar: Condvar,
+    #[cfg(feature = "async")]
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl Notifier {
+    /// Create a new Notifier.
+    pub fn new() -> Self {
+        Self {
+            waiters: AtomicUsize::new(0),
+            lock: Mutex::new(()),
+            cvar: Condvar::new(),
+            #[cfg(feature = "async")]
+            wakers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Block the current thread until the next notification, if `cond` returns true.
+    ///
+    /// The condition is checked after registering as a waiter, so a notification sent after the
+    /// condition became false cannot be missed.
+    ///
+    /// # Arguments
+    /// * `cond` - Returns true if the thread should wait.
+    pub fn wait_if<F: FnOnce() -> bool>(&self, cond: F) {
+        self.park(cond, None);
+    }
+
+    /// Block the current thread until the next notification or until the timeout, if `cond` returns true.
+    fn park<F: FnOnce() -> bool>(&self, cond: F, timeout: Option<Duration>) {
+        let guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        // Pairs with the fence in `notify`: either we see the new state, or the notifier sees us.
+        fence(Ordering::SeqCst);
+
+        if cond() {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(?timeout, "parking until notified");
+
+            let _guard = match timeout {
+                Some(timeout) => {
+                    self.cvar
+                        .wait_timeout(guard, timeout)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+                None => self
+                    .cvar
+                    .wait(guard)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+
+            #[cfg(feature = "tracing")]
+            tracing::trace!("woken up");
+        }
+
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Block the current thread for as long as `pred` returns true.
+    ///
+    /// This is the recheck loop around `wait_if`: the predicate is evaluated again after every
+    /// wakeup, so spurious wakeups and notifications for unrelated changes are handled here.
+    ///
+    /// # Arguments
+    /// * `pred` - Returns true while the thread should keep waiting.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// use fremkit::Notifier;
+    ///
+    /// let notifier = Arc::new(Notifier::new());
+    /// let ready = Arc::new(AtomicBool::new(false));
+    ///
+    /// let (n, r) = (notifier.clone(), ready.clone());
+    /// let handle = thread::spawn(move || {
+    ///     r.store(true, Ordering::SeqCst);
+    ///     n.notify();
+    /// });
+    ///
+    /// notifier.wait_while(|| !ready.load(Ordering::SeqCst));
+    /// assert!(ready.load(Ordering::SeqCst));
+    ///
+    /// handle.join().unwrap();
+    /// ```
+    pub fn wait_while<F: FnMut() -> bool>(&self, mut pred: F) {
+        while pred() {
+            self.wait_if(&mut pred);
+        }
+    }
+
+    /// Block the current thread for as long as `pred` returns true, or until the timeout elapses.
+    ///
+    /// # Returns
+    /// `true` if the predicate returned false before the timeout.
+    pub fn wait_while_timeout<F: FnMut() -> bool>(&self, mut pred: F, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+
+        while pred() {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+
+            self.park(&mut pred, Some(deadline - now));
+        }
+
+        true
+    }
+
+    /// Register a task to be woken up by the next notification.
+    ///
+    /// Like `wait_if`, the task must check its condition again after registering, as the state may
+    /// have changed before the waker was registered.
+    ///
+    /// # Arguments
+    /// * `waker` - The waker of the task to wake up.
+    #[cfg(feature = "async")]
+    pub fn register(&self, waker: &Waker) {
+        let mut wakers = self.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+            self.waiters.fetch_add(1, Ordering::SeqCst);
+        }
+        drop(wakers);
+
+        // Pairs with the fence in `notify`: either the task sees the new state, or the notifier sees it.
+        fence(Ordering::SeqCst);
+    }
+
+    /// Are threads or tasks currently waiting on this Notifier ?
+    #[cfg(feature = "stats")]
+    pub(crate) fn has_waiters(&self) -> bool {
+        self.waiters.load(Ordering::Relaxed) > 0
+    }
+
+    /// Wake up all threads and tasks currently waiting on this Notifier.
+    pub fn notify(&self) {
+        fence(Ordering::SeqCst);
+
+        let waiters = self.waiters.load(Ordering::SeqCst);
+        if waiters == 0 {
+            return;
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(waiters, "waking up waiters");
+
+        #[cfg(feature = "async")]
+        {
+            let wakers =
+                std::mem::take(&mut *self.wakers.lock().unwrap_or_else(PoisonError::into_inner));
+            self.waiters.fetch_sub(wakers.len(), Ordering::SeqCst);
+
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+
+        // Taking the lock ensures a waiter that saw the old state is parked before we notify it.
+        drop(self.lock.lock().unwrap_or_else(PoisonError::into_inner));
+        self.cvar.notify_all();
+    }
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Notifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Notifier")
+            .field("waiters", &self.waiters.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// A handle to cancel waits from another thread, for instance to shut consumers down.
+///
+/// Clones share the same state: cancelling one cancels them all.
+///
+/// # Examples
+/// ```
+/// use std::thread;
+///
+/// use fremkit::unbounded::Channel;
+/// use fremkit::{CancelToken, RecvError};
+///
+/// let channel: Channel<u64> = Channel::new();
+/// let token = CancelToken::new();
+///
+/// thread::scope(|s| {
+///     let consumer = s.spawn(|| channel.wait_for_cancellable(0, &token));
+///
+///     token.cancel();
+///     assert_eq!(consumer.join().unwrap(), Err(RecvError::Cancelled));
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Create a new token, not cancelled.
+    pub fn new() -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Cancel the waits using this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Has the token been cancelled ?
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A set of Notifiers, each covering a subset of keys.
+///
+/// Waiters for a key only get woken up by notifications for keys sharing their shard, instead of every
+/// notification. With many waiters on different keys, this avoids waking all of them on each notify.
+pub(crate) struct ShardedNotifier {
+    shards: Box<[Notifier]>,
+}
+
+impl ShardedNotifier {
+    /// Create a new ShardedNotifier with the given number of shards, at least 1.
+    pub(crate) fn new(shards: usize) -> Self {
+        Self {
+            shards: (0..shards.max(1)).map(|_| Notifier::new()).collect(),
+        }
+    }
+
+    /// Get the Notifier responsible for a key.
+    #[inline]
+    fn shard(&self, key: usize) -> &Notifier {
+        &self.shards[key % self.shards.len()]
+    }
+
+    /// Block the current thread for as long as `pred` returns true, waking up on notifications for
+    /// `key`'s shard.
+    pub(crate) fn wait_while<F: FnMut() -> bool>(&self, key: usize, pred: F) {
+        self.shard(key).wait_while(pred)
+    }
+
+    /// Like `wait_while`, but gives up once the timeout elapses.
+    ///
+    /// # Returns
+    /// `true` if the predicate returned false before the timeout.
+    pub(crate) fn wait_while_timeout<F: FnMut() -> bool>(
+        &self,
+        key: usize,
+        pred: F,
+        timeout: Duration,
+    ) -> bool {
+        self.shard(key).wait_while_timeout(pred, timeout)
+    }
+
+    /// Register a task to be woken up by the next notification for `key`'s shard.
+    #[cfg(feature = "async")]
+    pub(crate) fn register(&self, key: usize, waker: &Waker) {
+        self.shard(key).register(waker)
+    }
+
+    /// Are threads or tasks currently waiting on `key`'s shard ?
+    #[cfg(feature = "stats")]
+    pub(crate) fn has_waiters(&self, key: usize) -> bool {
+        self.shard(key).has_waiters()
+    }
+
+    /// Wake up all threads and tasks waiting on `key`'s shard.
+    #[inline]
+    pub(crate) fn notify(&self, key: usize) {
+        self.shard(key).notify()
+    }
+
+    /// Wake up all threads and tasks waiting on any shard.
+    pub(crate) fn notify_all(&self) {
+        for shard in self.shards.iter() {
+            shard.notify();
+        }
+    }
+}
+
+impl fmt::Debug for ShardedNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedNotifier")
+            .field("shards", &self.shards.len())
+            .finish()
+    }
+}
+
+/// Notifiers woken up on every push on a Log or Channel, on top of its own waiters.
+///
+/// A `WaitSet` registers its Notifier with each source it watches. Pushing is only slowed down by
+/// an atomic load while no Notifier is registered.
+pub struct Listeners {
+    count: AtomicUsize,
+    list: Mutex<Vec<Arc<Notifier>>>,
+}
+
+impl Listeners {
+    pub(crate) fn new() -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+            list: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Register a Notifier, to be woken up by every notification.
+    pub(crate) fn add(&self, notifier: Arc<Notifier>) {
+        let mut list = self.list.lock().unwrap_or_else(PoisonError::into_inner);
+
+        list.push(notifier);
+        self.count.store(list.len(), Ordering::SeqCst);
+    }
+
+    /// Unregister a Notifier, once for every time it was registered.
+    pub(crate) fn remove(&self, notifier: &Arc<Notifier>) {
+        let mut list = self.list.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(position) = list.iter().position(|n| Arc::ptr_eq(n, notifier)) {
+            list.swap_remove(position);
+        }
+        self.count.store(list.len(), Ordering::SeqCst);
+    }
+
+    /// Wake up every registered Notifier.
+    #[inline]
+    pub(crate) fn notify(&self) {
+        // Pairs with the fence of waiters registering on a Notifier, like `Notifier::notify`.
+        fence(Ordering::SeqCst);
+
+        if self.count.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+
+        for notifier in self
+            .list
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            notifier.notify();
+        }
+    }
+}
+
+impl fmt::Debug for Listeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Listeners")
+            .field("count", &self.count.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::sync::{thread, AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    #[cfg(loom)]
+    fn test_loom() {
+        loom::model(test_notifier_wakeup);
+        loom::model(test_sharded_notifier_wakeup);
+    }
+
+    #[test]
+    fn test_notifier_wakeup() {
+        init();
+
+        let notifier = Arc::new(Notifier::new());
+        let value = Arc::new(AtomicUsize::new(0));
+
+        let (n, v) = (notifier.clone(), value.clone());
+        let h1 = thread::spawn(move || {
+            v.store(1, Ordering::SeqCst);
+            n.notify();
+        });
+
+        while value.load(Ordering::SeqCst) == 0 {
+            notifier.wait_if(|| value.load(Ordering::SeqCst) == 0);
+        }
+
+        h1.join().unwrap();
+    }
+
+    #[test]
+    fn test_sharded_notifier_wakeup() {
+        init();
+
+        let notifier = Arc::new(ShardedNotifier::new(2));
+        let value = Arc::new(AtomicUsize::new(0));
+
+        let (n, v) = (notifier.clone(), value.clone());
+        let h1 = thread::spawn(move || {
+            v.store(3, Ordering::SeqCst);
+            n.notify(3);
+        });
+
+        notifier.wait_while(1, || value.load(Ordering::SeqCst) == 0);
+
+        assert_eq!(value.load(Ordering::SeqCst), 3);
+
+        h1.join().unwrap();
+    }
+}
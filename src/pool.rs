@@ -0,0 +1,396 @@
+//! This module contains the implementation of the `Pool` type.
+
+use crate::sync::{AtomicU64, AtomicUsize, Ordering};
+
+#[cfg(not(feature = "safe-impl"))]
+use std::cell::UnsafeCell;
+use std::fmt;
+
+use cache_padded::CachePadded;
+#[cfg(feature = "safe-impl")]
+use parking_lot::Mutex;
+
+/// A bounded pool of reusable items.
+///
+/// Pushing large payloads on a Log at a high rate allocates a fresh buffer for every item.
+/// A Pool lets consumers give buffers back once they are done with them, so producers can reuse them
+/// instead of churning the allocator.
+///
+/// Items are kept in a fixed array of cells. The indices of the cells holding an item, and of the
+/// empty ones, are kept in two lock-free stacks. Taking an item pops a full cell and pushes it back
+/// as empty, giving one back does the opposite. Only the thread which popped the index of a cell
+/// accesses it, so cells need no lock.
+///
+/// # Examples
+/// ```
+/// use fremkit::Pool;
+///
+/// let pool: Pool<Vec<u8>> = Pool::with(2, || Vec::with_capacity(1024));
+///
+/// let mut buf = pool.take_or_else(Vec::new);
+/// buf.extend_from_slice(b"hello");
+///
+/// buf.clear();
+/// pool.give(buf).unwrap();
+///
+/// assert_eq!(pool.len(), 2);
+/// ```
+pub struct Pool<T> {
+    cells: Box<[Cell<T>]>,
+    /// Top of the stack below each cell, as in a stack head.
+    next: Box<[AtomicUsize]>,
+    /// Bits of a stack head holding the top of the stack, as its index plus one. 0 is an empty stack.
+    mask: u64,
+    /// Increment of the tag held by the upper bits of a stack head, bumped on every push and pop so
+    /// a head popped and pushed back in between is not mistaken for an unchanged one.
+    tag: u64,
+    full: CachePadded<AtomicU64>,
+    empty: CachePadded<AtomicU64>,
+    len: CachePadded<AtomicUsize>,
+}
+
+impl<T> Pool<T> {
+    /// Create a new empty Pool. It will hold at most `capacity` items.
+    pub fn new(capacity: usize) -> Self {
+        Self::filled(capacity, || None)
+    }
+
+    /// Create a new Pool filled with `capacity` items.
+    ///
+    /// # Arguments
+    /// * `capacity` - The maximum number of items held by the pool.
+    /// * `f` - The function creating each item.
+    pub fn with<F: FnMut() -> T>(capacity: usize, mut f: F) -> Self {
+        Self::filled(capacity, || Some(f()))
+    }
+
+    /// Create a Pool with every cell holding the result of `f`.
+    fn filled<F: FnMut() -> Option<T>>(capacity: usize, mut f: F) -> Self {
+        let mut len = 0;
+        let cells = (0..capacity)
+            .map(|_| {
+                let item = f();
+                len += usize::from(item.is_some());
+                Cell::new(item)
+            })
+            .collect();
+
+        // The top of a stack takes as many bits as the capacity, the tag takes the rest.
+        let bits = u64::BITS - (capacity as u64).leading_zeros();
+        let tag = 1u64.checked_shl(bits).unwrap_or(0);
+
+        // Every cell is stacked in index order, on the stack matching what it holds.
+        let next = (0..capacity)
+            .map(|index| AtomicUsize::new(if index + 1 < capacity { index + 2 } else { 0 }))
+            .collect();
+        let (full, empty) = match (capacity, len) {
+            (0, _) => (0, 0),
+            (_, 0) => (0, 1),
+            _ => (1, 0),
+        };
+
+        Self {
+            cells,
+            next,
+            mask: tag.wrapping_sub(1),
+            tag,
+            full: CachePadded::new(AtomicU64::new(full)),
+            empty: CachePadded::new(AtomicU64::new(empty)),
+            len: CachePadded::new(AtomicUsize::new(len)),
+        }
+    }
+
+    /// Get the number of items currently available in the pool.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// Is the pool empty ?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the capacity of the pool.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Take an item from the pool.
+    ///
+    /// # Returns
+    /// An item, or `None` if the pool is empty.
+    pub fn take(&self) -> Option<T> {
+        let index = self.pop(&self.full)?;
+        self.len.fetch_sub(1, Ordering::Relaxed);
+
+        let item = self.cells[index].replace(None);
+        self.push(&self.empty, index);
+
+        item
+    }
+
+    /// Take an item from the pool, or create a new one if the pool is empty.
+    pub fn take_or_else<F: FnOnce() -> T>(&self, f: F) -> T {
+        self.take().unwrap_or_else(f)
+    }
+
+    /// Give an item back to the pool.
+    ///
+    /// # Returns
+    /// An error containing the item if the pool is full.
+    pub fn give(&self, item: T) -> Result<(), T> {
+        let Some(index) = self.pop(&self.empty) else {
+            return Err(item);
+        };
+
+        self.cells[index].replace(Some(item));
+
+        self.len.fetch_add(1, Ordering::Relaxed);
+        self.push(&self.full, index);
+
+        Ok(())
+    }
+
+    /// Pop the index of a cell from a stack.
+    fn pop(&self, head: &AtomicU64) -> Option<usize> {
+        let mut current = head.load(Ordering::Acquire);
+
+        loop {
+            let index = ((current & self.mask) as usize).checked_sub(1)?;
+
+            // The index may be popped and moved to the other stack meanwhile: the tag of the head
+            // changes, and the stale next top is discarded by the failed exchange.
+            let next = self.next[index].load(Ordering::Relaxed) as u64;
+            let new = (current & !self.mask).wrapping_add(self.tag) | next;
+
+            match head.compare_exchange_weak(current, new, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return Some(index),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Push the index of a cell on a stack.
+    fn push(&self, head: &AtomicU64, index: usize) {
+        let mut current = head.load(Ordering::Relaxed);
+
+        loop {
+            self.next[index].store((current & self.mask) as usize, Ordering::Relaxed);
+            let new = (current & !self.mask).wrapping_add(self.tag) | (index as u64 + 1);
+
+            match head.compare_exchange_weak(current, new, Ordering::Release, Ordering::Relaxed) {
+                Ok(_) => return,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for Pool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("capacity", &self.capacity())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// A cell of a Pool, holding an item or not.
+///
+/// A cell is only accessed by the thread which popped its index from one of the stacks of the pool,
+/// and the exchange popping it synchronizes with the one which pushed it. By default, it is a bare
+/// `UnsafeCell`. With the `safe-impl` feature, it is an uncontended lock.
+struct Cell<T> {
+    #[cfg(not(feature = "safe-impl"))]
+    item: UnsafeCell<Option<T>>,
+    #[cfg(feature = "safe-impl")]
+    item: Mutex<Option<T>>,
+}
+
+impl<T> Cell<T> {
+    fn new(item: Option<T>) -> Self {
+        Self {
+            #[cfg(not(feature = "safe-impl"))]
+            item: UnsafeCell::new(item),
+            #[cfg(feature = "safe-impl")]
+            item: Mutex::new(item),
+        }
+    }
+
+    /// Replace the item held by the cell, returning the previous one.
+    #[cfg(not(feature = "safe-impl"))]
+    #[inline]
+    fn replace(&self, item: Option<T>) -> Option<T> {
+        // SAFETY: The caller popped the index of the cell from a stack, so it is the only one
+        // accessing it until it pushes the index back.
+        unsafe { std::mem::replace(&mut *self.item.get(), item) }
+    }
+
+    /// Replace the item held by the cell, returning the previous one.
+    #[cfg(feature = "safe-impl")]
+    #[inline]
+    fn replace(&self, item: Option<T>) -> Option<T> {
+        std::mem::replace(&mut *self.item.lock(), item)
+    }
+}
+
+#[cfg(not(feature = "safe-impl"))]
+unsafe impl<T: Send> Sync for Cell<T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sync::thread;
+
+    use std::sync::Arc;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_pool_recycle() {
+        init();
+
+        let pool = Pool::with(1, || vec![0u8; 16]);
+
+        let buf = pool.take().unwrap();
+
+        assert!(pool.take().is_none());
+        assert_eq!(pool.take_or_else(Vec::new).capacity(), 0);
+
+        pool.give(buf).unwrap();
+
+        assert!(pool.give(Vec::new()).is_err());
+        assert_eq!(pool.take().map(|b| b.len()), Some(16));
+    }
+
+    #[test]
+    fn test_pool_new() {
+        init();
+
+        let pool = Pool::new(2);
+
+        assert!(pool.is_empty());
+        assert!(pool.take().is_none());
+
+        pool.give(1).unwrap();
+        pool.give(2).unwrap();
+
+        assert_eq!(pool.give(3), Err(3));
+        assert_eq!(pool.len(), 2);
+
+        // The last item given back is the first one taken.
+        assert_eq!(pool.take(), Some(2));
+        assert_eq!(pool.take(), Some(1));
+        assert_eq!(pool.take(), None);
+
+        let pool: Pool<u8> = Pool::new(0);
+
+        assert_eq!(pool.give(1), Err(1));
+        assert_eq!(pool.take(), None);
+    }
+
+    #[test]
+    #[cfg(loom)]
+    fn test_loom() {
+        loom::model(test_pool_swap);
+        loom::model(test_pool_recycled);
+    }
+
+    #[test]
+    fn test_pool_swap() {
+        init();
+
+        let pool = Arc::new(Pool::new(2));
+        pool.give(0).unwrap();
+
+        // One thread moves the item out and back in while the other gives a second one: neither is
+        // lost nor duplicated.
+        let mover = {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                if let Some(item) = pool.take() {
+                    pool.give(item).unwrap();
+                }
+            })
+        };
+
+        pool.give(1).unwrap();
+        mover.join().unwrap();
+
+        let mut items = vec![pool.take().unwrap(), pool.take().unwrap()];
+        items.sort_unstable();
+
+        assert_eq!(items, vec![0, 1]);
+        assert_eq!(pool.take(), None);
+    }
+
+    #[test]
+    fn test_pool_recycled() {
+        init();
+
+        let pool = Arc::new(Pool::new(3));
+        for i in 0..3 {
+            pool.give(i).unwrap();
+        }
+
+        // While one thread pops a cell, the other moves the top cells to the empty stack and back in
+        // another order: the first one may read the next top of a cell after it has been recycled.
+        let recycler = {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                let first = pool.take().unwrap();
+                let second = pool.take().unwrap();
+                pool.give(first).unwrap();
+                pool.give(second).unwrap();
+            })
+        };
+
+        // At most two items are held by the recycler, so one is always available.
+        let item = pool.take().unwrap();
+        recycler.join().unwrap();
+        pool.give(item).unwrap();
+
+        let mut items: Vec<_> = std::iter::from_fn(|| pool.take()).collect();
+        items.sort_unstable();
+
+        assert_eq!(items, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_pool_concurrent() {
+        init();
+
+        let pool = Arc::new(Pool::new(8));
+        for i in 0..8 {
+            pool.give(i).unwrap();
+        }
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let pool = pool.clone();
+                thread::spawn(move || {
+                    for _ in 0..10_000 {
+                        if let Some(item) = pool.take() {
+                            pool.give(item).unwrap();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // Every item is still in the pool, exactly once.
+        let mut items: Vec<_> = std::iter::from_fn(|| pool.take()).collect();
+        items.sort_unstable();
+
+        assert_eq!(items, (0..8).collect::<Vec<_>>());
+    }
+}
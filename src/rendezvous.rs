@@ -0,0 +1,223 @@
+//! This module contains the implementation of the `Rendezvous` type.
+
+use crate::unbounded::Channel;
+use crate::{LogError, Notifier};
+
+use std::fmt;
+use std::time::Duration;
+
+/// A pair of Channels matching requests with their responses.
+///
+/// A request is identified by its index in the request channel. Responders read requests, for
+/// instance through a work queue, and push their response along with the id of the request. The
+/// requester waits for the response carrying its id.
+///
+/// # Examples
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// use fremkit::Rendezvous;
+///
+/// let rendezvous: Rendezvous<u64, u64> = Rendezvous::new();
+///
+/// thread::scope(|s| {
+///     s.spawn(|| {
+///         let (id, request) = rendezvous.requests().work_queue().recv().unwrap();
+///         rendezvous.respond(id, request * 2).unwrap();
+///     });
+///
+///     assert_eq!(rendezvous.call(21, Duration::from_secs(10)), Some(&42));
+/// });
+/// ```
+pub struct Rendezvous<Req, Resp> {
+    requests: Channel<Req>,
+    responses: Channel<(usize, Resp)>,
+    notifier: Notifier,
+}
+
+impl<Req, Resp> Rendezvous<Req, Resp> {
+    /// Create a new Rendezvous.
+    pub fn new() -> Self {
+        Self {
+            requests: Channel::new(),
+            responses: Channel::new(),
+            notifier: Notifier::new(),
+        }
+    }
+
+    /// Get the Channel of requests.
+    #[inline]
+    pub fn requests(&self) -> &Channel<Req> {
+        &self.requests
+    }
+
+    /// Get the Channel of responses, along with the id of the request they answer.
+    #[inline]
+    pub fn responses(&self) -> &Channel<(usize, Resp)> {
+        &self.responses
+    }
+
+    /// Push a request.
+    ///
+    /// # Returns
+    /// A handle to wait for the response, or an error containing the request if the request channel
+    /// rejected it or is closed.
+    pub fn request(&self, request: Req) -> Result<Pending<'_, Req, Resp>, LogError<Req>> {
+        // A response cannot be pushed before its request.
+        let idx = self.responses.len();
+
+        Ok(Pending {
+            id: self.requests.push(request)?,
+            idx,
+            rendezvous: self,
+        })
+    }
+
+    /// Push the response to a request.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the request, its index in the request channel.
+    /// * `response` - The response.
+    ///
+    /// # Returns
+    /// An error containing the response if the response channel rejected it or is closed.
+    pub fn respond(&self, id: usize, response: Resp) -> Result<(), LogError<Resp>> {
+        self.responses
+            .push((id, response))
+            .map_err(|err| err.map(|(_, response)| response))?;
+        self.notifier.notify();
+
+        Ok(())
+    }
+
+    /// Push a request, and wait for its response.
+    ///
+    /// # Returns
+    /// The response, or `None` if the request could not be pushed or the response did not arrive
+    /// before the timeout.
+    pub fn call(&self, request: Req, timeout: Duration) -> Option<&Resp> {
+        self.request(request).ok()?.wait(timeout)
+    }
+}
+
+impl<Req, Resp> Default for Rendezvous<Req, Resp> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Req: fmt::Debug, Resp: fmt::Debug> fmt::Debug for Rendezvous<Req, Resp> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rendezvous")
+            .field("requests", &self.requests)
+            .field("responses", &self.responses)
+            .finish()
+    }
+}
+
+/// A request waiting for its response.
+#[derive(Debug)]
+pub struct Pending<'a, Req, Resp> {
+    id: usize,
+    idx: usize,
+    rendezvous: &'a Rendezvous<Req, Resp>,
+}
+
+impl<'a, Req, Resp> Pending<'a, Req, Resp> {
+    /// Get the id of the request.
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Get the response, if it has arrived.
+    pub fn try_recv(&mut self) -> Option<&'a Resp> {
+        while let Some((id, response)) = self.rendezvous.responses.get(self.idx) {
+            self.idx += 1;
+
+            if *id == self.id {
+                return Some(response);
+            }
+        }
+
+        None
+    }
+
+    /// Wait for the response.
+    ///
+    /// # Returns
+    /// The response, or `None` if it did not arrive before the timeout.
+    pub fn wait(mut self, timeout: Duration) -> Option<&'a Resp> {
+        let mut response = None;
+
+        // The predicate is checked again after it found the response: keep it instead of
+        // overwriting it with the result of the next check.
+        self.rendezvous.notifier.wait_while_timeout(
+            || {
+                response = response.or_else(|| self.try_recv());
+                response.is_none()
+            },
+            timeout,
+        );
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_rendezvous_correlation() {
+        init();
+
+        let rendezvous: Rendezvous<usize, usize> = Rendezvous::new();
+
+        let queue = rendezvous.requests().work_queue();
+
+        thread::scope(|s| {
+            for _ in 0..2 {
+                let (rendezvous, queue) = (&rendezvous, &queue);
+                s.spawn(move || {
+                    for _ in 0..50 {
+                        let (id, request) = queue.recv().unwrap();
+                        rendezvous.respond(id, request + 1).unwrap();
+                    }
+                });
+            }
+
+            for t in 0..4 {
+                let rendezvous = &rendezvous;
+                s.spawn(move || {
+                    for i in 0..25 {
+                        let request = t * 100 + i;
+                        let response = rendezvous.call(request, Duration::from_secs(10));
+
+                        assert_eq!(response, Some(&(request + 1)));
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_rendezvous_timeout() {
+        init();
+
+        let rendezvous: Rendezvous<u8, u8> = Rendezvous::new();
+
+        let mut pending = rendezvous.request(1).unwrap();
+        rendezvous.respond(pending.id() + 1, 2).unwrap();
+
+        assert_eq!(pending.try_recv(), None);
+        assert_eq!(pending.wait(Duration::from_millis(10)), None);
+    }
+}
@@ -0,0 +1,242 @@
+//! This module contains the implementation of the `Trace` type, a record-and-replay test harness.
+
+use crate::bounded::Log;
+use crate::unbounded::Channel;
+use crate::LogError;
+
+use std::fmt;
+
+use parking_lot::Mutex;
+
+/// An append-only sequence which can be recorded and replayed by a `Trace`.
+pub trait Append<T> {
+    /// Append an item, returning its index.
+    fn append(&self, value: T) -> Result<usize, LogError<T>>;
+
+    /// Get the item at an index, if it is available.
+    fn lookup(&self, index: usize) -> Option<&T>;
+}
+
+impl<T> Append<T> for Log<T> {
+    fn append(&self, value: T) -> Result<usize, LogError<T>> {
+        self.push(value)
+    }
+
+    fn lookup(&self, index: usize) -> Option<&T> {
+        self.get(index)
+    }
+}
+
+impl<T> Append<T> for Channel<T> {
+    fn append(&self, value: T) -> Result<usize, LogError<T>> {
+        self.push(value)
+    }
+
+    fn lookup(&self, index: usize) -> Option<&T> {
+        self.get(index)
+    }
+}
+
+/// A record of every push made on a Log or Channel, and of the producer which made it.
+///
+/// Producers push through `record` instead of pushing on the target directly. The trace can then be
+/// replayed into a fresh target, in the exact interleaving it was recorded in, and the two targets
+/// compared with `verify`.
+///
+/// # Examples
+/// ```
+/// use std::thread;
+///
+/// use fremkit::bounded::Log;
+/// use fremkit::Trace;
+///
+/// let log: Log<u64> = Log::new(100);
+/// let trace = Trace::new();
+///
+/// thread::scope(|s| {
+///     for producer in 0..4 {
+///         let (log, trace) = (&log, &trace);
+///         s.spawn(move || {
+///             for i in 0..10 {
+///                 trace.record(log, producer, i).unwrap();
+///             }
+///         });
+///     }
+/// });
+///
+/// let replayed: Log<u64> = Log::new(100);
+/// trace.replay(&replayed).unwrap();
+///
+/// trace.verify(&log);
+/// trace.verify(&replayed);
+/// ```
+pub struct Trace<T> {
+    events: Mutex<Vec<Event<T>>>,
+}
+
+/// A single recorded push.
+struct Event<T> {
+    index: usize,
+    producer: usize,
+    value: T,
+}
+
+impl<T: Clone> Trace<T> {
+    /// Create a new empty Trace.
+    pub fn new() -> Self {
+        Self {
+            events: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Push an item on the target, and record the push.
+    ///
+    /// # Arguments
+    /// * `target` - The Log or Channel to push on.
+    /// * `producer` - The id of the producer making the push.
+    /// * `value` - The item to push.
+    pub fn record<A: Append<T>>(
+        &self,
+        target: &A,
+        producer: usize,
+        value: T,
+    ) -> Result<usize, LogError<T>> {
+        let copy = value.clone();
+        let index = target.append(value)?;
+
+        self.events.lock().push(Event {
+            index,
+            producer,
+            value: copy,
+        });
+
+        Ok(index)
+    }
+
+    /// Get the recorded pushes, as `(index, producer)` pairs in index order.
+    pub fn events(&self) -> Vec<(usize, usize)> {
+        let mut events: Vec<_> = self
+            .events
+            .lock()
+            .iter()
+            .map(|event| (event.index, event.producer))
+            .collect();
+
+        events.sort_unstable();
+        events
+    }
+
+    /// Replay the recorded pushes into an empty target, in the order they were committed.
+    ///
+    /// # Panics
+    /// If the target does not return the recorded indices, meaning it was not empty.
+    pub fn replay<A: Append<T>>(&self, target: &A) -> Result<(), LogError<T>> {
+        let mut events: Vec<_> = self
+            .events
+            .lock()
+            .iter()
+            .map(|event| (event.index, event.value.clone()))
+            .collect();
+
+        events.sort_unstable_by_key(|(index, _)| *index);
+
+        for (index, value) in events {
+            let replayed = target.append(value)?;
+
+            assert_eq!(
+                replayed, index,
+                "fremkit: replay diverged at index {}",
+                index
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Check that the target holds every recorded item, at its recorded index.
+    ///
+    /// # Panics
+    /// On the first recorded item which is missing or different in the target.
+    pub fn verify<A: Append<T>>(&self, target: &A)
+    where
+        T: PartialEq + fmt::Debug,
+    {
+        for event in self.events.lock().iter() {
+            assert_eq!(
+                target.lookup(event.index),
+                Some(&event.value),
+                "fremkit: state diverged at index {} (producer {})",
+                event.index,
+                event.producer
+            );
+        }
+    }
+}
+
+impl<T: Clone> Default for Trace<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Trace<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Trace")
+            .field("events", &self.events.lock().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_trace_replay_channel() {
+        init();
+
+        let channel = Channel::with_segment_capacity(4);
+        let trace = Trace::new();
+
+        thread::scope(|s| {
+            for producer in 0..3 {
+                let (channel, trace) = (&channel, &trace);
+                s.spawn(move || {
+                    for i in 0..20 {
+                        trace.record(channel, producer, (producer, i)).unwrap();
+                    }
+                });
+            }
+        });
+
+        let replayed = Channel::with_segment_capacity(7);
+        trace.replay(&replayed).unwrap();
+
+        trace.verify(&replayed);
+        assert_eq!(trace.events().len(), 60);
+        assert_eq!(
+            replayed.iter().collect::<Vec<_>>(),
+            channel.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "state diverged at index 0")]
+    fn test_trace_verify_diverged() {
+        init();
+
+        let trace = Trace::new();
+        trace.record(&Log::new(1), 0, 1).unwrap();
+
+        let other = Log::new(1);
+        other.push(2).unwrap();
+
+        trace.verify(&other);
+    }
+}
@@ -0,0 +1,181 @@
+//! This module contains the implementation of the `Router` type.
+
+use crate::unbounded::Channel;
+use crate::LogError;
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// A set of Channels, with items routed to a partition by hashing their key.
+///
+/// Items sharing a key always land in the same partition, in the order they were pushed. Each
+/// partition can be consumed by its own Subscriber, so ordered processing scales with the number of
+/// partitions instead of being bound to a single consumer.
+///
+/// # Examples
+/// ```
+/// use fremkit::Router;
+///
+/// let router = Router::new(4, |event: &(u32, &str)| event.0);
+///
+/// let (partition, _) = router.push((1, "created")).unwrap();
+/// router.push((1, "updated")).unwrap();
+///
+/// let mut subscriber = router.subscribe(partition);
+///
+/// assert_eq!(subscriber.try_recv(), Some(&(1, "created")));
+/// assert_eq!(subscriber.try_recv(), Some(&(1, "updated")));
+/// assert_eq!(subscriber.try_recv(), None);
+/// ```
+pub struct Router<T, F> {
+    partitions: Vec<Channel<T>>,
+    key: F,
+}
+
+impl<T, K, F> Router<T, F>
+where
+    K: Hash,
+    F: Fn(&T) -> K,
+{
+    /// Create a new Router with `partitions` partitions, at least 1.
+    ///
+    /// # Arguments
+    /// * `partitions` - The number of partitions.
+    /// * `key` - The function extracting the key of an item.
+    pub fn new(partitions: usize, key: F) -> Self {
+        Self {
+            partitions: (0..partitions.max(1)).map(|_| Channel::new()).collect(),
+            key,
+        }
+    }
+
+    /// Get the number of partitions.
+    #[inline]
+    pub fn partitions(&self) -> usize {
+        self.partitions.len()
+    }
+
+    /// Get the partition an item is routed to.
+    pub fn partition_of(&self, item: &T) -> usize {
+        let mut hasher = DefaultHasher::new();
+        (self.key)(item).hash(&mut hasher);
+
+        (hasher.finish() % self.partitions.len() as u64) as usize
+    }
+
+    /// Append an item to its partition.
+    ///
+    /// # Returns
+    /// The partition the item was routed to and its index in that partition, or an error containing
+    /// the item if the partition rejected it or is closed.
+    pub fn push(&self, value: T) -> Result<(usize, usize), LogError<T>> {
+        let partition = self.partition_of(&value);
+
+        Ok((partition, self.partitions[partition].push(value)?))
+    }
+
+    /// Get the Channel backing a partition.
+    ///
+    /// # Panics
+    /// If `partition` is not lower than the number of partitions.
+    pub fn partition(&self, partition: usize) -> &Channel<T> {
+        &self.partitions[partition]
+    }
+
+    /// Create a Subscriber reading a partition from its beginning.
+    ///
+    /// # Panics
+    /// If `partition` is not lower than the number of partitions.
+    pub fn subscribe(&self, partition: usize) -> Subscriber<'_, T> {
+        Subscriber {
+            idx: 0,
+            channel: &self.partitions[partition],
+        }
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for Router<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Router")
+            .field("partitions", &self.partitions)
+            .finish()
+    }
+}
+
+/// Reader following a single partition of a Router.
+#[derive(Debug)]
+pub struct Subscriber<'a, T> {
+    idx: usize,
+    channel: &'a Channel<T>,
+}
+
+impl<'a, T> Subscriber<'a, T> {
+    /// Get the index of the next item to be read.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.idx
+    }
+
+    /// Read the next item of the partition, if it is available.
+    pub fn try_recv(&mut self) -> Option<&'a T> {
+        let item = self.channel.get(self.idx)?;
+        self.idx += 1;
+
+        Some(item)
+    }
+
+    /// Read the next item of the partition, blocking until it becomes available.
+    ///
+    /// # Returns
+    /// The next item, or `None` once the partition is closed and every item has been read.
+    pub fn recv(&mut self) -> Option<&'a T> {
+        let item = self.channel.wait_for(self.idx)?;
+        self.idx += 1;
+
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_router_ordering() {
+        init();
+
+        let router = Router::new(3, |item: &(usize, usize)| item.0);
+
+        thread::scope(|s| {
+            for key in 0..6 {
+                let router = &router;
+                s.spawn(move || {
+                    for seq in 0..100 {
+                        router.push((key, seq)).unwrap();
+                    }
+                });
+            }
+        });
+
+        // Items sharing a key are read in the order they were pushed.
+        for partition in 0..router.partitions() {
+            let mut subscriber = router.subscribe(partition);
+            let mut last = [None; 6];
+
+            while let Some(&(key, seq)) = subscriber.try_recv() {
+                assert!(last[key] < Some(seq));
+                last[key] = Some(seq);
+            }
+        }
+
+        let total: usize = (0..3).map(|p| router.partition(p).len()).sum();
+        assert_eq!(total, 600);
+    }
+}
@@ -0,0 +1,183 @@
+//! This module contains the implementation of the `StartGate` type, releasing threads all at once.
+
+use crate::sync::{AtomicBool, AtomicUsize, Ordering};
+use crate::Notifier;
+
+use std::fmt;
+use std::time::Instant;
+
+/// A gate holding a set of threads on the starting line until it is opened, and then telling them
+/// when to stop.
+///
+/// Unlike a `Barrier`, the thread opening the gate is not one of the parties: `open` waits until
+/// every party is waiting at the gate, and releases them all at once. Once released, the parties
+/// can poll `is_stopped` to end a timed run, which is a single atomic load.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// use fremkit::bounded::Log;
+/// use fremkit::StartGate;
+///
+/// let gate = Arc::new(StartGate::new(4));
+/// let log = Arc::new(Log::new(1_000_000));
+///
+/// let threads: Vec<_> = (0..4)
+///     .map(|_| {
+///         let (gate, log) = (gate.clone(), log.clone());
+///         thread::spawn(move || {
+///             gate.wait();
+///             while !gate.is_stopped() && log.push(0).is_ok() {}
+///         })
+///     })
+///     .collect();
+///
+/// let start = gate.open();
+/// gate.stop();
+///
+/// for thread in threads {
+///     thread.join().unwrap();
+/// }
+/// println!("{} items in {:?}", log.len(), start.elapsed());
+/// ```
+pub struct StartGate {
+    parties: usize,
+    arrived: AtomicUsize,
+    open: AtomicBool,
+    stopped: AtomicBool,
+    notifier: Notifier,
+}
+
+impl StartGate {
+    /// Create a new closed gate, for `parties` threads.
+    pub fn new(parties: usize) -> Self {
+        Self {
+            parties,
+            arrived: AtomicUsize::new(0),
+            open: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            notifier: Notifier::new(),
+        }
+    }
+
+    /// Get the number of threads the gate waits for before opening.
+    #[inline]
+    pub fn parties(&self) -> usize {
+        self.parties
+    }
+
+    /// Get the number of threads waiting at the gate, or released by it.
+    #[inline]
+    pub fn arrived(&self) -> usize {
+        self.arrived.load(Ordering::Acquire)
+    }
+
+    /// Is the gate open ?
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open.load(Ordering::Acquire)
+    }
+
+    /// Should the released threads stop ?
+    #[inline]
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Block the current thread at the gate until it is opened.
+    ///
+    /// Returns immediately if the gate is already open.
+    pub fn wait(&self) {
+        self.arrived.fetch_add(1, Ordering::AcqRel);
+        self.notifier.notify();
+
+        self.notifier.wait_while(|| !self.is_open());
+    }
+
+    /// Block the current thread until every party is waiting at the gate, then release them all.
+    ///
+    /// # Returns
+    /// The instant the gate was opened at, to time the run of the parties.
+    pub fn open(&self) -> Instant {
+        self.notifier
+            .wait_while(|| self.arrived.load(Ordering::Acquire) < self.parties);
+
+        let start = Instant::now();
+        self.open.store(true, Ordering::Release);
+        self.notifier.notify();
+
+        start
+    }
+
+    /// Tell the released threads to stop, see `is_stopped`.
+    #[inline]
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for StartGate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StartGate")
+            .field("parties", &self.parties)
+            .field("arrived", &self.arrived())
+            .field("open", &self.is_open())
+            .field("stopped", &self.is_stopped())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::bounded::Log;
+    use crate::sync::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    #[cfg(loom)]
+    fn test_loom() {
+        loom::model(test_start_gate);
+    }
+
+    #[test]
+    fn test_start_gate() {
+        init();
+
+        let gate = Arc::new(StartGate::new(2));
+        let log = Arc::new(Log::new(2));
+
+        let threads: Vec<_> = (0..2)
+            .map(|i| {
+                let (gate, log) = (gate.clone(), log.clone());
+                thread::spawn(move || {
+                    gate.wait();
+                    assert!(gate.is_open());
+
+                    log.push(i).unwrap();
+                })
+            })
+            .collect();
+
+        // Nothing runs before the gate is opened, which waits for both parties.
+        assert!(log.is_empty());
+        gate.open();
+        assert_eq!(gate.arrived(), 2);
+        gate.stop();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(log.len(), 2);
+        assert!(gate.is_stopped());
+    }
+}
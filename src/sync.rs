@@ -3,13 +3,17 @@
 #[allow(unused_imports)]
 #[cfg(not(loom))]
 pub(crate) use std::{
-    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+    hint::spin_loop,
+    sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering},
+    sync::{Condvar, Mutex},
     thread,
 };
 
 #[allow(unused_imports)]
 #[cfg(loom)]
 pub(crate) use loom::{
-    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+    sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering},
+    sync::{Condvar, Mutex},
     thread,
+    thread::yield_now as spin_loop,
 };
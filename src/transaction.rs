@@ -0,0 +1,207 @@
+//! This module contains the implementation of the `Coordinator` type, for appends spanning several logs.
+
+use crate::replay::Append;
+use crate::sync::{AtomicBool, Ordering};
+use crate::unbounded::Channel;
+use crate::LogError;
+
+use std::fmt;
+
+/// An item appended by a transaction, hidden from readers until the transaction commits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Staged<T> {
+    txn: usize,
+    value: T,
+}
+
+impl<T> Staged<T> {
+    /// Get the id of the transaction which appended the item.
+    #[inline]
+    pub fn txn(&self) -> usize {
+        self.txn
+    }
+}
+
+/// Coordinates appends to several Logs or Channels, so readers see all of them or none.
+///
+/// A transaction appends `Staged` items to any number of targets, then commits. Appending is not
+/// atomic across targets, but readers go through `Coordinator::visible`, which hides the items of a
+/// transaction until its commit marker is set. A transaction dropped without committing is aborted:
+/// its items stay in the targets, and are never visible.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::Log;
+/// use fremkit::unbounded::Channel;
+/// use fremkit::Coordinator;
+///
+/// let coordinator = Coordinator::new();
+/// let orders = Log::new(10);
+/// let audit = Channel::new();
+///
+/// let txn = coordinator.begin();
+/// txn.append(&orders, "order").unwrap();
+/// txn.append(&audit, "order placed").unwrap();
+///
+/// assert_eq!(coordinator.visible(orders.get(0).unwrap()), None);
+///
+/// txn.commit();
+///
+/// assert_eq!(coordinator.visible(orders.get(0).unwrap()), Some(&"order"));
+/// assert_eq!(coordinator.visible(audit.get(0).unwrap()), Some(&"order placed"));
+/// ```
+pub struct Coordinator {
+    /// Commit marker of every transaction, indexed by transaction id.
+    markers: Channel<AtomicBool>,
+}
+
+impl Coordinator {
+    /// Create a new Coordinator.
+    pub fn new() -> Self {
+        Self {
+            markers: Channel::new(),
+        }
+    }
+
+    /// Begin a new transaction.
+    pub fn begin(&self) -> Transaction<'_> {
+        Transaction {
+            id: self.markers.append(AtomicBool::new(false)),
+            coordinator: self,
+        }
+    }
+
+    /// Has a transaction been committed ?
+    pub fn is_committed(&self, txn: usize) -> bool {
+        self.markers
+            .get(txn)
+            .is_some_and(|marker| marker.load(Ordering::Acquire))
+    }
+
+    /// Get a staged item, if its transaction has been committed.
+    pub fn visible<'a, T>(&self, staged: &'a Staged<T>) -> Option<&'a T> {
+        self.is_committed(staged.txn).then_some(&staged.value)
+    }
+}
+
+impl Default for Coordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Coordinator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Coordinator")
+            .field("transactions", &self.markers.len())
+            .finish()
+    }
+}
+
+/// A transaction in progress, returned by `Coordinator::begin`.
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    id: usize,
+    coordinator: &'a Coordinator,
+}
+
+impl<'a> Transaction<'a> {
+    /// Get the id of the transaction.
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Append an item to a target, hidden until the transaction commits.
+    ///
+    /// # Returns
+    /// The index of the item in the target, or an error containing the item if the target is full.
+    pub fn append<T, A: Append<Staged<T>>>(
+        &self,
+        target: &A,
+        value: T,
+    ) -> Result<usize, LogError<T>> {
+        let staged = Staged {
+            txn: self.id,
+            value,
+        };
+
+        target
+            .append(staged)
+            .map_err(|err| err.map(|staged| staged.value))
+    }
+
+    /// Commit the transaction, making all its items visible at once.
+    pub fn commit(self) {
+        // The marker is pushed when the transaction begins, so it is always found.
+        if let Some(marker) = self.coordinator.markers.get(self.id) {
+            marker.store(true, Ordering::Release);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use crate::bounded::Log;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_coordinator_all_or_none() {
+        init();
+
+        let coordinator = Coordinator::new();
+        let left: Channel<Staged<usize>> = Channel::with_segment_capacity(8);
+        let right: Log<Staged<usize>> = Log::new(200);
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 0..100 {
+                    let txn = coordinator.begin();
+                    txn.append(&left, i).unwrap();
+                    txn.append(&right, i).unwrap();
+
+                    // Every other transaction is aborted.
+                    if i % 2 == 0 {
+                        txn.commit();
+                    }
+                }
+            });
+
+            // Once an item is visible on the left, its counterpart is visible on the right.
+            s.spawn(|| {
+                for index in 0..100 {
+                    let staged = left.wait_for(index).unwrap();
+
+                    if let Some(&value) = coordinator.visible(staged) {
+                        let counterpart = right.wait_for(index).unwrap();
+                        assert_eq!(coordinator.visible(counterpart), Some(&value));
+                    }
+                }
+            });
+        });
+
+        let visible: Vec<_> = right
+            .iter()
+            .filter_map(|staged| coordinator.visible(staged))
+            .collect();
+
+        assert_eq!(visible.len(), 50);
+        assert!(visible.iter().all(|&&i| i % 2 == 0));
+
+        // A full target hands the item back.
+        let full: Log<Staged<usize>> = Log::new(1);
+        let txn = coordinator.begin();
+        assert_eq!(txn.append(&full, 1).ok(), Some(0));
+        assert!(matches!(
+            txn.append(&full, 2),
+            Err(LogError::LogCapacityExceeded(2))
+        ));
+    }
+}
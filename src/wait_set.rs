@@ -0,0 +1,336 @@
+//! This module contains the implementation of the `WaitSet` type, waiting on several sources at once.
+
+use crate::bounded::{Log, Storage};
+use crate::notifier::Listeners;
+use crate::unbounded::Channel;
+use crate::Notifier;
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod sealed {
+    use crate::notifier::Listeners;
+
+    pub trait Listened {
+        fn listeners(&self) -> &Listeners;
+    }
+}
+
+/// A Log or a Channel which can be watched by a `WaitSet`.
+pub trait Watch: sealed::Listened {
+    /// Is an item available at this index ?
+    fn is_available(&self, index: usize) -> bool;
+
+    /// Is it certain that no item will ever be available at this index ?
+    fn is_ended(&self, index: usize) -> bool;
+}
+
+impl<T, S: Storage<T>> sealed::Listened for Log<T, S> {
+    fn listeners(&self) -> &Listeners {
+        Log::listeners(self)
+    }
+}
+
+/// A Log ends at its capacity.
+impl<T, S: Storage<T>> Watch for Log<T, S> {
+    fn is_available(&self, index: usize) -> bool {
+        self.get(index).is_some()
+    }
+
+    fn is_ended(&self, index: usize) -> bool {
+        index >= self.capacity()
+    }
+}
+
+impl<T> sealed::Listened for Channel<T> {
+    fn listeners(&self) -> &Listeners {
+        Channel::listeners(self)
+    }
+}
+
+/// A Channel ends once it is closed.
+impl<T> Watch for Channel<T> {
+    fn is_available(&self, index: usize) -> bool {
+        self.get(index).is_some()
+    }
+
+    fn is_ended(&self, index: usize) -> bool {
+        self.is_closed_before(index)
+    }
+}
+
+/// A source watched by a WaitSet, and the index of its next item.
+struct Source<'a> {
+    source: &'a dyn Watch,
+    position: usize,
+}
+
+/// Waits for the next item of any of several Logs and Channels.
+///
+/// Each watched source is identified by a key, and has a position: the index of its next item.
+/// `select` blocks until a source has an item at its position, then reports its key and the index
+/// of the item, and moves its position forward. Sources are checked in turn, so a busy source does
+/// not starve the others.
+///
+/// A source which ended, a full Log or a closed Channel, is reported once with the index past its
+/// end, where no item will ever be available, and is no longer watched.
+///
+/// # Examples
+/// ```
+/// use fremkit::bounded::Log;
+/// use fremkit::unbounded::Channel;
+/// use fremkit::WaitSet;
+///
+/// let log: Log<u64> = Log::new(10);
+/// let channel: Channel<&str> = Channel::new();
+///
+/// let mut set = WaitSet::new();
+/// let from_log = set.watch(&log, 0);
+/// let from_channel = set.watch(&channel, 0);
+///
+/// channel.push("a").unwrap();
+///
+/// let (key, index) = set.select();
+/// assert_eq!(key, from_channel);
+/// assert_eq!(channel.get(index), Some(&"a"));
+///
+/// log.push(1).unwrap();
+///
+/// assert_eq!(set.select(), (from_log, 0));
+/// assert_eq!(set.try_select(), None);
+/// ```
+pub struct WaitSet<'a> {
+    notifier: Arc<Notifier>,
+    sources: Vec<Option<Source<'a>>>,
+    /// Key of the source to check first, so sources are selected in turn.
+    next: usize,
+}
+
+impl<'a> WaitSet<'a> {
+    /// Create a new empty WaitSet.
+    pub fn new() -> Self {
+        Self {
+            notifier: Arc::new(Notifier::new()),
+            sources: Vec::new(),
+            next: 0,
+        }
+    }
+
+    /// Watch a source, starting at the given index.
+    ///
+    /// # Returns
+    /// The key of the source, reported by `select`.
+    pub fn watch<W: Watch + 'a>(&mut self, source: &'a W, from: usize) -> usize {
+        source.listeners().add(self.notifier.clone());
+
+        self.sources.push(Some(Source {
+            source,
+            position: from,
+        }));
+
+        self.sources.len() - 1
+    }
+
+    /// Get the number of sources still watched.
+    pub fn len(&self) -> usize {
+        self.sources.iter().flatten().count()
+    }
+
+    /// Is no source watched anymore ?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the index of the next item of a source, or `None` if it is not watched anymore.
+    pub fn position(&self, key: usize) -> Option<usize> {
+        Some(self.sources.get(key)?.as_ref()?.position)
+    }
+
+    /// Report a source with an item at its position, if any.
+    ///
+    /// # Returns
+    /// The key of the source and the index of the item.
+    pub fn try_select(&mut self) -> Option<(usize, usize)> {
+        let len = self.sources.len();
+
+        for key in (self.next..len).chain(0..self.next) {
+            let Some(watched) = &mut self.sources[key] else {
+                continue;
+            };
+            let index = watched.position;
+
+            if watched.source.is_available(index) {
+                watched.position += 1;
+            } else if watched.source.is_ended(index) {
+                self.unwatch(key);
+            } else {
+                continue;
+            }
+
+            self.next = (key + 1) % len;
+            return Some((key, index));
+        }
+
+        None
+    }
+
+    /// Block until a source has an item at its position.
+    ///
+    /// Note that if no watched source ever gets a new item, this will block forever.
+    ///
+    /// # Returns
+    /// The key of the source and the index of the item.
+    pub fn select(&mut self) -> (usize, usize) {
+        let notifier = self.notifier.clone();
+
+        // `wait_while` only returns once the predicate is false, so this loops once.
+        loop {
+            let mut selected = None;
+
+            // The predicate is checked again after it selected a source: keep it instead of
+            // selecting another one.
+            notifier.wait_while(|| {
+                selected = selected.or_else(|| self.try_select());
+                selected.is_none()
+            });
+
+            if let Some(selected) = selected {
+                return selected;
+            }
+        }
+    }
+
+    /// Block until a source has an item at its position, or the timeout elapses.
+    ///
+    /// # Returns
+    /// The key of the source and the index of the item, or `None` on timeout.
+    pub fn select_timeout(&mut self, timeout: Duration) -> Option<(usize, usize)> {
+        let notifier = self.notifier.clone();
+        let mut selected = None;
+
+        notifier.wait_while_timeout(
+            || {
+                selected = selected.or_else(|| self.try_select());
+                selected.is_none()
+            },
+            timeout,
+        );
+
+        selected
+    }
+
+    /// Stop watching a source.
+    fn unwatch(&mut self, key: usize) {
+        if let Some(watched) = self.sources[key].take() {
+            watched.source.listeners().remove(&self.notifier);
+        }
+    }
+}
+
+impl<'a> Default for WaitSet<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Drop for WaitSet<'a> {
+    fn drop(&mut self) {
+        for key in 0..self.sources.len() {
+            self.unwatch(key);
+        }
+    }
+}
+
+impl<'a> fmt::Debug for WaitSet<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WaitSet")
+            .field(
+                "positions",
+                &self
+                    .sources
+                    .iter()
+                    .map(|watched| watched.as_ref().map(|w| w.position))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_wait_set_select() {
+        init();
+
+        let logs: Vec<Log<usize>> = (0..3).map(|_| Log::new(100)).collect();
+        let channel: Channel<usize> = Channel::with_segment_capacity(8);
+
+        thread::scope(|s| {
+            for (id, log) in logs.iter().enumerate() {
+                s.spawn(move || {
+                    for i in 0..100 {
+                        log.push(id * 1000 + i).unwrap();
+                    }
+                });
+            }
+
+            s.spawn(|| {
+                for i in 0..50 {
+                    channel.push(i).unwrap();
+                }
+                channel.close();
+            });
+
+            let mut set = WaitSet::new();
+            for log in &logs {
+                set.watch(log, 0);
+            }
+            let from_channel = set.watch(&channel, 0);
+
+            // Every item of every source is reported once, then every source ends.
+            let mut seen = [0; 4];
+            while !set.is_empty() {
+                let (key, index) = set.select();
+
+                let item = if key == from_channel {
+                    channel.get(index)
+                } else {
+                    logs[key].get(index)
+                };
+
+                if item.is_some() {
+                    assert_eq!(index, seen[key]);
+                    seen[key] += 1;
+                }
+            }
+
+            assert_eq!(seen, [100, 100, 100, 50]);
+        });
+    }
+
+    #[test]
+    fn test_wait_set_timeout() {
+        init();
+
+        let channel: Channel<u8> = Channel::new();
+
+        let mut set = WaitSet::new();
+        let key = set.watch(&channel, 1);
+
+        channel.push(0).unwrap();
+        assert_eq!(set.select_timeout(Duration::from_millis(10)), None);
+
+        channel.push(1).unwrap();
+        assert_eq!(set.select_timeout(Duration::from_secs(10)), Some((key, 1)));
+        assert_eq!(set.position(key), Some(2));
+    }
+}